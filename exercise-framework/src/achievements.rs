@@ -0,0 +1,173 @@
+//! Achievements defined as data and awarded from progress.
+//!
+//! Definitions are `[[achievement]]` tables in a TOML file (the built-in
+//! ones are in `achievements.toml`), each with a rule over a learner's
+//! progress. Rules are thresholds that only ever become true, so
+//! evaluating again never takes an achievement away or awards it twice.
+
+use crate::progress::{Achievement, AchievementCategory, UserProgress};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::path::Path;
+
+const BUILTIN_ACHIEVEMENTS: &str = include_str!("../achievements.toml");
+
+/// One entry of an achievements file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AchievementDefinition {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub icon: String,
+    pub category: AchievementCategory,
+    pub points: u32,
+    pub rule: AchievementRule,
+}
+
+/// What a learner's progress must show to earn an achievement
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum AchievementRule {
+    ExercisesCompleted { count: u32 },
+    ChaptersCompleted { count: u32 },
+    ChapterCompleted { chapter: u32 },
+    Streak { days: u32 }, // longest streak, so breaking it later keeps the award
+    NoHintSolves { count: u32 },
+    FirstTrySolves { count: u32 }, // completions without a failed test run
+    QuickSolves { count: u32, minutes: u32 },
+    ConceptsMastered {
+        count: u32,
+        #[serde(default = "default_mastery_level")]
+        level: f64,
+        #[serde(default = "default_repetitions")]
+        repetitions: u32, // remembered practices in a row, so one lucky solve is not mastery
+        #[serde(default)]
+        concept: Option<String>, // only this concept counts
+    },
+}
+
+#[derive(Debug, Deserialize)]
+struct AchievementFile {
+    #[serde(default)]
+    achievement: Vec<AchievementDefinition>,
+}
+
+/// The achievements learners can earn
+#[derive(Debug, Clone, Default)]
+pub struct AchievementCatalog {
+    definitions: Vec<AchievementDefinition>,
+}
+
+impl AchievementCatalog {
+    /// The achievements that ship with the framework
+    pub fn builtin() -> Self {
+        Self::parse(BUILTIN_ACHIEVEMENTS).unwrap_or_else(|e| panic!("invalid built-in achievements: {:#}", e))
+    }
+
+    /// Read an achievements file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+        Self::parse(&content).with_context(|| format!("Invalid achievements in {:?}", path))
+    }
+
+    /// Parse an achievements file; ids must be unique
+    pub fn parse(content: &str) -> Result<Self> {
+        let file: AchievementFile = toml::from_str(content)?;
+        let mut ids = HashSet::new();
+        for definition in &file.achievement {
+            if !ids.insert(definition.id.as_str()) {
+                anyhow::bail!("Achievement '{}' is defined twice", definition.id);
+            }
+        }
+        Ok(Self {
+            definitions: file.achievement,
+        })
+    }
+
+    pub fn definitions(&self) -> &[AchievementDefinition] {
+        &self.definitions
+    }
+
+    pub fn len(&self) -> usize {
+        self.definitions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.definitions.is_empty()
+    }
+
+    /// Award every achievement whose rule now holds and that the learner
+    /// does not have yet; returns the new ones
+    pub fn evaluate(&self, progress: &mut UserProgress) -> Vec<Achievement> {
+        // Earlier releases could award the same achievement twice
+        let mut seen = HashSet::new();
+        progress.achievements.retain(|earned| seen.insert(earned.id.clone()));
+
+        let now = chrono::Utc::now();
+        let awarded: Vec<Achievement> = self
+            .definitions
+            .iter()
+            .filter(|definition| !seen.contains(&definition.id))
+            .filter(|definition| definition.rule.is_met(progress))
+            .map(|definition| Achievement {
+                id: definition.id.clone(),
+                title: definition.title.clone(),
+                description: definition.description.clone(),
+                icon: definition.icon.clone(),
+                earned_at: now,
+                category: definition.category.clone(),
+                points: definition.points,
+            })
+            .collect();
+
+        progress.achievements.extend(awarded.iter().cloned());
+        awarded
+    }
+}
+
+impl AchievementRule {
+    pub fn is_met(&self, progress: &UserProgress) -> bool {
+        let solves = |solved: &dyn Fn(&crate::progress::ExerciseCompletion) -> bool| {
+            progress.exercise_history.iter().filter(|c| solved(c)).count() as u32
+        };
+
+        match self {
+            AchievementRule::ExercisesCompleted { count } => progress.exercises_completed >= *count,
+            AchievementRule::ChaptersCompleted { count } => progress.chapters_completed >= *count,
+            AchievementRule::ChapterCompleted { chapter } => progress
+                .chapters
+                .get(chapter)
+                .is_some_and(|ch| ch.completed_at.is_some()),
+            AchievementRule::Streak { days } => progress.longest_streak.max(progress.current_streak) >= *days,
+            AchievementRule::NoHintSolves { count } => solves(&|c| c.hints_used == 0) >= *count,
+            AchievementRule::FirstTrySolves { count } => solves(&|c| c.test_failures == 0) >= *count,
+            // A time of 0 means the client did not measure it
+            AchievementRule::QuickSolves { count, minutes } => {
+                solves(&|c| c.time_taken_minutes > 0 && c.time_taken_minutes <= *minutes) >= *count
+            }
+            AchievementRule::ConceptsMastered { count, level, repetitions, concept } => {
+                let mastered = progress
+                    .chapters
+                    .values()
+                    .flat_map(|ch| ch.concept_mastery.values())
+                    .filter(|m| concept.as_ref().is_none_or(|c| *c == m.concept))
+                    .filter(|m| m.mastery_level >= *level && m.schedule.repetitions >= *repetitions)
+                    .map(|m| m.concept.as_str())
+                    .collect::<HashSet<_>>()
+                    .len() as u32;
+                mastered >= *count
+            }
+        }
+    }
+}
+
+fn default_mastery_level() -> f64 {
+    0.8
+}
+
+fn default_repetitions() -> u32 {
+    2
+}
@@ -0,0 +1,135 @@
+//! Daily activity calendar and the streaks derived from it.
+//!
+//! Activity is counted per day in the learner's own timezone (an IANA name
+//! such as `Europe/Berlin` in their preferences), so practising late in the
+//! evening lands on the day the learner experienced. A streak is a run of
+//! consecutive active days; the current one stays alive until a whole local
+//! day passes without activity.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Timezone of learners who have not set one
+pub const DEFAULT_TIMEZONE: &str = "UTC";
+
+/// Something a learner did that counts towards their streak
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActivityKind {
+    View,
+    Save,
+    TestRun,
+    Completion,
+}
+
+/// What a learner did on one local day
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DayActivity {
+    pub views: u32,
+    pub saves: u32,
+    pub test_runs: u32,
+    pub completions: u32,
+}
+
+impl DayActivity {
+    pub fn total(&self) -> u32 {
+        self.views + self.saves + self.test_runs + self.completions
+    }
+}
+
+/// Activity per local day
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ActivityCalendar {
+    days: BTreeMap<NaiveDate, DayActivity>,
+}
+
+/// Current and longest run of active days
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Streaks {
+    pub current: u32,
+    pub longest: u32,
+}
+
+/// One day of a heatmap, active or not
+#[derive(Debug, Clone, Serialize)]
+pub struct CalendarDay {
+    pub date: NaiveDate,
+    pub count: u32, // all activity that day
+    #[serde(flatten)]
+    pub activity: DayActivity,
+}
+
+/// Parse an IANA timezone name
+pub fn parse_timezone(name: &str) -> Option<Tz> {
+    name.parse().ok()
+}
+
+/// The local date of `at` in `timezone`; unknown names fall back to UTC
+pub fn local_date(at: DateTime<Utc>, timezone: &str) -> NaiveDate {
+    match parse_timezone(timezone) {
+        Some(tz) => at.with_timezone(&tz).date_naive(),
+        None => at.date_naive(),
+    }
+}
+
+impl ActivityCalendar {
+    pub fn is_empty(&self) -> bool {
+        self.days.is_empty()
+    }
+
+    /// Count one activity on a local day
+    pub fn record(&mut self, kind: ActivityKind, date: NaiveDate) {
+        let day = self.days.entry(date).or_default();
+        match kind {
+            ActivityKind::View => day.views += 1,
+            ActivityKind::Save => day.saves += 1,
+            ActivityKind::TestRun => day.test_runs += 1,
+            ActivityKind::Completion => day.completions += 1,
+        }
+    }
+
+    pub fn day(&self, date: NaiveDate) -> DayActivity {
+        self.days.get(&date).copied().unwrap_or_default()
+    }
+
+    /// Streaks as of `today`. Today need not be active yet: a run that
+    /// ended yesterday is still current.
+    pub fn streaks(&self, today: NaiveDate) -> Streaks {
+        let mut streaks = Streaks::default();
+        let mut run = 0;
+        let mut previous: Option<NaiveDate> = None;
+
+        for (&date, activity) in self.days.range(..=today) {
+            if activity.total() == 0 {
+                continue;
+            }
+            run = match previous {
+                Some(prev) if date.pred_opt() == Some(prev) => run + 1,
+                _ => 1,
+            };
+            streaks.longest = streaks.longest.max(run);
+            previous = Some(date);
+        }
+
+        let alive = previous.is_some_and(|last| last == today || last.succ_opt() == Some(today));
+        streaks.current = if alive { run } else { 0 };
+        streaks
+    }
+
+    /// Every day from `from` to `to` inclusive, inactive days included
+    pub fn range(&self, from: NaiveDate, to: NaiveDate) -> Vec<CalendarDay> {
+        from.iter_days()
+            .take_while(|date| *date <= to)
+            .map(|date| {
+                let activity = self.day(date);
+                CalendarDay {
+                    date,
+                    count: activity.total(),
+                    activity,
+                }
+            })
+            .collect()
+    }
+}
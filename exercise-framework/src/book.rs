@@ -0,0 +1,399 @@
+//! The Rust Book, by section id.
+//!
+//! Exercises refer to the book with ids like `"3"` (a chapter) or `"3.1"` (a
+//! section). [`TableOfContents`] maps them to the book's real pages, e.g.
+//! `ch03-01-variables-and-mutability`, using the book's `SUMMARY.md`: the
+//! copy vendored in `book/SUMMARY.md` or one from a local checkout.
+//! [`LocalBook`] serves an installed HTML copy (such as `rustup doc --book`)
+//! for offline classrooms and cuts out the part of a page a `BookSection`
+//! names.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Where the book is published online
+pub const ONLINE_BOOK_URL: &str = "https://doc.rust-lang.org/book/";
+
+/// `src/SUMMARY.md` of the book edition the exercises are written against
+const VENDORED_SUMMARY: &str = include_str!("../book/SUMMARY.md");
+
+/// One page of the book
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BookPage {
+    pub id: String,    // "3.1"
+    pub slug: String,  // "ch03-01-variables-and-mutability"
+    pub title: String,
+}
+
+impl BookPage {
+    pub fn file_name(&self) -> String {
+        format!("{}.html", self.slug)
+    }
+
+    pub fn online_url(&self) -> String {
+        format!("{}{}", ONLINE_BOOK_URL, self.file_name())
+    }
+}
+
+/// The part of a page a `BookSection` refers to
+#[derive(Debug, Clone, Serialize)]
+pub struct BookExcerpt {
+    pub page: BookPage,
+    pub heading: String, // the heading the excerpt starts at
+    pub html: String,
+}
+
+/// The numbered pages of the book, in reading order
+#[derive(Debug, Clone, Default)]
+pub struct TableOfContents {
+    pages: Vec<BookPage>,
+}
+
+impl TableOfContents {
+    /// The vendored table of contents
+    pub fn vendored() -> &'static TableOfContents {
+        static VENDORED: OnceLock<TableOfContents> = OnceLock::new();
+        VENDORED.get_or_init(|| Self::parse(VENDORED_SUMMARY))
+    }
+
+    /// Read a `SUMMARY.md`, or the one in a book checkout (`src/SUMMARY.md`)
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let file = if path.is_dir() {
+            [path.join("SUMMARY.md"), path.join("src").join("SUMMARY.md")]
+                .into_iter()
+                .find(|file| file.is_file())
+                .with_context(|| format!("No SUMMARY.md in {:?}", path))?
+        } else {
+            path.to_path_buf()
+        };
+
+        let summary = std::fs::read_to_string(&file)
+            .with_context(|| format!("Failed to read {:?}", file))?;
+        let contents = Self::parse(&summary);
+        if contents.pages.is_empty() {
+            anyhow::bail!("{:?} lists no numbered chapters", file);
+        }
+        Ok(contents)
+    }
+
+    /// Parse an mdBook `SUMMARY.md`. List items are numbered the way mdBook
+    /// numbers them; unnumbered prefix and suffix chapters are left out.
+    pub fn parse(summary: &str) -> Self {
+        let mut pages = Vec::new();
+        let mut numbers: Vec<u32> = Vec::new();
+        let mut indents: Vec<usize> = Vec::new(); // indentation of each open level
+
+        for line in summary.lines() {
+            let trimmed = line.trim_start();
+            let Some(item) = trimmed.strip_prefix("- ").or_else(|| trimmed.strip_prefix("* ")) else {
+                continue;
+            };
+            let indent = line.len() - trimmed.len();
+
+            while indents.last().is_some_and(|&open| open > indent) {
+                indents.pop();
+                numbers.pop();
+            }
+            if indents.last() == Some(&indent) {
+                if let Some(number) = numbers.last_mut() {
+                    *number += 1;
+                }
+            } else {
+                indents.push(indent);
+                numbers.push(1);
+            }
+
+            // `- [Title](file.md)`; drafts have an empty link but still take a number
+            let Some((title, link)) = item
+                .strip_prefix('[')
+                .and_then(|item| item.rsplit_once("]("))
+            else {
+                continue;
+            };
+            let link = link.trim_end().trim_end_matches(')');
+            let Some(slug) = link.strip_suffix(".md") else {
+                continue;
+            };
+
+            let id: Vec<String> = numbers.iter().map(u32::to_string).collect();
+            pages.push(BookPage {
+                id: id.join("."),
+                slug: slug.rsplit('/').next().unwrap_or(slug).to_string(),
+                title: title.trim().to_string(),
+            });
+        }
+
+        Self { pages }
+    }
+
+    pub fn pages(&self) -> &[BookPage] {
+        &self.pages
+    }
+
+    pub fn get(&self, id: &str) -> Option<&BookPage> {
+        let id = normalize_id(id)?;
+        self.pages.iter().find(|page| page.id == id)
+    }
+
+    pub fn by_slug(&self, slug: &str) -> Option<&BookPage> {
+        self.pages.iter().find(|page| page.slug == slug)
+    }
+
+    /// The page a book URL points at, e.g.
+    /// `https://doc.rust-lang.org/book/ch03-01-variables-and-mutability.html#shadowing`
+    pub fn by_url(&self, url: &str) -> Option<&BookPage> {
+        let path = url.split(['#', '?']).next()?;
+        let file = path.rsplit('/').next()?;
+        self.by_slug(file.strip_suffix(".html")?)
+    }
+
+    /// The page with this title, ignoring case and punctuation
+    pub fn by_title(&self, title: &str) -> Option<&BookPage> {
+        let wanted = normalize_text(title);
+        self.pages.iter().find(|page| normalize_text(&page.title) == wanted)
+    }
+}
+
+/// Page for a section id, from the vendored table of contents
+pub fn section(id: &str) -> Option<BookPage> {
+    TableOfContents::vendored().get(id).cloned()
+}
+
+/// Every section id the book has, in reading order
+pub fn section_ids() -> impl Iterator<Item = &'static str> {
+    TableOfContents::vendored().pages().iter().map(|page| page.id.as_str())
+}
+
+/// `"03.01"` and `"3.1"` name the same section; `"3.0"` is chapter `"3"`
+fn normalize_id(id: &str) -> Option<String> {
+    let mut parts = id.trim().trim_start_matches("ch").split('.');
+    let chapter: u32 = parts.next()?.parse().ok()?;
+    let section: Option<u32> = match parts.next() {
+        Some(section) => Some(section.parse().ok()?),
+        None => None,
+    };
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(match section {
+        Some(section) if section > 0 => format!("{}.{}", chapter, section),
+        _ => chapter.to_string(),
+    })
+}
+
+/// An installed HTML copy of the book
+#[derive(Debug, Clone)]
+pub struct LocalBook {
+    root: PathBuf,
+    pages: HashMap<String, BookPage>, // by section id
+}
+
+impl LocalBook {
+    /// Open a book directory. Its own table of contents takes precedence
+    /// over the vendored one, so other editions of the book still map.
+    pub fn open<P: AsRef<Path>>(root: P) -> Result<Self> {
+        let root = root.as_ref().to_path_buf();
+        if !root.join("index.html").is_file() {
+            anyhow::bail!("{:?} does not contain an HTML book (no index.html)", root);
+        }
+
+        let mut pages: HashMap<String, BookPage> = TableOfContents::vendored()
+            .pages()
+            .iter()
+            .filter(|page| root.join(page.file_name()).is_file())
+            .map(|page| (page.id.clone(), page.clone()))
+            .collect();
+        if let Ok(toc) = std::fs::read_to_string(root.join("toc.html")) {
+            for page in parse_toc(&toc) {
+                if root.join(page.file_name()).is_file() {
+                    pages.insert(page.id.clone(), page);
+                }
+            }
+        }
+
+        Ok(Self { root, pages })
+    }
+
+    /// The book named by `RUST_TOUR_BOOK_DIR`, or else the one installed
+    /// with the active Rust toolchain
+    pub fn locate() -> Option<PathBuf> {
+        if let Ok(dir) = std::env::var("RUST_TOUR_BOOK_DIR") {
+            return Some(PathBuf::from(dir));
+        }
+
+        let output = std::process::Command::new("rustup")
+            .args(["doc", "--book", "--path"])
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        let index = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+        let root = index.parent()?.to_path_buf();
+        root.join("index.html").is_file().then_some(root)
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn page(&self, id: &str) -> Option<&BookPage> {
+        self.pages.get(&normalize_id(id)?)
+    }
+
+    /// A file of the book (page, stylesheet, image) by its path relative to
+    /// the book root; `None` for anything outside it
+    pub fn file(&self, relative: &str) -> Option<PathBuf> {
+        let relative = Path::new(relative);
+        if !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+            return None;
+        }
+        let path = self.root.join(relative);
+        path.is_file().then_some(path)
+    }
+
+    /// The part of a page under the heading titled `title`, or the whole
+    /// page when no heading matches
+    pub fn excerpt(&self, id: &str, title: Option<&str>) -> Result<Option<BookExcerpt>> {
+        let Some(page) = self.page(id) else {
+            return Ok(None);
+        };
+        let path = self.root.join(page.file_name());
+        let html = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read book page {:?}", path))?;
+        let content = main_content(&html);
+        let headings = headings(content);
+
+        let start = title.and_then(|title| {
+            let wanted = normalize_text(title);
+            headings.iter().position(|heading| normalize_text(&heading.text) == wanted)
+        });
+        let (heading, html) = match start {
+            Some(i) => {
+                let level = headings[i].level;
+                let end = headings[i + 1..]
+                    .iter()
+                    .find(|heading| heading.level <= level)
+                    .map(|heading| heading.start)
+                    .unwrap_or(content.len());
+                (headings[i].text.clone(), &content[headings[i].start..end])
+            }
+            None => (page.title.clone(), content),
+        };
+
+        Ok(Some(BookExcerpt {
+            page: page.clone(),
+            heading,
+            html: html.trim().to_string(),
+        }))
+    }
+}
+
+/// Numbered entries of an mdBook `toc.html`:
+/// `<a href="ch03-01-....html"><strong aria-hidden="true">3.1.</strong> Title</a>`
+fn parse_toc(toc: &str) -> Vec<BookPage> {
+    let mut pages = Vec::new();
+    for entry in toc.split("<a href=\"").skip(1) {
+        let Some((href, rest)) = entry.split_once('"') else {
+            continue;
+        };
+        let Some(slug) = href.strip_suffix(".html") else {
+            continue;
+        };
+        let Some((_, rest)) = rest.split_once("<strong aria-hidden=\"true\">") else {
+            continue;
+        };
+        let Some((number, rest)) = rest.split_once("</strong>") else {
+            continue;
+        };
+        let Some(id) = normalize_id(number.trim_end_matches('.')) else {
+            continue;
+        };
+        let title = rest.split("</a>").next().unwrap_or_default();
+        pages.push(BookPage {
+            id,
+            slug: slug.to_string(),
+            title: decode_entities(&strip_tags(title)).trim().to_string(),
+        });
+    }
+    pages
+}
+
+/// The page body mdBook puts in `<main>`, or the whole document
+fn main_content(html: &str) -> &str {
+    let start = html.find("<main>").map(|i| i + "<main>".len());
+    let end = html.rfind("</main>");
+    match (start, end) {
+        (Some(start), Some(end)) if start <= end => &html[start..end],
+        _ => html,
+    }
+}
+
+struct Heading {
+    level: u8,
+    start: usize, // byte offset of the opening tag
+    text: String,
+}
+
+fn headings(html: &str) -> Vec<Heading> {
+    let mut headings = Vec::new();
+    let mut offset = 0;
+    while let Some(found) = html[offset..].find("<h") {
+        let start = offset + found;
+        offset = start + 2;
+        let Some(level) = html[offset..].chars().next().and_then(|c| c.to_digit(10)) else {
+            continue;
+        };
+        if !(1..=6).contains(&level) {
+            continue;
+        }
+        let close = format!("</h{}>", level);
+        let Some(end) = html[start..].find(&close) else {
+            continue;
+        };
+        let text = decode_entities(&strip_tags(&html[start..start + end]));
+        headings.push(Heading {
+            level: level as u8,
+            start,
+            text: text.trim().to_string(),
+        });
+    }
+    headings
+}
+
+fn strip_tags(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Headings are compared ignoring case, punctuation and curly quotes
+fn normalize_text(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric() || c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,692 @@
+use crate::exercise::Exercise;
+use crate::metadata::{
+    find_metadata_file, is_metadata_file, ExerciseMetadata, MetadataError, METADATA_FILE_NAMES,
+};
+use crate::recommendations::PrerequisiteGraph;
+use crate::validation::{
+    ExerciseValidator, IssueCategory, IssueSeverity, ValidationIssue, ValidationResult,
+};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Cross-exercise check that produced a curriculum issue
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CurriculumCheck {
+    InvalidExercise,         // metadata or exercise files fail to load
+    DuplicateId,
+    DuplicateExerciseNumber, // two exercises share a chapter and number
+    DanglingPrerequisite,    // prerequisite names no exercise
+    PrerequisiteCycle,
+    ConceptBeforeIntroduction,
+    DifficultyRegression, // easier than an earlier exercise in the same chapter
+}
+
+impl CurriculumCheck {
+    pub const ALL: [CurriculumCheck; 7] = [
+        CurriculumCheck::InvalidExercise,
+        CurriculumCheck::DuplicateId,
+        CurriculumCheck::DuplicateExerciseNumber,
+        CurriculumCheck::DanglingPrerequisite,
+        CurriculumCheck::PrerequisiteCycle,
+        CurriculumCheck::ConceptBeforeIntroduction,
+        CurriculumCheck::DifficultyRegression,
+    ];
+
+    /// snake_case name, as used in the JSON report
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CurriculumCheck::InvalidExercise => "invalid_exercise",
+            CurriculumCheck::DuplicateId => "duplicate_id",
+            CurriculumCheck::DuplicateExerciseNumber => "duplicate_exercise_number",
+            CurriculumCheck::DanglingPrerequisite => "dangling_prerequisite",
+            CurriculumCheck::PrerequisiteCycle => "prerequisite_cycle",
+            CurriculumCheck::ConceptBeforeIntroduction => "concept_before_introduction",
+            CurriculumCheck::DifficultyRegression => "difficulty_regression",
+        }
+    }
+}
+
+/// A problem spanning one or more exercises
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CurriculumIssue {
+    pub check: CurriculumCheck,
+    pub exercise_ids: Vec<String>,
+    #[serde(flatten)]
+    pub issue: ValidationIssue, // `file` is relative to the exercises directory
+}
+
+/// Everything the curriculum validator found
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CurriculumReport {
+    pub generated_at: DateTime<Utc>,
+    pub exercise_count: usize,
+    pub is_valid: bool, // no errors, in the curriculum or any exercise
+    pub issues: Vec<CurriculumIssue>,
+    pub exercises: Vec<ValidationResult>, // per exercise, in course order
+}
+
+/// Validates every exercise on disk, and the curriculum they form together
+pub struct CurriculumValidator {
+    validator: ExerciseValidator,
+}
+
+impl CurriculumValidator {
+    pub fn new() -> Self {
+        Self {
+            validator: ExerciseValidator::new(),
+        }
+    }
+
+    /// Validate each exercise with `validator`, e.g. one that verifies solutions
+    pub fn with_validator(validator: ExerciseValidator) -> Self {
+        Self { validator }
+    }
+
+    /// Load every exercise under `exercises_root` and check them together
+    pub fn validate<P: AsRef<Path>>(&self, exercises_root: P) -> Result<CurriculumReport> {
+        let exercises_root = exercises_root.as_ref();
+        let mut issues = Vec::new();
+
+        let entries = load_metadata(exercises_root, &mut issues);
+        let (exercises, duplicate_issues) = dedupe(&entries, exercises_root);
+        issues.extend(duplicate_issues);
+
+        let graph = PrerequisiteGraph::new(&exercises);
+        let paths: HashMap<&str, &Path> = entries
+            .iter()
+            .map(|(metadata, path)| (metadata.id.as_str(), path.as_path()))
+            .collect();
+        let metadata_file = |id: &str| {
+            paths
+                .get(id)
+                .map(|path| relative_file(exercises_root, &metadata_path(path)))
+        };
+
+        check_exercise_numbers(&exercises, &metadata_file, &mut issues);
+        check_prerequisites(&graph, &metadata_file, &mut issues);
+        check_concept_introduction(&graph, &metadata_file, &mut issues);
+        check_difficulty_progression(&exercises, &metadata_file, &mut issues);
+        issues.sort_by(|a, b| a.check.cmp(&b.check).then_with(|| a.exercise_ids.cmp(&b.exercise_ids)));
+
+        let mut results = Vec::new();
+        for metadata in graph.exercises() {
+            let Some(path) = paths.get(metadata.id.as_str()) else {
+                continue;
+            };
+            match Exercise::load_from_path(path).and_then(|exercise| self.validator.validate(&exercise)) {
+                Ok(result) => results.push(result),
+                Err(e) => issues.push(curriculum_issue(
+                    CurriculumCheck::InvalidExercise,
+                    IssueSeverity::Error,
+                    vec![metadata.id.clone()],
+                    format!("Exercise {} could not be validated: {:#}", metadata.id, e),
+                    Some(relative_file(exercises_root, path)),
+                    None,
+                )),
+            }
+        }
+
+        let is_valid = !issues.iter().any(|i| is_error(&i.issue))
+            && results.iter().all(|result| result.is_valid);
+
+        Ok(CurriculumReport {
+            generated_at: Utc::now(),
+            exercise_count: exercises.len(),
+            is_valid,
+            issues,
+            exercises: results,
+        })
+    }
+}
+
+impl Default for CurriculumValidator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CurriculumReport {
+    /// Curriculum issues plus every exercise's own issues at this severity
+    pub fn count(&self, severity: IssueSeverity) -> usize {
+        self.issues.iter().filter(|i| i.issue.severity == severity).count()
+            + self
+                .exercises
+                .iter()
+                .flat_map(|result| &result.issues)
+                .filter(|issue| issue.severity == severity)
+                .count()
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Human-readable summary for review comments
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# Curriculum Validation Report\n");
+        let _ = writeln!(
+            out,
+            "{} {} exercises, {} errors, {} warnings (generated {})\n",
+            if self.is_valid { "✅" } else { "❌" },
+            self.exercise_count,
+            self.count(IssueSeverity::Error),
+            self.count(IssueSeverity::Warning),
+            self.generated_at.format("%Y-%m-%d %H:%M UTC"),
+        );
+
+        let _ = writeln!(out, "## Curriculum\n");
+        if self.issues.is_empty() {
+            let _ = writeln!(out, "No cross-exercise issues.\n");
+        } else {
+            let _ = writeln!(out, "| Severity | Check | Exercises | Location | Message |");
+            let _ = writeln!(out, "|---|---|---|---|---|");
+            for issue in &self.issues {
+                let _ = writeln!(
+                    out,
+                    "| {:?} | {} | {} | {} | {} |",
+                    issue.issue.severity,
+                    issue.check.as_str(),
+                    issue.exercise_ids.join(", "),
+                    location(&issue.issue),
+                    markdown_cell(&issue.issue.message),
+                );
+            }
+            out.push('\n');
+        }
+
+        let _ = writeln!(out, "## Exercises\n");
+        let _ = writeln!(out, "| Exercise | Valid | Score | Errors | Warnings |");
+        let _ = writeln!(out, "|---|---|---|---|---|");
+        for result in &self.exercises {
+            let errors = result.issues.iter().filter(|i| is_error(i)).count();
+            let warnings = result
+                .issues
+                .iter()
+                .filter(|i| i.severity == IssueSeverity::Warning)
+                .count();
+            let _ = writeln!(
+                out,
+                "| {} | {} | {:.2} | {} | {} |",
+                result.exercise_id,
+                if result.is_valid { "✅" } else { "❌" },
+                result.score,
+                errors,
+                warnings
+            );
+        }
+
+        for result in self.exercises.iter().filter(|result| !result.issues.is_empty()) {
+            let _ = writeln!(out, "\n### {}\n", result.exercise_id);
+            for issue in &result.issues {
+                let _ = writeln!(
+                    out,
+                    "- **{:?}** {}{}{}",
+                    issue.severity,
+                    match location(issue) {
+                        location if location.is_empty() => String::new(),
+                        location => format!("`{}`: ", location),
+                    },
+                    issue.message,
+                    match &issue.suggestion {
+                        Some(suggestion) => format!(" — {}", suggestion),
+                        None => String::new(),
+                    }
+                );
+            }
+        }
+
+        out
+    }
+
+    /// JUnit XML: one test case per curriculum check and per exercise, failing
+    /// on errors; warnings are attached as system output
+    pub fn to_junit_xml(&self) -> String {
+        let mut curriculum = String::new();
+        let mut curriculum_failures = 0;
+        for check in CurriculumCheck::ALL {
+            let found: Vec<&ValidationIssue> = self
+                .issues
+                .iter()
+                .filter(|issue| issue.check == check)
+                .map(|issue| &issue.issue)
+                .collect();
+            if found.iter().any(|issue| is_error(issue)) {
+                curriculum_failures += 1;
+            }
+            curriculum.push_str(&junit_case("curriculum", check.as_str(), &found));
+        }
+
+        let mut exercises = String::new();
+        let mut exercise_failures = 0;
+        for result in &self.exercises {
+            let found: Vec<&ValidationIssue> = result.issues.iter().collect();
+            if found.iter().any(|issue| is_error(issue)) {
+                exercise_failures += 1;
+            }
+            exercises.push_str(&junit_case("exercises", &result.exercise_id, &found));
+        }
+
+        let tests = CurriculumCheck::ALL.len() + self.exercises.len();
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <testsuites name=\"curriculum\" tests=\"{}\" failures=\"{}\" timestamp=\"{}\">\n\
+             \x20 <testsuite name=\"curriculum\" tests=\"{}\" failures=\"{}\">\n{}  </testsuite>\n\
+             \x20 <testsuite name=\"exercises\" tests=\"{}\" failures=\"{}\">\n{}  </testsuite>\n\
+             </testsuites>\n",
+            tests,
+            curriculum_failures + exercise_failures,
+            self.generated_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            CurriculumCheck::ALL.len(),
+            curriculum_failures,
+            curriculum,
+            self.exercises.len(),
+            exercise_failures,
+            exercises,
+        )
+    }
+}
+
+/// Every loadable exercise's metadata with its exercise directory, in
+/// course order; metadata that fails to load becomes an issue
+fn load_metadata(exercises_root: &Path, issues: &mut Vec<CurriculumIssue>) -> Vec<(ExerciseMetadata, PathBuf)> {
+    let mut entries = Vec::new();
+    let mut seen = HashSet::new();
+    for entry in WalkDir::new(exercises_root)
+        .into_iter()
+        .filter_entry(|entry| entry.file_name() != "target")
+        .filter_map(|e| e.ok())
+        .filter(|e| is_metadata_file(e.path()))
+    {
+        let exercise_path = entry.path().parent().unwrap_or(exercises_root).to_path_buf();
+        if !seen.insert(exercise_path.clone()) {
+            continue;
+        }
+        match ExerciseMetadata::load_from_dir(&exercise_path) {
+            Ok(metadata) => entries.push((metadata, exercise_path)),
+            Err(e) => {
+                let error = e.downcast_ref::<MetadataError>();
+                let file = error.and_then(|error| error.path.as_deref()).unwrap_or(entry.path());
+                let message = error.map_or_else(|| format!("{:#}", e), |error| error.message.clone());
+                let mut issue = curriculum_issue(
+                    CurriculumCheck::InvalidExercise,
+                    IssueSeverity::Error,
+                    Vec::new(),
+                    format!("Invalid metadata: {}", message),
+                    Some(relative_file(exercises_root, file)),
+                    None,
+                );
+                issue.issue.line = error.and_then(|error| error.line).map(|line| line as u32);
+                issues.push(issue);
+            }
+        }
+    }
+
+    entries.sort_by(|(a, a_path), (b, b_path)| {
+        (a.chapter, a.exercise_number, a_path).cmp(&(b.chapter, b.exercise_number, b_path))
+    });
+    entries
+}
+
+/// Keep the first exercise with each id, reporting the others
+fn dedupe(entries: &[(ExerciseMetadata, PathBuf)], exercises_root: &Path) -> (Vec<ExerciseMetadata>, Vec<CurriculumIssue>) {
+    let mut by_id: BTreeMap<&str, Vec<&Path>> = BTreeMap::new();
+    let mut exercises = Vec::new();
+    for (metadata, path) in entries {
+        let paths = by_id.entry(metadata.id.as_str()).or_default();
+        if paths.is_empty() {
+            exercises.push(metadata.clone());
+        }
+        paths.push(path);
+    }
+
+    let issues = by_id
+        .into_iter()
+        .filter(|(_, paths)| paths.len() > 1)
+        .map(|(id, paths)| {
+            let dirs: Vec<String> = paths.iter().map(|path| relative_file(exercises_root, path)).collect();
+            curriculum_issue(
+                CurriculumCheck::DuplicateId,
+                IssueSeverity::Error,
+                vec![id.to_string()],
+                format!("Exercise id {} is used by {}", id, dirs.join(", ")),
+                Some(relative_file(exercises_root, &metadata_path(paths[1]))),
+                Some("Give every exercise a unique id".to_string()),
+            )
+        })
+        .collect();
+
+    (exercises, issues)
+}
+
+fn check_exercise_numbers(
+    exercises: &[ExerciseMetadata],
+    metadata_file: &dyn Fn(&str) -> Option<String>,
+    issues: &mut Vec<CurriculumIssue>,
+) {
+    let mut by_number: BTreeMap<(u32, u32), Vec<&str>> = BTreeMap::new();
+    for exercise in exercises {
+        by_number
+            .entry((exercise.chapter, exercise.exercise_number))
+            .or_default()
+            .push(&exercise.id);
+    }
+
+    for ((chapter, number), ids) in by_number.into_iter().filter(|(_, ids)| ids.len() > 1) {
+        issues.push(curriculum_issue(
+            CurriculumCheck::DuplicateExerciseNumber,
+            IssueSeverity::Error,
+            ids.iter().map(|id| id.to_string()).collect(),
+            format!("Chapter {} exercise {} is claimed by {}", chapter, number, ids.join(", ")),
+            metadata_file(ids[1]),
+            Some("Renumber the exercises so each chapter counts up from 1".to_string()),
+        ));
+    }
+}
+
+fn check_prerequisites(
+    graph: &PrerequisiteGraph,
+    metadata_file: &dyn Fn(&str) -> Option<String>,
+    issues: &mut Vec<CurriculumIssue>,
+) {
+    for (exercise_id, prerequisite) in graph.unresolved() {
+        issues.push(curriculum_issue(
+            CurriculumCheck::DanglingPrerequisite,
+            IssueSeverity::Error,
+            vec![exercise_id.clone()],
+            format!("Prerequisite '{}' of {} matches no exercise", prerequisite, exercise_id),
+            metadata_file(exercise_id),
+            Some("Use the full id of an existing exercise".to_string()),
+        ));
+    }
+
+    let cycles = prerequisite_cycles(graph);
+    let on_cycle: HashSet<&str> = cycles.iter().flatten().map(String::as_str).collect();
+    for cycle in &cycles {
+        issues.push(curriculum_issue(
+            CurriculumCheck::PrerequisiteCycle,
+            IssueSeverity::Error,
+            cycle.clone(),
+            format!(
+                "Prerequisite cycle: {} -> {}",
+                cycle.join(" -> "),
+                cycle[0]
+            ),
+            metadata_file(&cycle[0]),
+            Some("Remove one of the prerequisites so the cycle is broken".to_string()),
+        ));
+    }
+
+    // Exercises downstream of a cycle are locked too
+    for exercise_id in graph.cyclic().iter().filter(|id| !on_cycle.contains(id.as_str())) {
+        issues.push(curriculum_issue(
+            CurriculumCheck::PrerequisiteCycle,
+            IssueSeverity::Error,
+            vec![exercise_id.clone()],
+            format!("{} can never be unlocked because it depends on a prerequisite cycle", exercise_id),
+            metadata_file(exercise_id),
+            None,
+        ));
+    }
+}
+
+/// The cycles among the graph's cyclic exercises, each listed so every
+/// exercise's next entry is one of its prerequisites
+fn prerequisite_cycles(graph: &PrerequisiteGraph) -> Vec<Vec<String>> {
+    let cyclic: HashSet<&str> = graph.cyclic().iter().map(String::as_str).collect();
+    let reachable = |from: &str| {
+        let mut seen = HashSet::new();
+        let mut stack = vec![from];
+        while let Some(id) = stack.pop() {
+            for prerequisite in graph.prerequisites_of(id) {
+                if cyclic.contains(prerequisite.as_str()) && seen.insert(prerequisite.as_str()) {
+                    stack.push(prerequisite);
+                }
+            }
+        }
+        seen
+    };
+
+    let reach: HashMap<&str, HashSet<&str>> = graph
+        .cyclic()
+        .iter()
+        .map(|id| (id.as_str(), reachable(id)))
+        .collect();
+
+    let mut assigned = HashSet::new();
+    let mut cycles = Vec::new();
+    for id in graph.cyclic() {
+        if assigned.contains(id.as_str()) || !reach[id.as_str()].contains(id.as_str()) {
+            continue;
+        }
+        // Walk prerequisites within the component to list it in cycle order
+        let component: HashSet<&str> = reach[id.as_str()]
+            .iter()
+            .copied()
+            .filter(|other| reach[other].contains(id.as_str()))
+            .collect();
+        let mut cycle = vec![id.clone()];
+        let mut current = id.as_str();
+        loop {
+            let next = graph
+                .prerequisites_of(current)
+                .iter()
+                .map(String::as_str)
+                .find(|p| component.contains(p) && !cycle.iter().any(|c| c == p));
+            match next {
+                Some(next) => {
+                    cycle.push(next.to_string());
+                    current = next;
+                }
+                None => break,
+            }
+        }
+        assigned.extend(component.iter().copied());
+        cycles.push(cycle);
+    }
+    cycles
+}
+
+/// A concept is introduced by the first exercise in course order that lists
+/// it. Any later exercise using it must require that exercise (directly or
+/// through other prerequisites), or learners can reach it before seeing the
+/// concept.
+fn check_concept_introduction(
+    graph: &PrerequisiteGraph,
+    metadata_file: &dyn Fn(&str) -> Option<String>,
+    issues: &mut Vec<CurriculumIssue>,
+) {
+    let cyclic: HashSet<&str> = graph.cyclic().iter().map(String::as_str).collect();
+    let mut required: HashMap<&str, HashSet<&str>> = HashMap::new(); // transitive prerequisites
+    let mut introduced_by: HashMap<&str, &str> = HashMap::new();
+
+    for exercise in graph.exercises() {
+        if cyclic.contains(exercise.id.as_str()) {
+            continue; // already reported
+        }
+
+        let mut ancestors = HashSet::new();
+        for prerequisite in graph.prerequisites_of(&exercise.id) {
+            ancestors.insert(prerequisite.as_str());
+            if let Some(theirs) = required.get(prerequisite.as_str()) {
+                ancestors.extend(theirs.iter().copied());
+            }
+        }
+
+        for concept in &exercise.concepts {
+            match introduced_by.get(concept.as_str()) {
+                None => {
+                    introduced_by.insert(concept, &exercise.id);
+                }
+                Some(&introducer) if !ancestors.contains(introducer) => {
+                    issues.push(curriculum_issue(
+                        CurriculumCheck::ConceptBeforeIntroduction,
+                        IssueSeverity::Warning,
+                        vec![exercise.id.clone(), introducer.to_string()],
+                        format!(
+                            "{} uses '{}', which is introduced by {}, but does not require it",
+                            exercise.id, concept, introducer
+                        ),
+                        metadata_file(&exercise.id),
+                        Some(format!("Add {} (or a later exercise that requires it) as a prerequisite", introducer)),
+                    ));
+                }
+                Some(_) => {}
+            }
+        }
+
+        required.insert(&exercise.id, ancestors);
+    }
+}
+
+/// Within a chapter, exercises should not get easier than one before them
+fn check_difficulty_progression(
+    exercises: &[ExerciseMetadata],
+    metadata_file: &dyn Fn(&str) -> Option<String>,
+    issues: &mut Vec<CurriculumIssue>,
+) {
+    let mut chapters: BTreeMap<u32, Vec<&ExerciseMetadata>> = BTreeMap::new();
+    for exercise in exercises {
+        chapters.entry(exercise.chapter).or_default().push(exercise);
+    }
+
+    for chapter in chapters.values_mut() {
+        chapter.sort_by_key(|exercise| exercise.exercise_number);
+        let mut hardest: Option<&ExerciseMetadata> = None;
+        for &exercise in chapter.iter() {
+            match hardest {
+                Some(previous) if exercise.difficulty < previous.difficulty => {
+                    issues.push(curriculum_issue(
+                        CurriculumCheck::DifficultyRegression,
+                        IssueSeverity::Warning,
+                        vec![exercise.id.clone(), previous.id.clone()],
+                        format!(
+                            "{} is {} but comes after {}, which is {}",
+                            exercise.id, exercise.difficulty, previous.id, previous.difficulty
+                        ),
+                        metadata_file(&exercise.id),
+                        Some("Reorder the chapter or adjust the difficulty".to_string()),
+                    ));
+                }
+                Some(previous) if exercise.difficulty == previous.difficulty => {}
+                _ => hardest = Some(exercise),
+            }
+        }
+    }
+}
+
+fn curriculum_issue(
+    check: CurriculumCheck,
+    severity: IssueSeverity,
+    exercise_ids: Vec<String>,
+    message: String,
+    file: Option<String>,
+    suggestion: Option<String>,
+) -> CurriculumIssue {
+    let category = match check {
+        CurriculumCheck::ConceptBeforeIntroduction | CurriculumCheck::DifficultyRegression => {
+            IssueCategory::Pedagogical
+        }
+        _ => IssueCategory::Metadata,
+    };
+    CurriculumIssue {
+        check,
+        exercise_ids,
+        issue: ValidationIssue {
+            severity,
+            category,
+            message,
+            file,
+            line: None,
+            suggestion,
+        },
+    }
+}
+
+fn is_error(issue: &ValidationIssue) -> bool {
+    issue.severity == IssueSeverity::Error
+}
+
+/// Path relative to the exercises directory, with forward slashes
+fn relative_file(exercises_root: &Path, path: &Path) -> String {
+    path.strip_prefix(exercises_root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+fn location(issue: &ValidationIssue) -> String {
+    match (&issue.file, issue.line) {
+        (Some(file), Some(line)) => format!("{}:{}", file, line),
+        (Some(file), None) => file.clone(),
+        _ => String::new(),
+    }
+}
+
+fn markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// One JUnit test case: a failure listing the errors, warnings as output
+fn junit_case(classname: &str, name: &str, issues: &[&ValidationIssue]) -> String {
+    let describe = |issue: &ValidationIssue| match location(issue) {
+        location if location.is_empty() => issue.message.clone(),
+        location => format!("{}: {}", location, issue.message),
+    };
+    let errors: Vec<String> = issues
+        .iter()
+        .filter(|issue| is_error(issue))
+        .map(|issue| describe(issue))
+        .collect();
+    let others: Vec<String> = issues
+        .iter()
+        .filter(|issue| !is_error(issue))
+        .map(|issue| format!("{:?}: {}", issue.severity, describe(issue)))
+        .collect();
+
+    let mut case = format!(
+        "    <testcase classname=\"{}\" name=\"{}\"",
+        xml_escape(classname),
+        xml_escape(name)
+    );
+    if errors.is_empty() && others.is_empty() {
+        case.push_str("/>\n");
+        return case;
+    }
+    case.push_str(">\n");
+    if !errors.is_empty() {
+        let _ = writeln!(
+            case,
+            "      <failure message=\"{}\">{}</failure>",
+            xml_escape(&errors[0]),
+            xml_escape(&errors.join("\n"))
+        );
+    }
+    if !others.is_empty() {
+        let _ = writeln!(case, "      <system-out>{}</system-out>", xml_escape(&others.join("\n")));
+    }
+    case.push_str("    </testcase>\n");
+    case
+}
+
+/// The metadata file in an exercise directory, whichever format it uses
+fn metadata_path(exercise_path: &Path) -> PathBuf {
+    find_metadata_file(exercise_path)
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| exercise_path.join(METADATA_FILE_NAMES[0]))
+}
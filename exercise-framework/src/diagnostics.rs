@@ -0,0 +1,303 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Severity of a compiler diagnostic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiagnosticLevel {
+    Error,
+    Warning,
+    Note,
+    Help,
+    #[serde(rename = "failure-note")]
+    FailureNote,
+    #[serde(rename = "error: internal compiler error")]
+    InternalCompilerError,
+}
+
+impl DiagnosticLevel {
+    /// Level as rustc prints it
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DiagnosticLevel::Error => "error",
+            DiagnosticLevel::Warning => "warning",
+            DiagnosticLevel::Note => "note",
+            DiagnosticLevel::Help => "help",
+            DiagnosticLevel::FailureNote => "failure-note",
+            DiagnosticLevel::InternalCompilerError => "error: internal compiler error",
+        }
+    }
+}
+
+/// How safely a suggested replacement can be applied
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Applicability {
+    MachineApplicable, // Safe to apply automatically
+    MaybeIncorrect,    // Probably right, needs review
+    HasPlaceholders,   // Contains `(...)` style placeholders
+    Unspecified,
+}
+
+/// Error or lint code attached to a diagnostic
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiagnosticCode {
+    pub code: String, // "E0382", "clippy::needless_return", "unused_variables"
+    pub explanation: Option<String>,
+}
+
+/// Source location a diagnostic points at (1-based lines and columns)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiagnosticSpan {
+    pub file_name: String,
+    pub byte_start: u32,
+    pub byte_end: u32,
+    pub line_start: u32,
+    pub line_end: u32,
+    pub column_start: u32,
+    pub column_end: u32,
+    pub is_primary: bool,
+    pub label: Option<String>,
+    pub suggested_replacement: Option<String>,
+    pub suggestion_applicability: Option<Applicability>,
+}
+
+/// A rustc or clippy diagnostic as reported by `--message-format=json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub message: String,
+    pub code: Option<DiagnosticCode>,
+    pub level: DiagnosticLevel,
+    pub spans: Vec<DiagnosticSpan>,
+    pub children: Vec<Diagnostic>, // notes and help messages
+    pub rendered: Option<String>,   // human-readable form as printed by cargo
+}
+
+/// A replacement the compiler proposes for a span
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Suggestion {
+    pub message: String,
+    pub span: DiagnosticSpan,
+    pub replacement: String,
+    pub applicability: Applicability,
+}
+
+/// Envelope for one line of cargo JSON output
+#[derive(Debug, Deserialize)]
+struct CargoMessage {
+    reason: String,
+    message: Option<Diagnostic>,
+    target: Option<CargoTarget>,
+    executable: Option<PathBuf>,
+    fresh: Option<bool>,
+    success: Option<bool>,
+    profile: Option<CargoProfile>,
+}
+
+/// Target an artifact was built for
+#[derive(Debug, Deserialize)]
+struct CargoTarget {
+    name: String,
+    kind: Vec<String>, // "bin", "lib", "test", ...
+    #[serde(default)]
+    src_path: PathBuf,
+}
+
+/// Profile an artifact was built with
+#[derive(Debug, Deserialize)]
+struct CargoProfile {
+    test: bool, // built as a libtest harness
+}
+
+/// A test harness reported by `cargo test --no-run --message-format=json`
+#[derive(Debug, Clone)]
+pub struct TestArtifact {
+    pub suite: String, // source file stem, as in cargo's "Running" lines
+    pub executable: PathBuf,
+}
+
+/// A cargo JSON message worth reporting while a build is in progress
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CargoEvent {
+    /// A crate finished compiling, or was already up to date (`fresh`)
+    Artifact {
+        name: String,
+        fresh: bool,
+        executable: Option<PathBuf>,
+    },
+    Diagnostic { diagnostic: Diagnostic },
+    BuildFinished { success: bool },
+}
+
+/// Parse one line of `--message-format=json` output.
+///
+/// Returns `None` for lines that are not cargo messages and for messages
+/// that carry nothing worth showing (build-script output, summaries).
+pub fn parse_cargo_event(line: &str) -> Option<CargoEvent> {
+    let msg = serde_json::from_str::<CargoMessage>(line.trim()).ok()?;
+    match msg.reason.as_str() {
+        "compiler-artifact" => Some(CargoEvent::Artifact {
+            name: msg.target?.name,
+            fresh: msg.fresh.unwrap_or(false),
+            executable: msg.executable,
+        }),
+        "compiler-message" => msg
+            .message
+            .filter(|diag| !diag.is_summary())
+            .map(|diagnostic| CargoEvent::Diagnostic { diagnostic }),
+        "build-finished" => Some(CargoEvent::BuildFinished {
+            success: msg.success.unwrap_or(false),
+        }),
+        _ => None,
+    }
+}
+
+/// Parse the diagnostics out of `cargo ... --message-format=json` stdout.
+///
+/// Non-diagnostic messages (artifacts, build-script output, build-finished)
+/// and lines that are not JSON are skipped, as is the trailing
+/// "aborting due to N previous errors" summary rustc emits.
+pub fn parse_cargo_messages(stdout: &str) -> Vec<Diagnostic> {
+    stdout
+        .lines()
+        .filter_map(|line| serde_json::from_str::<CargoMessage>(line.trim()).ok())
+        .filter(|msg| msg.reason == "compiler-message")
+        .filter_map(|msg| msg.message)
+        .filter(|diag| !diag.is_summary())
+        .collect()
+}
+
+/// Paths of the binaries reported by `cargo build --message-format=json`
+pub fn parse_binary_artifacts(stdout: &str) -> Vec<PathBuf> {
+    stdout
+        .lines()
+        .filter_map(|line| serde_json::from_str::<CargoMessage>(line.trim()).ok())
+        .filter(|msg| msg.reason == "compiler-artifact")
+        .filter(|msg| {
+            msg.target
+                .as_ref()
+                .is_some_and(|t| t.kind.iter().any(|k| k == "bin"))
+        })
+        .filter_map(|msg| msg.executable)
+        .collect()
+}
+
+impl TestArtifact {
+    /// Command running this harness the way `cargo test` would, from the
+    /// package directory
+    pub fn command(&self, package_dir: &Path) -> Command {
+        let mut command = Command::new(&self.executable);
+        command.current_dir(package_dir).env("CARGO_MANIFEST_DIR", package_dir);
+        command
+    }
+}
+
+/// Test harnesses reported by `cargo test --no-run --message-format=json`
+pub fn parse_test_artifacts(stdout: &str) -> Vec<TestArtifact> {
+    stdout
+        .lines()
+        .filter_map(|line| serde_json::from_str::<CargoMessage>(line.trim()).ok())
+        .filter(|msg| msg.reason == "compiler-artifact")
+        .filter(|msg| msg.profile.as_ref().is_some_and(|p| p.test))
+        .filter_map(|msg| {
+            let target = msg.target?;
+            let suite = target
+                .src_path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or(&target.name)
+                .to_string();
+            Some(TestArtifact {
+                suite,
+                executable: msg.executable?,
+            })
+        })
+        .collect()
+}
+
+impl Diagnostic {
+    /// Error or lint code, e.g. "E0382"
+    pub fn code(&self) -> Option<&str> {
+        self.code.as_ref().map(|c| c.code.as_str())
+    }
+
+    /// Whether this diagnostic prevents compilation
+    pub fn is_error(&self) -> bool {
+        matches!(
+            self.level,
+            DiagnosticLevel::Error | DiagnosticLevel::InternalCompilerError
+        )
+    }
+
+    /// Whether this diagnostic comes from a clippy lint
+    pub fn is_clippy_lint(&self) -> bool {
+        self.code().is_some_and(|code| code.starts_with("clippy::"))
+    }
+
+    /// The span the diagnostic is primarily about
+    pub fn primary_span(&self) -> Option<&DiagnosticSpan> {
+        self.spans.iter().find(|s| s.is_primary)
+    }
+
+    /// Additional spans that give context (e.g. "value moved here")
+    pub fn secondary_spans(&self) -> impl Iterator<Item = &DiagnosticSpan> {
+        self.spans.iter().filter(|s| !s.is_primary)
+    }
+
+    /// Replacement suggestions from this diagnostic and its children
+    pub fn suggestions(&self) -> Vec<Suggestion> {
+        let mut suggestions = Vec::new();
+        self.collect_suggestions(&self.message, &mut suggestions);
+        suggestions
+    }
+
+    /// Suggestions that are safe to apply without review
+    pub fn machine_applicable_suggestions(&self) -> Vec<Suggestion> {
+        self.suggestions()
+            .into_iter()
+            .filter(|s| s.applicability == Applicability::MachineApplicable)
+            .collect()
+    }
+
+    /// Text to show to a learner, preferring cargo's rendered output
+    pub fn display_text(&self) -> String {
+        self.rendered.clone().unwrap_or_else(|| match self.code() {
+            Some(code) => format!("{}[{}]: {}", self.level.as_str(), code, self.message),
+            None => format!("{}: {}", self.level.as_str(), self.message),
+        })
+    }
+
+    fn collect_suggestions(&self, parent_message: &str, out: &mut Vec<Suggestion>) {
+        for span in &self.spans {
+            if let Some(replacement) = &span.suggested_replacement {
+                let message = if self.message.is_empty() {
+                    parent_message
+                } else {
+                    &self.message
+                };
+                out.push(Suggestion {
+                    message: message.to_string(),
+                    span: span.clone(),
+                    replacement: replacement.clone(),
+                    applicability: span
+                        .suggestion_applicability
+                        .unwrap_or(Applicability::Unspecified),
+                });
+            }
+        }
+        for child in &self.children {
+            child.collect_suggestions(&self.message, out);
+        }
+    }
+
+    /// "aborting due to previous error" / "N warnings emitted" / "try rustc --explain" summaries
+    fn is_summary(&self) -> bool {
+        self.spans.is_empty()
+            && self.code.is_none()
+            && (self.level == DiagnosticLevel::FailureNote
+                || self.message.starts_with("aborting due to")
+                || self.message.ends_with("warning emitted")
+                || self.message.ends_with("warnings emitted"))
+    }
+}
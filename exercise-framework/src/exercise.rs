@@ -1,11 +1,13 @@
-use crate::metadata::ExerciseMetadata;
+use crate::hints::{self, Hint};
+use crate::metadata::{find_metadata_file, is_metadata_file, ExerciseMetadata, MetadataError};
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
-/// Exercise difficulty levels
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// Exercise difficulty levels, easiest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ExerciseDifficulty {
     Beginner,
     Intermediate,
@@ -13,7 +15,8 @@ pub enum ExerciseDifficulty {
 }
 
 /// Types of exercises available
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ExerciseType {
     CodeCompletion,  // Fill in the blanks
     BugFixing,       // Fix intentional errors
@@ -22,14 +25,86 @@ pub enum ExerciseType {
     Performance,     // Optimization challenge
 }
 
+impl ExerciseDifficulty {
+    pub const ALL: [ExerciseDifficulty; 3] = [Self::Beginner, Self::Intermediate, Self::Advanced];
+
+    /// Name as written in `metadata.json`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Beginner => "beginner",
+            Self::Intermediate => "intermediate",
+            Self::Advanced => "advanced",
+        }
+    }
+}
+
+impl ExerciseType {
+    pub const ALL: [ExerciseType; 5] = [
+        Self::CodeCompletion,
+        Self::BugFixing,
+        Self::FromScratch,
+        Self::CodeReview,
+        Self::Performance,
+    ];
+
+    /// Name as written in `metadata.json`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::CodeCompletion => "code_completion",
+            Self::BugFixing => "bug_fixing",
+            Self::FromScratch => "from_scratch",
+            Self::CodeReview => "code_review",
+            Self::Performance => "performance",
+        }
+    }
+}
+
+impl std::fmt::Display for ExerciseDifficulty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::fmt::Display for ExerciseType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for ExerciseDifficulty {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|difficulty| difficulty.as_str() == s)
+            .with_context(|| format!("Unknown difficulty '{}'; expected beginner, intermediate or advanced", s))
+    }
+}
+
+impl std::str::FromStr for ExerciseType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|exercise_type| exercise_type.as_str() == s)
+            .with_context(|| {
+                let expected: Vec<&str> = Self::ALL.iter().map(|t| t.as_str()).collect();
+                format!("Unknown exercise type '{}'; expected one of {}", s, expected.join(", "))
+            })
+    }
+}
+
 /// Main exercise structure
 #[derive(Debug, Clone)]
 pub struct Exercise {
     pub metadata: ExerciseMetadata,
     pub path: PathBuf,
+    pub metadata_file: PathBuf, // metadata.json, metadata.toml or metadata.yaml
     pub source_files: Vec<SourceFile>,
     pub test_files: Vec<TestFile>,
-    pub hints: Vec<String>,
+    pub hints: Vec<Hint>, // parsed from hints.md
     pub solutions: Vec<Solution>,
 }
 
@@ -70,13 +145,21 @@ pub struct Solution {
 }
 
 impl Exercise {
-    /// Load an exercise from the filesystem
+    /// Load an exercise from the filesystem, searching its chapter for the
+    /// id. Repeated lookups should go through an [`crate::ExerciseIndex`].
     pub fn load<P: AsRef<Path>>(exercises_root: P, exercise_id: &str) -> Result<Self> {
         let exercise_path = Self::find_exercise_path(&exercises_root, exercise_id)?;
-        
+        Self::load_from_path(exercise_path)
+    }
+
+    /// Load an exercise from its directory (e.g. `exercises/ch01_getting_started/ex01_hello_world`)
+    pub fn load_from_path<P: AsRef<Path>>(exercise_path: P) -> Result<Self> {
+        let exercise_path = exercise_path.as_ref().to_path_buf();
+
         // Load metadata
-        let metadata_path = exercise_path.join("metadata.json");
-        let metadata = ExerciseMetadata::load_from_file(&metadata_path)
+        let metadata_file = find_metadata_file(&exercise_path)?
+            .ok_or_else(|| MetadataError::in_file(&exercise_path, "No metadata file".to_string()))?;
+        let metadata = ExerciseMetadata::load_from_file(&metadata_file)
             .context("Failed to load exercise metadata")?;
 
         // Load source files
@@ -94,6 +177,7 @@ impl Exercise {
         Ok(Exercise {
             metadata,
             path: exercise_path,
+            metadata_file,
             source_files,
             test_files,
             hints,
@@ -126,13 +210,9 @@ impl Exercise {
                     
                     // Check if this exercise matches the ID
                     let exercise_path = exercise_entry.path();
-                    let metadata_path = exercise_path.join("metadata.json");
-                    
-                    if metadata_path.exists() {
-                        if let Ok(metadata) = ExerciseMetadata::load_from_file(&metadata_path) {
-                            if metadata.id == exercise_id {
-                                return Ok(exercise_path);
-                            }
+                    if let Ok(metadata) = ExerciseMetadata::load_from_dir(&exercise_path) {
+                        if metadata.id == exercise_id {
+                            return Ok(exercise_path);
                         }
                     }
                 }
@@ -153,7 +233,7 @@ impl Exercise {
 
         for entry in WalkDir::new(&src_path).into_iter().filter_map(|e| e.ok()) {
             let path = entry.path();
-            if path.is_file() && path.extension().map_or(false, |ext| ext == "rs") {
+            if path.is_file() && path.extension().is_some_and(|ext| ext == "rs") {
                 let name = path.file_name()
                     .and_then(|n| n.to_str())
                     .unwrap_or("unknown")
@@ -189,7 +269,7 @@ impl Exercise {
 
         for entry in WalkDir::new(&tests_path).into_iter().filter_map(|e| e.ok()) {
             let path = entry.path();
-            if path.is_file() && path.extension().map_or(false, |ext| ext == "rs") {
+            if path.is_file() && path.extension().is_some_and(|ext| ext == "rs") {
                 let name = path.file_name()
                     .and_then(|n| n.to_str())
                     .unwrap_or("unknown")
@@ -222,40 +302,19 @@ impl Exercise {
         Ok(test_files)
     }
 
-    /// Load hints from hints.md file
-    fn load_hints(exercise_path: &Path) -> Result<Vec<String>> {
-        let hints_path = exercise_path.join("src").join("hints.md");
-        if !hints_path.exists() {
+    /// Load hints from hints.md file (at the exercise root, or in `src/` for older exercises)
+    fn load_hints(exercise_path: &Path) -> Result<Vec<Hint>> {
+        let Some(hints_path) = [exercise_path.join("hints.md"), exercise_path.join("src").join("hints.md")]
+            .into_iter()
+            .find(|path| path.exists())
+        else {
             return Ok(vec![]);
-        }
+        };
 
         let content = std::fs::read_to_string(&hints_path)
             .context("Failed to read hints file")?;
 
-        // Parse hints by looking for level markers
-        let mut hints = Vec::new();
-        let mut current_hint = String::new();
-        let mut in_hint = false;
-
-        for line in content.lines() {
-            if line.starts_with("## Level") {
-                if in_hint && !current_hint.trim().is_empty() {
-                    hints.push(current_hint.trim().to_string());
-                }
-                current_hint.clear();
-                in_hint = true;
-            } else if in_hint {
-                current_hint.push_str(line);
-                current_hint.push('\n');
-            }
-        }
-
-        // Don't forget the last hint
-        if in_hint && !current_hint.trim().is_empty() {
-            hints.push(current_hint.trim().to_string());
-        }
-
-        Ok(hints)
+        Ok(hints::parse_hints(&content))
     }
 
     /// Load solutions with explanations
@@ -272,7 +331,7 @@ impl Exercise {
             let entry = entry?;
             let path = entry.path();
             
-            if path.is_file() && path.extension().map_or(false, |ext| ext == "rs") {
+            if path.is_file() && path.extension().is_some_and(|ext| ext == "rs") {
                 let name = path.file_stem()
                     .and_then(|n| n.to_str())
                     .unwrap_or("unknown")
@@ -302,28 +361,42 @@ impl Exercise {
         }
 
         // Sort solutions so primary comes first
-        solutions.sort_by(|a, b| b.is_primary.cmp(&a.is_primary));
+        solutions.sort_by_key(|s| !s.is_primary);
 
         Ok(solutions)
     }
 
     /// List all available exercises
     pub fn list_all<P: AsRef<Path>>(exercises_root: P) -> Result<Vec<ExerciseMetadata>> {
+        Ok(Self::list_all_with_paths(exercises_root)?
+            .into_iter()
+            .map(|(metadata, _)| metadata)
+            .collect())
+    }
+
+    /// List all available exercises together with their directories
+    pub fn list_all_with_paths<P: AsRef<Path>>(exercises_root: P) -> Result<Vec<(ExerciseMetadata, PathBuf)>> {
         let exercises_root = exercises_root.as_ref();
         let mut exercises = Vec::new();
 
         for entry in WalkDir::new(exercises_root)
             .into_iter()
             .filter_map(|e| e.ok())
-            .filter(|e| e.file_name() == "metadata.json")
+            .filter(|e| is_metadata_file(e.path()))
         {
-            if let Ok(metadata) = ExerciseMetadata::load_from_file(entry.path()) {
-                exercises.push(metadata);
+            let exercise_path = entry.path()
+                .parent()
+                .unwrap_or(exercises_root)
+                .to_path_buf();
+            // Directories with several metadata files fail to load, so each
+            // exercise is listed once
+            if let Ok(metadata) = ExerciseMetadata::load_from_dir(&exercise_path) {
+                exercises.push((metadata, exercise_path));
             }
         }
 
         // Sort by chapter and exercise number
-        exercises.sort_by(|a, b| {
+        exercises.sort_by(|(a, _), (b, _)| {
             a.chapter.cmp(&b.chapter)
                 .then_with(|| a.exercise_number.cmp(&b.exercise_number))
         });
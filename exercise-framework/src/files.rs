@@ -0,0 +1,291 @@
+//! Learner access to the files of an exercise.
+//!
+//! Rust sources under `src/` are always editable. Anything else (tests,
+//! solutions, `Cargo.toml`, metadata) is off limits unless the exercise
+//! lists it in `editable_files`; an entry ending in `/` opens every file
+//! below that directory. Paths are relative to the exercise directory and
+//! use `/` separators, e.g. `src/shapes/circle.rs`.
+
+use crate::metadata::ExerciseMetadata;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use walkdir::WalkDir;
+
+const SOURCE_DIR: &str = "src/";
+
+#[derive(Debug, thiserror::Error)]
+pub enum FileError {
+    #[error("Invalid file path '{0}'")]
+    InvalidPath(String),
+    #[error("'{0}' is not editable in this exercise")]
+    NotEditable(String),
+    #[error("'{0}' is the crate root and cannot be removed or renamed")]
+    CrateRoot(String),
+    #[error("File not found: {0}")]
+    NotFound(String),
+    #[error("File already exists: {0}")]
+    AlreadyExists(String),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// A file the learner can open in the editor
+#[derive(Debug, Clone, Serialize)]
+pub struct EditableFile {
+    pub path: String,
+    pub size: u64,
+}
+
+/// The editable files of one exercise directory (shared or a learner's copy)
+#[derive(Debug, Clone)]
+pub struct ExerciseFiles {
+    dir: PathBuf,
+    editable: Vec<String>, // extra entries from the metadata
+}
+
+impl ExerciseFiles {
+    pub fn new<P: AsRef<Path>>(exercise_dir: P, metadata: &ExerciseMetadata) -> Self {
+        Self {
+            dir: exercise_dir.as_ref().to_path_buf(),
+            editable: metadata.editable_files.clone(),
+        }
+    }
+
+    /// The file the editor opens first: `src/main.rs`, or `src/lib.rs` for
+    /// library exercises
+    pub fn main_file(&self) -> &'static str {
+        if !self.dir.join("src/main.rs").exists() && self.dir.join("src/lib.rs").exists() {
+            "src/lib.rs"
+        } else {
+            "src/main.rs"
+        }
+    }
+
+    /// Whether a learner may change `path`
+    pub fn check(&self, path: &str) -> Result<(), FileError> {
+        if !is_clean_path(path) {
+            return Err(FileError::InvalidPath(path.to_string()));
+        }
+        if !self.allows(path) {
+            return Err(FileError::NotEditable(path.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Every editable file that currently exists, sorted by path
+    pub fn list(&self) -> Result<Vec<EditableFile>, FileError> {
+        let mut roots: Vec<&str> = vec![SOURCE_DIR];
+        roots.extend(self.editable.iter().map(String::as_str));
+
+        let mut files = Vec::new();
+        for root in roots {
+            let entries = WalkDir::new(self.dir.join(root))
+                .follow_links(false)
+                .into_iter()
+                .filter_entry(|entry| entry.file_name() != "target")
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.file_type().is_file());
+
+            for entry in entries {
+                let Some(path) = self.relative(entry.path()) else {
+                    continue;
+                };
+                if self.check(&path).is_ok() {
+                    let size = entry.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+                    files.push(EditableFile { path, size });
+                }
+            }
+        }
+
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        files.dedup_by(|a, b| a.path == b.path);
+        Ok(files)
+    }
+
+    /// Content of every editable file, by path
+    pub fn contents(&self) -> Result<BTreeMap<String, String>, FileError> {
+        self.list()?
+            .into_iter()
+            .map(|file| {
+                let content = self.read(&file.path)?;
+                Ok((file.path, content))
+            })
+            .collect()
+    }
+
+    /// Content of every editable file as committed at git `HEAD`, by path,
+    /// ignoring changes in the working tree. `None` when the directory is
+    /// not in a git repository or has nothing committed.
+    pub fn committed_contents(&self) -> Result<Option<BTreeMap<String, String>>, FileError> {
+        let Some(listing) = self.git(&["ls-tree", "-r", "-z", "--name-only", "HEAD", "--", "."]) else {
+            return Ok(None);
+        };
+        if listing.is_empty() {
+            return Ok(None);
+        }
+
+        let mut contents = BTreeMap::new();
+        for path in listing.split('\0').filter(|path| self.check(path).is_ok()) {
+            let content = self
+                .git(&["show", &format!("HEAD:./{}", path)])
+                .ok_or_else(|| FileError::NotFound(path.to_string()))?;
+            contents.insert(path.to_string(), content);
+        }
+        Ok(Some(contents))
+    }
+
+    /// Output of a git command run in the exercise directory, if it succeeded
+    fn git(&self, args: &[&str]) -> Option<String> {
+        let output = Command::new("git").args(args).current_dir(&self.dir).output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        String::from_utf8(output.stdout).ok()
+    }
+
+    /// Make the editable files exactly `contents`: write every entry and
+    /// delete editable files it does not mention
+    pub fn replace_all(&self, contents: &BTreeMap<String, String>) -> Result<(), FileError> {
+        for path in contents.keys() {
+            self.check(path)?;
+        }
+        for file in self.list()? {
+            if !contents.contains_key(&file.path) {
+                let target = self.resolve(&file.path)?;
+                std::fs::remove_file(&target)?;
+                self.remove_empty_parents(&target);
+            }
+        }
+        for (path, content) in contents {
+            self.write(path, content)?;
+        }
+        Ok(())
+    }
+
+    pub fn read(&self, path: &str) -> Result<String, FileError> {
+        let target = self.resolve(path)?;
+        if !target.is_file() {
+            return Err(FileError::NotFound(path.to_string()));
+        }
+        Ok(std::fs::read_to_string(target)?)
+    }
+
+    /// Create or replace a file. Returns whether it was created.
+    pub fn write(&self, path: &str, content: &str) -> Result<bool, FileError> {
+        let target = self.resolve(path)?;
+        let created = !target.exists();
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(target, content)?;
+        Ok(created)
+    }
+
+    /// Create a file that must not exist yet
+    pub fn create(&self, path: &str, content: &str) -> Result<(), FileError> {
+        if self.resolve(path)?.exists() {
+            return Err(FileError::AlreadyExists(path.to_string()));
+        }
+        self.write(path, content).map(|_| ())
+    }
+
+    pub fn rename(&self, from: &str, to: &str) -> Result<(), FileError> {
+        let source = self.existing_file(from)?;
+        let target = self.resolve(to)?;
+        if target.exists() {
+            return Err(FileError::AlreadyExists(to.to_string()));
+        }
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::rename(&source, target)?;
+        self.remove_empty_parents(&source);
+        Ok(())
+    }
+
+    pub fn delete(&self, path: &str) -> Result<(), FileError> {
+        let target = self.existing_file(path)?;
+        std::fs::remove_file(&target)?;
+        self.remove_empty_parents(&target);
+        Ok(())
+    }
+
+    /// Absolute path of an editable file, rejecting anything that could
+    /// leave the exercise directory
+    fn resolve(&self, path: &str) -> Result<PathBuf, FileError> {
+        self.check(path)?;
+
+        // Refuse to follow symlinks out of the exercise
+        let target = self.dir.join(path);
+        let mut current = self.dir.clone();
+        for component in path.split('/') {
+            current.push(component);
+            if current.symlink_metadata().is_ok_and(|metadata| metadata.file_type().is_symlink()) {
+                return Err(FileError::InvalidPath(path.to_string()));
+            }
+        }
+        Ok(target)
+    }
+
+    /// An editable file that exists and is not the crate root
+    fn existing_file(&self, path: &str) -> Result<PathBuf, FileError> {
+        let target = self.resolve(path)?;
+        if !target.is_file() {
+            return Err(FileError::NotFound(path.to_string()));
+        }
+        if path == "src/main.rs" || path == "src/lib.rs" {
+            return Err(FileError::CrateRoot(path.to_string()));
+        }
+        Ok(target)
+    }
+
+    fn allows(&self, path: &str) -> bool {
+        let is_source = path.starts_with(SOURCE_DIR) && path.ends_with(".rs");
+        is_source
+            || self.editable.iter().any(|entry| {
+                if entry.ends_with('/') {
+                    path.starts_with(entry.as_str())
+                } else {
+                    path == entry
+                }
+            })
+    }
+
+    fn relative(&self, path: &Path) -> Option<String> {
+        let relative = path.strip_prefix(&self.dir).ok()?;
+        let parts: Option<Vec<&str>> = relative.components().map(|c| c.as_os_str().to_str()).collect();
+        Some(parts?.join("/"))
+    }
+
+    /// Drop directories a rename or delete left empty, up to but not
+    /// including `src/` and the exercise directory itself
+    fn remove_empty_parents(&self, removed: &Path) {
+        let source_dir = self.dir.join(SOURCE_DIR.trim_end_matches('/'));
+        let mut dir = removed.parent();
+        while let Some(current) = dir {
+            if current == source_dir || current == self.dir || !current.starts_with(&self.dir) {
+                break;
+            }
+            if std::fs::remove_dir(current).is_err() {
+                break;
+            }
+            dir = current.parent();
+        }
+    }
+}
+
+/// A relative `/`-separated path without empty, `.`, `..` or hidden
+/// components and without characters that mean something to a shell or
+/// another platform's filesystem
+fn is_clean_path(path: &str) -> bool {
+    !path.is_empty()
+        && path.split('/').all(|component| {
+            !component.is_empty()
+                && !component.starts_with('.')
+                && component
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        })
+}
@@ -0,0 +1,240 @@
+//! Catalog of contextual hints for compiler errors, lints and panics.
+//!
+//! Rules are TOML files of `[[rule]]` tables (see `hint-rules/` for the
+//! built-in catalog). A rule matches diagnostics by their exact code
+//! (`E0382`, `unused_mut`, `clippy::needless_return`) and panics by a
+//! substring of the panic message. Rules scoped to `exercises` or
+//! `concepts` only apply there, and win over general rules for the same
+//! trigger.
+
+use crate::book;
+use crate::diagnostics::Diagnostic;
+use crate::hints::{Hint, HintLevel};
+use crate::metadata::ExerciseMetadata;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Built-in rule files, by name
+const BUILTIN_RULES: [(&str, &str); 3] = [
+    ("rustc.toml", include_str!("../hint-rules/rustc.toml")),
+    ("clippy.toml", include_str!("../hint-rules/clippy.toml")),
+    ("panics.toml", include_str!("../hint-rules/panics.toml")),
+];
+
+/// Contextual hints suggested for one result
+const MAX_SUGGESTIONS: usize = 5;
+
+/// One entry of a rule file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HintRule {
+    pub id: String,
+    #[serde(default)]
+    pub codes: Vec<String>, // exact diagnostic codes
+    #[serde(default)]
+    pub panics: Vec<String>, // substrings of panic messages
+    #[serde(default)]
+    pub exercises: Vec<String>, // only for these exercise ids
+    #[serde(default)]
+    pub concepts: Vec<String>, // only for exercises teaching one of these
+    #[serde(default = "default_level")]
+    pub level: HintLevel,
+    pub title: String,
+    pub content: String, // markdown
+    #[serde(default)]
+    pub book: Vec<String>, // Rust Book section ids, e.g. "4.2"
+}
+
+#[derive(Debug, Deserialize)]
+struct RuleFile {
+    #[serde(default)]
+    rule: Vec<HintRule>,
+}
+
+/// A hint suggested for something that went wrong in the learner's code
+#[derive(Debug, Clone, Serialize)]
+pub struct ContextualHint {
+    pub rule: String,             // id of the matching rule
+    pub trigger: String,          // the code or panic message it matched
+    pub location: Option<String>, // "src/main.rs:4" of the diagnostic
+    #[serde(flatten)]
+    pub hint: Hint,
+}
+
+/// The rules a [`crate::HintSystem`] suggests contextual hints from
+#[derive(Debug, Clone, Default)]
+pub struct HintCatalog {
+    rules: Vec<HintRule>, // later rules override earlier ones of equal scope
+}
+
+/// How closely a rule is scoped to an exercise; lower wins
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Scope {
+    Exercise,
+    Concept,
+    General,
+}
+
+impl HintCatalog {
+    /// The rules that ship with the framework
+    pub fn builtin() -> Self {
+        let mut catalog = Self::default();
+        for (name, content) in BUILTIN_RULES {
+            let rules = Self::parse(content).unwrap_or_else(|e| panic!("invalid built-in hint rules {}: {:#}", name, e));
+            catalog.extend(rules);
+        }
+        catalog
+    }
+
+    /// Read a rule file, or every `.toml` file in a directory
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let mut files = if path.is_dir() {
+            std::fs::read_dir(path)
+                .with_context(|| format!("Failed to read {:?}", path))?
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|file| file.extension().is_some_and(|ext| ext == "toml"))
+                .collect()
+        } else {
+            vec![path.to_path_buf()]
+        };
+        files.sort();
+
+        let mut catalog = Self::default();
+        for file in files {
+            let content = std::fs::read_to_string(&file)
+                .with_context(|| format!("Failed to read {:?}", file))?;
+            catalog.extend(Self::parse(&content).with_context(|| format!("Invalid hint rules in {:?}", file))?);
+        }
+        Ok(catalog)
+    }
+
+    /// Parse one rule file; every rule needs a trigger and known book sections
+    pub fn parse(content: &str) -> Result<Self> {
+        let file: RuleFile = toml::from_str(content)?;
+        for rule in &file.rule {
+            if rule.codes.is_empty() && rule.panics.is_empty() {
+                anyhow::bail!("Rule '{}' has neither codes nor panics", rule.id);
+            }
+            if let Some(unknown) = rule.book.iter().find(|id| book::section(id).is_none()) {
+                anyhow::bail!("Rule '{}' links unknown book section '{}'", rule.id, unknown);
+            }
+        }
+        Ok(Self { rules: file.rule })
+    }
+
+    /// Add rules that take precedence over the ones already loaded
+    pub fn extend(&mut self, other: HintCatalog) {
+        self.rules.extend(other.rules);
+    }
+
+    pub fn rules(&self) -> &[HintRule] {
+        &self.rules
+    }
+
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Hints for the diagnostics and panic messages of a failed build, run
+    /// or check, most relevant first: errors before lints, then panics
+    pub fn suggest(
+        &self,
+        exercise: &ExerciseMetadata,
+        diagnostics: &[Diagnostic],
+        panics: &[String],
+    ) -> Vec<ContextualHint> {
+        let mut ordered: Vec<&Diagnostic> = diagnostics.iter().filter(|d| d.is_error()).collect();
+        ordered.extend(diagnostics.iter().filter(|d| !d.is_error()));
+
+        let mut suggestions: Vec<ContextualHint> = Vec::new();
+        let mut suggest = |rule: &HintRule, trigger: &str, location: Option<String>| {
+            if suggestions.len() < MAX_SUGGESTIONS && suggestions.iter().all(|s| s.rule != rule.id) {
+                suggestions.push(rule.to_hint(trigger, location));
+            }
+        };
+
+        for diagnostic in ordered {
+            let Some(code) = diagnostic.code() else {
+                continue;
+            };
+            if let Some(rule) = self.best_rule(exercise, |rule| rule.codes.iter().any(|c| c == code)) {
+                let location = diagnostic
+                    .primary_span()
+                    .map(|span| format!("{}:{}", span.file_name, span.line_start));
+                suggest(rule, code, location);
+            }
+        }
+        for message in panics {
+            if let Some(rule) = self.best_rule(exercise, |rule| rule.matches_panic(message)) {
+                // A test's own message may quote the panic of the program it ran
+                let line = message.lines().find(|line| rule.matches_panic(line)).unwrap_or(message);
+                suggest(rule, line.trim(), None);
+            }
+        }
+
+        suggestions
+    }
+
+    /// The most specific rule in scope for the exercise that `matches`
+    fn best_rule(&self, exercise: &ExerciseMetadata, matches: impl Fn(&HintRule) -> bool) -> Option<&HintRule> {
+        self.rules
+            .iter()
+            .rev()
+            .filter(|rule| matches(rule))
+            .filter_map(|rule| rule.scope(exercise).map(|scope| (scope, rule)))
+            .min_by_key(|(scope, _)| *scope)
+            .map(|(_, rule)| rule)
+    }
+}
+
+impl HintRule {
+    /// How this rule applies to an exercise, if at all
+    fn scope(&self, exercise: &ExerciseMetadata) -> Option<Scope> {
+        if !self.exercises.is_empty() {
+            self.exercises.contains(&exercise.id).then_some(Scope::Exercise)
+        } else if !self.concepts.is_empty() {
+            self.concepts
+                .iter()
+                .any(|concept| exercise.concepts.contains(concept))
+                .then_some(Scope::Concept)
+        } else {
+            Some(Scope::General)
+        }
+    }
+
+    fn matches_panic(&self, message: &str) -> bool {
+        self.panics.iter().any(|pattern| message.contains(pattern.as_str()))
+    }
+
+    fn to_hint(&self, trigger: &str, location: Option<String>) -> ContextualHint {
+        ContextualHint {
+            rule: self.id.clone(),
+            trigger: trigger.to_string(),
+            location,
+            hint: Hint {
+                level: self.level,
+                title: self.title.clone(),
+                content: self.content.trim().to_string(),
+                rust_book_links: self
+                    .book
+                    .iter()
+                    .filter_map(|id| book::section(id))
+                    .map(|page| page.online_url())
+                    .collect(),
+                code_snippets: Vec::new(),
+                related_concepts: self.concepts.clone(),
+            },
+        }
+    }
+}
+
+fn default_level() -> HintLevel {
+    HintLevel::Conceptual
+}
@@ -1,6 +1,13 @@
+use crate::diagnostics::Diagnostic;
+use crate::hint_rules::{ContextualHint, HintCatalog};
+use crate::metadata::{ExerciseMetadata, HintConfig};
+use crate::progress::{HintPreference, UserProgress};
+use crate::progress_store::ProgressStore;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+const BOOK_URL_PREFIX: &str = "https://doc.rust-lang.org/book/";
+
 /// Hint system for progressive learning assistance
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum HintLevel {
@@ -9,12 +16,32 @@ pub enum HintLevel {
     Implementation, // Level 3: Provides specific code guidance
 }
 
+impl HintLevel {
+    /// The `N` of a `## Level N` heading
+    pub fn number(self) -> u32 {
+        match self {
+            HintLevel::Conceptual => 1,
+            HintLevel::Strategic => 2,
+            HintLevel::Implementation => 3,
+        }
+    }
+
+    pub fn from_number(number: u32) -> Option<Self> {
+        match number {
+            1 => Some(HintLevel::Conceptual),
+            2 => Some(HintLevel::Strategic),
+            3 => Some(HintLevel::Implementation),
+            _ => None,
+        }
+    }
+}
+
 /// Individual hint with context
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Hint {
     pub level: HintLevel,
     pub title: String,
-    pub content: String,
+    pub content: String, // markdown, code blocks included
     pub rust_book_links: Vec<String>,
     pub code_snippets: Vec<CodeSnippet>,
     pub related_concepts: Vec<String>,
@@ -29,15 +56,15 @@ pub struct CodeSnippet {
     pub is_complete: bool, // true if runnable, false if just a fragment
 }
 
-/// Hint usage tracking
+/// One learner's use of the hints of one exercise
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HintUsage {
     pub exercise_id: String,
     pub user_id: String,
     pub hints_requested: Vec<HintRequest>,
     pub total_hints_used: u32,
-    pub time_before_first_hint: u32, // seconds
-    pub solved_after_hints: bool,
+    pub time_before_first_hint: u32, // seconds from opening the exercise
+    pub solved_after_hints: bool,   // completed the exercise after the first hint
 }
 
 /// Individual hint request
@@ -48,15 +75,40 @@ pub struct HintRequest {
     pub was_helpful: Option<bool>, // User feedback
 }
 
+/// How a request for the next hint turned out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HintStatus {
+    Revealed,  // the next level is in `hint`
+    Locked,    // the next level needs more failed test runs first
+    Exhausted, // every level the learner may see is revealed
+    Disabled,  // the learner turned hints off
+}
+
+/// Answer to a request for the next hint of an exercise
+#[derive(Debug, Clone, Serialize)]
+pub struct HintReveal {
+    pub status: HintStatus,
+    pub hint: Option<Hint>,
+    pub revealed_level: u32,  // highest level seen, including this one
+    pub max_level: u32,       // highest level the learner may see
+    pub failures_needed: u32, // failed test runs still needed to unlock the next level
+}
+
 /// Hint system manager
 pub struct HintSystem {
     exercise_hints: HashMap<String, Vec<Hint>>,
-    usage_tracker: HintUsageTracker,
+    rules: HintCatalog, // for contextual hints
 }
 
-/// Tracks hint usage for analytics
+/// Learners below which a rate is too noisy to suggest changes from
+const MIN_SAMPLE: u32 = 3;
+
+/// Hint usage gathered from the progress of every learner, for analytics
+#[derive(Debug, Clone, Default)]
 pub struct HintUsageTracker {
-    usage_data: HashMap<String, Vec<HintUsage>>,
+    usage_data: HashMap<String, Vec<HintUsage>>, // by exercise id
+    learners: HashMap<String, u32>,              // learners who started each exercise
 }
 
 impl HintSystem {
@@ -64,17 +116,21 @@ impl HintSystem {
     pub fn new() -> Self {
         Self {
             exercise_hints: HashMap::new(),
-            usage_tracker: HintUsageTracker::new(),
+            rules: HintCatalog::builtin(),
         }
     }
 
     /// Load hints for an exercise from markdown file
     pub fn load_exercise_hints(&mut self, exercise_id: &str, hints_content: &str) -> anyhow::Result<()> {
-        let hints = Self::parse_hints_from_markdown(hints_content)?;
-        self.exercise_hints.insert(exercise_id.to_string(), hints);
+        self.set_exercise_hints(exercise_id, parse_hints(hints_content));
         Ok(())
     }
 
+    /// Replace the hints of an exercise with already parsed ones
+    pub fn set_exercise_hints(&mut self, exercise_id: &str, hints: Vec<Hint>) {
+        self.exercise_hints.insert(exercise_id.to_string(), hints);
+    }
+
     /// Get next available hint for an exercise
     pub fn get_next_hint(&self, exercise_id: &str, current_level: Option<HintLevel>) -> Option<&Hint> {
         let hints = self.exercise_hints.get(exercise_id)?;
@@ -89,172 +145,90 @@ impl HintSystem {
         hints.iter().find(|hint| hint.level == target_level)
     }
 
-    /// Get all hints for an exercise (for instructors/solutions)
-    pub fn get_all_hints(&self, exercise_id: &str) -> Option<&Vec<Hint>> {
-        self.exercise_hints.get(exercise_id)
-    }
-
-    /// Record hint usage for analytics
-    pub fn record_hint_usage(&mut self, exercise_id: &str, user_id: &str, level: HintLevel) {
-        self.usage_tracker.record_usage(exercise_id, user_id, level);
-    }
-
-    /// Get hint effectiveness statistics
-    pub fn get_hint_effectiveness(&self, exercise_id: &str) -> HintEffectiveness {
-        self.usage_tracker.calculate_effectiveness(exercise_id)
+    /// Highest level a learner may see: limited by the levels hints.md
+    /// has, the exercise's `available` setting and the learner's preference
+    pub fn max_level(&self, exercise_id: &str, config: &HintConfig, preference: &HintPreference) -> u32 {
+        let written = self
+            .exercise_hints
+            .get(exercise_id)
+            .and_then(|hints| hints.iter().map(|hint| hint.level.number()).max())
+            .unwrap_or(0);
+        let preferred = match preference {
+            HintPreference::Disabled => 0,
+            HintPreference::Minimal => 1,
+            HintPreference::Progressive | HintPreference::Detailed => 3,
+        };
+        written.min(config.available).min(preferred)
     }
 
-    /// Parse hints from markdown content
-    fn parse_hints_from_markdown(content: &str) -> anyhow::Result<Vec<Hint>> {
-        let mut hints = Vec::new();
-        let mut current_hint: Option<Hint> = None;
-        let mut current_content = String::new();
-        let mut in_code_block = false;
-        let mut current_code_snippet = String::new();
-
-        for line in content.lines() {
-            // Check for hint level headers
-            if let Some(level) = Self::parse_hint_level(line) {
-                // Save previous hint if exists
-                if let Some(mut hint) = current_hint.take() {
-                    hint.content = current_content.trim().to_string();
-                    hints.push(hint);
-                    current_content.clear();
-                }
+    /// Decide what the next hint request reveals to a learner who has seen
+    /// levels up to `revealed` and failed the tests `failed_runs` times.
+    /// Level N needs N - 1 failed test runs, unless the exercise sets
+    /// `auto_unlock` or the learner prefers detailed hints.
+    pub fn next_hint(
+        &self,
+        exercise_id: &str,
+        config: &HintConfig,
+        preference: &HintPreference,
+        revealed: u32,
+        failed_runs: u32,
+    ) -> HintReveal {
+        let max_level = self.max_level(exercise_id, config, preference);
+        let mut reveal = HintReveal {
+            status: HintStatus::Exhausted,
+            hint: None,
+            revealed_level: revealed.min(max_level),
+            max_level,
+            failures_needed: 0,
+        };
 
-                // Start new hint
-                current_hint = Some(Hint {
-                    level,
-                    title: line.trim_start_matches('#').trim().to_string(),
-                    content: String::new(),
-                    rust_book_links: Vec::new(),
-                    code_snippets: Vec::new(),
-                    related_concepts: Vec::new(),
-                });
-            } else if line.starts_with("```rust") {
-                in_code_block = true;
-                current_code_snippet.clear();
-            } else if line.starts_with("```") && in_code_block {
-                in_code_block = false;
-                if let Some(ref mut hint) = current_hint {
-                    hint.code_snippets.push(CodeSnippet {
-                        language: "rust".to_string(),
-                        code: current_code_snippet.clone(),
-                        explanation: "Code example".to_string(),
-                        is_complete: current_code_snippet.contains("fn main()"),
-                    });
-                }
-                current_code_snippet.clear();
-            } else if in_code_block {
-                current_code_snippet.push_str(line);
-                current_code_snippet.push('\n');
-            } else {
-                // Regular content
-                if current_hint.is_some() {
-                    // Look for Rust Book links
-                    if line.contains("https://doc.rust-lang.org/book/") {
-                        if let Some(ref mut hint) = current_hint {
-                            Self::extract_book_links(line, &mut hint.rust_book_links);
-                        }
-                    }
-                    current_content.push_str(line);
-                    current_content.push('\n');
-                }
-            }
+        if matches!(preference, HintPreference::Disabled) {
+            reveal.status = HintStatus::Disabled;
+            return reveal;
         }
 
-        // Don't forget the last hint
-        if let Some(mut hint) = current_hint {
-            hint.content = current_content.trim().to_string();
-            hints.push(hint);
+        let Some(next) = self.exercise_hints.get(exercise_id).and_then(|hints| {
+            hints
+                .iter()
+                .filter(|hint| hint.level.number() > revealed && hint.level.number() <= max_level)
+                .min_by_key(|hint| hint.level.number())
+        }) else {
+            return reveal;
+        };
+
+        let unlocked = config.auto_unlock || matches!(preference, HintPreference::Detailed);
+        let required = next.level.number() - 1;
+        if !unlocked && failed_runs < required {
+            reveal.status = HintStatus::Locked;
+            reveal.failures_needed = required - failed_runs;
+            return reveal;
         }
 
-        Ok(hints)
+        reveal.status = HintStatus::Revealed;
+        reveal.revealed_level = next.level.number();
+        reveal.hint = Some(next.clone());
+        reveal
     }
 
-    /// Parse hint level from markdown header
-    fn parse_hint_level(line: &str) -> Option<HintLevel> {
-        let line = line.trim();
-        if line.starts_with("## Level 1") || line.contains("Conceptual") {
-            Some(HintLevel::Conceptual)
-        } else if line.starts_with("## Level 2") || line.contains("Strategic") {
-            Some(HintLevel::Strategic)
-        } else if line.starts_with("## Level 3") || line.contains("Implementation") {
-            Some(HintLevel::Implementation)
-        } else {
-            None
-        }
+    /// Get all hints for an exercise (for instructors/solutions)
+    pub fn get_all_hints(&self, exercise_id: &str) -> Option<&Vec<Hint>> {
+        self.exercise_hints.get(exercise_id)
     }
 
-    /// Extract Rust Book links from text
-    fn extract_book_links(text: &str, links: &mut Vec<String>) {
-        // Simple regex-like extraction
-        if let Some(start) = text.find("https://doc.rust-lang.org/book/") {
-            if let Some(end) = text[start..].find([' ', ')', '\n', ']']) {
-                let link = &text[start..start + end];
-                links.push(link.to_string());
-            } else {
-                // Link goes to end of line
-                let link = &text[start..];
-                links.push(link.to_string());
-            }
-        }
+    /// Add rules that take precedence over the built-in contextual hints
+    pub fn add_rules(&mut self, rules: HintCatalog) {
+        self.rules.extend(rules);
     }
 
-    /// Generate contextual hints based on error patterns
-    pub fn generate_contextual_hint(&self, _exercise_id: &str, error_message: &str) -> Option<Hint> {
-        // Analyze common error patterns and provide targeted hints
-        if error_message.contains("cannot borrow") {
-            Some(Hint {
-                level: HintLevel::Conceptual,
-                title: "Borrowing Error".to_string(),
-                content: "This error occurs when you're trying to borrow a value in a way that violates Rust's borrowing rules.".to_string(),
-                rust_book_links: vec!["https://doc.rust-lang.org/book/ch04-02-references-and-borrowing.html".to_string()],
-                code_snippets: vec![
-                    CodeSnippet {
-                        language: "rust".to_string(),
-                        code: "// Instead of:\n// let mut x = vec![1, 2, 3];\n// let y = &mut x;\n// let z = &mut x; // Error!\n\n// Do:\nlet mut x = vec![1, 2, 3];\n{\n    let y = &mut x;\n    // use y here\n}\nlet z = &mut x; // OK now".to_string(),
-                        explanation: "Only one mutable reference is allowed at a time".to_string(),
-                        is_complete: true,
-                    }
-                ],
-                related_concepts: vec!["borrowing".to_string(), "references".to_string()],
-            })
-        } else if error_message.contains("move occurs") {
-            Some(Hint {
-                level: HintLevel::Conceptual,
-                title: "Ownership Move".to_string(),
-                content: "This error happens when you try to use a value after it has been moved to another owner.".to_string(),
-                rust_book_links: vec!["https://doc.rust-lang.org/book/ch04-01-what-is-ownership.html".to_string()],
-                code_snippets: vec![
-                    CodeSnippet {
-                        language: "rust".to_string(),
-                        code: "// Instead of moving, try borrowing:\nlet s1 = String::from(\"hello\");\nlet len = calculate_length(&s1); // borrow instead of move\nprintln!(\"{}\", s1); // s1 is still valid".to_string(),
-                        explanation: "Use references to avoid moving ownership".to_string(),
-                        is_complete: false,
-                    }
-                ],
-                related_concepts: vec!["ownership".to_string(), "move-semantics".to_string()],
-            })
-        } else if error_message.contains("type annotations needed") {
-            Some(Hint {
-                level: HintLevel::Strategic,
-                title: "Type Inference".to_string(),
-                content: "Rust can't figure out what type you want. You need to help it with a type annotation.".to_string(),
-                rust_book_links: vec!["https://doc.rust-lang.org/book/ch03-02-data-types.html".to_string()],
-                code_snippets: vec![
-                    CodeSnippet {
-                        language: "rust".to_string(),
-                        code: "// Be explicit about the type:\nlet numbers: Vec<i32> = vec.iter().map(|x| x * 2).collect();\n// or\nlet numbers = vec.iter().map(|x| x * 2).collect::<Vec<i32>>();".to_string(),
-                        explanation: "Specify the type Rust should infer".to_string(),
-                        is_complete: false,
-                    }
-                ],
-                related_concepts: vec!["type-inference".to_string(), "generics".to_string()],
-            })
-        } else {
-            None
-        }
+    /// Hints for the diagnostics and panic messages of a failed build, run
+    /// or check of an exercise
+    pub fn contextual_hints(
+        &self,
+        exercise: &ExerciseMetadata,
+        diagnostics: &[Diagnostic],
+        panics: &[String],
+    ) -> Vec<ContextualHint> {
+        self.rules.suggest(exercise, diagnostics, panics)
     }
 }
 
@@ -267,85 +241,259 @@ impl Default for HintSystem {
 impl HintUsageTracker {
     /// Create a new usage tracker
     pub fn new() -> Self {
-        Self {
-            usage_data: HashMap::new(),
+        Self::default()
+    }
+
+    /// Gather the hint usage of every user in a progress store
+    pub fn from_store(store: &dyn ProgressStore) -> anyhow::Result<Self> {
+        let mut tracker = Self::new();
+        for user_id in store.user_ids()? {
+            tracker.add_progress(&store.load(&user_id)?);
         }
+        Ok(tracker)
     }
 
-    /// Record hint usage
-    pub fn record_usage(&mut self, exercise_id: &str, user_id: &str, level: HintLevel) {
-        let usage = self.usage_data
-            .entry(exercise_id.to_string())
-            .or_insert_with(Vec::new);
-
-        // Find or create usage record for this user
-        if let Some(user_usage) = usage.iter_mut().find(|u| u.user_id == user_id) {
-            user_usage.hints_requested.push(HintRequest {
-                level,
-                requested_at: chrono::Utc::now(),
-                was_helpful: None, // Will be updated by user feedback
-            });
-            user_usage.total_hints_used += 1;
-        } else {
-            // New user
-            usage.push(HintUsage {
-                exercise_id: exercise_id.to_string(),
-                user_id: user_id.to_string(),
-                hints_requested: vec![HintRequest {
-                    level,
-                    requested_at: chrono::Utc::now(),
-                    was_helpful: None,
-                }],
-                total_hints_used: 1,
-                time_before_first_hint: 0, // TODO: Calculate from exercise start time
-                solved_after_hints: false, // TODO: Update when exercise is completed
-            });
+    /// Add one learner's hint usage, and the exercises they started
+    pub fn add_progress(&mut self, progress: &UserProgress) {
+        let mut started: Vec<&str> = progress
+            .active_exercises
+            .keys()
+            .chain(progress.hint_usage.keys())
+            .map(String::as_str)
+            .chain(progress.exercise_history.iter().map(|c| c.exercise_id.as_str()))
+            .collect();
+        started.sort();
+        started.dedup();
+        for exercise_id in started {
+            *self.learners.entry(exercise_id.to_string()).or_insert(0) += 1;
+        }
+
+        for usage in progress.hint_usage.values() {
+            self.usage_data
+                .entry(usage.exercise_id.clone())
+                .or_default()
+                .push(usage.clone());
         }
     }
 
     /// Calculate hint effectiveness for an exercise
     pub fn calculate_effectiveness(&self, exercise_id: &str) -> HintEffectiveness {
-        let usage_data = self.usage_data.get(exercise_id);
-        
-        if let Some(data) = usage_data {
-            let total_users = data.len() as f64;
-            let users_who_used_hints = data.iter().filter(|u| u.total_hints_used > 0).count() as f64;
-            let users_solved_after_hints = data.iter().filter(|u| u.solved_after_hints).count() as f64;
-            
-            let hint_usage_rate = users_who_used_hints / total_users;
-            let success_rate_with_hints = if users_who_used_hints > 0.0 {
-                users_solved_after_hints / users_who_used_hints
+        let data = self.usage_data.get(exercise_id).map_or(&[][..], Vec::as_slice);
+        let learners = self.learners.get(exercise_id).copied().unwrap_or(0).max(data.len() as u32);
+        let users_who_used_hints = data.iter().filter(|u| u.total_hints_used > 0).count() as u32;
+        let users_solved_after_hints = data.iter().filter(|u| u.solved_after_hints).count() as u32;
+
+        let levels: Vec<LevelEffectiveness> = [HintLevel::Conceptual, HintLevel::Strategic, HintLevel::Implementation]
+            .into_iter()
+            .map(|level| LevelEffectiveness::measure(level, data))
+            .filter(|stats| stats.learners > 0)
+            .collect();
+
+        let mut times: Vec<u32> = data.iter().map(|u| u.time_before_first_hint).collect();
+        times.sort_unstable();
+
+        let mut effectiveness = HintEffectiveness {
+            exercise_id: exercise_id.to_string(),
+            learners,
+            hint_usage_rate: rate(users_who_used_hints, learners),
+            success_rate_with_hints: rate(users_solved_after_hints, users_who_used_hints),
+            average_hints_per_user: if users_who_used_hints > 0 {
+                data.iter().map(|u| u.total_hints_used).sum::<u32>() as f64 / users_who_used_hints as f64
             } else {
                 0.0
-            };
+            },
+            median_seconds_to_first_hint: times.get(times.len() / 2).copied(),
+            most_requested_level: levels.iter().max_by_key(|stats| stats.learners).map(|stats| stats.level),
+            levels,
+            improvement_suggestions: Vec::new(),
+        };
+        effectiveness.improvement_suggestions = effectiveness.suggest_improvements();
+        effectiveness
+    }
 
-            let avg_hints_per_user = if users_who_used_hints > 0.0 {
-                data.iter().map(|u| u.total_hints_used).sum::<u32>() as f64 / users_who_used_hints
-            } else {
-                0.0
+    /// Effectiveness of every exercise whose hints were used, by exercise id
+    pub fn report(&self) -> Vec<HintEffectiveness> {
+        let mut exercise_ids: Vec<&String> = self.usage_data.keys().collect();
+        exercise_ids.sort();
+        exercise_ids
+            .into_iter()
+            .map(|exercise_id| self.calculate_effectiveness(exercise_id))
+            .collect()
+    }
+}
+
+impl LevelEffectiveness {
+    fn measure(level: HintLevel, data: &[HintUsage]) -> Self {
+        let mut stats = Self {
+            level,
+            learners: 0,
+            helpful: 0,
+            not_helpful: 0,
+            final_level_for: 0,
+            solved_after: 0,
+        };
+        for usage in data {
+            // Feedback is kept on the latest request of a level
+            let Some(request) = usage.hints_requested.iter().rev().find(|r| r.level == level) else {
+                continue;
             };
+            stats.learners += 1;
+            match request.was_helpful {
+                Some(true) => stats.helpful += 1,
+                Some(false) => stats.not_helpful += 1,
+                None => {}
+            }
+            let highest = usage.hints_requested.iter().map(|r| r.level.number()).max();
+            if highest == Some(level.number()) {
+                stats.final_level_for += 1;
+                if usage.solved_after_hints {
+                    stats.solved_after += 1;
+                }
+            }
+        }
+        stats
+    }
+}
 
-            HintEffectiveness {
-                exercise_id: exercise_id.to_string(),
-                hint_usage_rate,
-                success_rate_with_hints,
-                average_hints_per_user: avg_hints_per_user,
-                most_requested_level: HintLevel::Conceptual, // TODO: Calculate actual
-                improvement_suggestions: vec![
-                    "Consider adding more conceptual hints".to_string(),
-                    "Strategic hints may need more detail".to_string(),
-                ],
+impl HintEffectiveness {
+    /// Point authors at the hints learners rate poorly or that do not get
+    /// them unstuck
+    fn suggest_improvements(&self) -> Vec<String> {
+        let mut suggestions = Vec::new();
+        for stats in &self.levels {
+            let votes = stats.helpful + stats.not_helpful;
+            if votes >= MIN_SAMPLE && stats.not_helpful > stats.helpful {
+                suggestions.push(format!(
+                    "Level {} was rated unhelpful by {} of {} learners; consider rewording it",
+                    stats.level.number(),
+                    stats.not_helpful,
+                    votes
+                ));
             }
-        } else {
-            HintEffectiveness {
-                exercise_id: exercise_id.to_string(),
-                hint_usage_rate: 0.0,
-                success_rate_with_hints: 0.0,
-                average_hints_per_user: 0.0,
-                most_requested_level: HintLevel::Conceptual,
-                improvement_suggestions: vec!["No usage data available".to_string()],
+            if stats.final_level_for >= MIN_SAMPLE && rate(stats.solved_after, stats.final_level_for) < 0.5 {
+                suggestions.push(format!(
+                    "Only {} of {} learners who stopped at level {} solved the exercise; it may not be enough to get unstuck",
+                    stats.solved_after,
+                    stats.final_level_for,
+                    stats.level.number()
+                ));
             }
         }
+
+        let users_who_used_hints = self.levels.iter().map(|stats| stats.learners).max().unwrap_or(0);
+        let needed_implementation = self
+            .levels
+            .iter()
+            .find(|stats| stats.level == HintLevel::Implementation)
+            .map_or(0, |stats| stats.learners);
+        if users_who_used_hints >= MIN_SAMPLE && rate(needed_implementation, users_who_used_hints) >= 0.5 {
+            suggestions.push(format!(
+                "{} of {} learners who used hints needed the implementation hint; earlier levels may be too vague",
+                needed_implementation, users_who_used_hints
+            ));
+        }
+        if self.learners >= MIN_SAMPLE && self.hint_usage_rate >= 0.8 {
+            suggestions.push(format!(
+                "{:.0}% of learners asked for hints; the instructions may be unclear",
+                self.hint_usage_rate * 100.0
+            ));
+        }
+        suggestions
+    }
+}
+
+fn rate(part: u32, whole: u32) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 / whole as f64
+    }
+}
+
+/// Parse a hints.md file. Every `## Level N` section (N from 1 to 3) is
+/// one hint; another `##` heading ends it, and the file's `#` title and
+/// anything before the first level are left out. A level written twice
+/// keeps its first section.
+pub fn parse_hints(content: &str) -> Vec<Hint> {
+    let mut hints: Vec<Hint> = Vec::new();
+    let mut current: Option<Hint> = None;
+    let mut code_block: Option<(String, String)> = None; // language, code
+
+    for line in content.lines() {
+        let fence = line.trim_start().strip_prefix("```");
+
+        if let Some((language, code)) = code_block.as_mut() {
+            if fence.is_some() {
+                if let Some(hint) = current.as_mut() {
+                    hint.code_snippets.push(CodeSnippet {
+                        is_complete: language == "rust" && code.contains("fn main("),
+                        language: std::mem::take(language),
+                        code: std::mem::take(code),
+                        explanation: String::new(),
+                    });
+                }
+                code_block = None;
+            } else {
+                code.push_str(line);
+                code.push('\n');
+            }
+        } else if let Some(info) = fence {
+            let language = info.split_whitespace().next().unwrap_or("text");
+            code_block = Some((language.to_string(), String::new()));
+        } else if line.starts_with("## ") {
+            hints.extend(current.take().map(finish_hint));
+            current = parse_level_heading(line)
+                .filter(|(level, _)| hints.iter().all(|hint| hint.level != *level))
+                .map(|(level, title)| Hint {
+                    level,
+                    title,
+                    content: String::new(),
+                    rust_book_links: Vec::new(),
+                    code_snippets: Vec::new(),
+                    related_concepts: Vec::new(),
+                });
+            continue;
+        } else if let Some(hint) = current.as_mut() {
+            extract_book_links(line, &mut hint.rust_book_links);
+        }
+
+        if let Some(hint) = current.as_mut() {
+            hint.content.push_str(line);
+            hint.content.push('\n');
+        }
+    }
+
+    hints.extend(current.map(finish_hint));
+    hints
+}
+
+/// The level and title of a `## Level N: Title` heading
+fn parse_level_heading(line: &str) -> Option<(HintLevel, String)> {
+    let rest = line.strip_prefix("## ")?.trim().strip_prefix("Level")?.trim_start();
+    let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let level = HintLevel::from_number(rest[..digits].parse().ok()?)?;
+    let after = &rest[digits..];
+    if !(after.is_empty() || after.starts_with([':', ' ', '-'])) {
+        return None;
+    }
+    Some((level, line.trim_start_matches('#').trim().to_string()))
+}
+
+fn finish_hint(mut hint: Hint) -> Hint {
+    hint.content = hint.content.trim().to_string();
+    hint
+}
+
+/// Collect the Rust Book links on a line
+fn extract_book_links(text: &str, links: &mut Vec<String>) {
+    let mut rest = text;
+    while let Some(start) = rest.find(BOOK_URL_PREFIX) {
+        let link = &rest[start..];
+        let end = link.find([' ', ')', ']', '>', '"']).unwrap_or(link.len());
+        if !links.iter().any(|known| known == &link[..end]) {
+            links.push(link[..end].to_string());
+        }
+        rest = &link[end..];
     }
 }
 
@@ -353,13 +501,27 @@ impl HintUsageTracker {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HintEffectiveness {
     pub exercise_id: String,
-    pub hint_usage_rate: f64, // Percentage of users who use hints
-    pub success_rate_with_hints: f64, // Success rate for users who used hints
+    pub learners: u32,                // learners who started the exercise
+    pub hint_usage_rate: f64,         // share of those learners who used hints
+    pub success_rate_with_hints: f64, // share of hint users who then solved it
     pub average_hints_per_user: f64,
-    pub most_requested_level: HintLevel,
+    pub median_seconds_to_first_hint: Option<u32>,
+    pub most_requested_level: Option<HintLevel>,
+    pub levels: Vec<LevelEffectiveness>,
     pub improvement_suggestions: Vec<String>,
 }
 
+/// How one hint level of an exercise fared
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LevelEffectiveness {
+    pub level: HintLevel,
+    pub learners: u32,        // learners who revealed it
+    pub helpful: u32,         // of those, learners who rated it helpful
+    pub not_helpful: u32,     // ... and unhelpful
+    pub final_level_for: u32, // learners for whom it was the last level revealed
+    pub solved_after: u32,    // of those, learners who then solved the exercise
+}
+
 /// Helper functions for hint formatting
 impl Hint {
     /// Format hint for display in terminal
@@ -387,10 +549,11 @@ impl Hint {
             }
         }
         
-        if !self.code_snippets.is_empty() {
+        // Hints parsed from hints.md carry their code in the content already
+        if !self.code_snippets.is_empty() && !self.content.contains("```") {
             formatted.push_str("\n💻 Code Example:\n");
             for snippet in &self.code_snippets {
-                formatted.push_str("```rust\n");
+                formatted.push_str(&format!("```{}\n", snippet.language));
                 formatted.push_str(&snippet.code);
                 formatted.push_str("\n```\n");
                 if !snippet.explanation.is_empty() {
@@ -398,7 +561,6 @@ impl Hint {
                 }
             }
         }
-        
         formatted
     }
 
@@ -0,0 +1,195 @@
+use crate::exercise::Exercise;
+use crate::metadata::{is_metadata_file, ExerciseMetadata};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// In-memory index of the exercises on disk, so lookups by id, directory
+/// or chapter do not walk and parse the tree.
+///
+/// Built once with [`ExerciseIndex::build`] and kept current by passing
+/// changed paths to [`ExerciseIndex::refresh`]. Paths are relative to the
+/// exercises root, e.g. `ch01_getting_started/ex01_hello_world`.
+#[derive(Debug, Clone)]
+pub struct ExerciseIndex {
+    exercises_root: PathBuf,
+    exercises: Vec<(ExerciseMetadata, PathBuf)>, // course order
+    by_id: HashMap<String, usize>,               // first exercise with each id
+    by_path: HashMap<PathBuf, usize>,
+}
+
+impl ExerciseIndex {
+    /// Scan the exercises root
+    pub fn build<P: AsRef<Path>>(exercises_root: P) -> Result<Self> {
+        let exercises_root = exercises_root.as_ref().to_path_buf();
+        let exercises = Exercise::list_all_with_paths(&exercises_root)?
+            .into_iter()
+            .map(|(metadata, path)| {
+                let relative = path
+                    .strip_prefix(&exercises_root)
+                    .map(Path::to_path_buf)
+                    .unwrap_or(path);
+                (metadata, relative)
+            })
+            .collect();
+
+        let mut index = Self {
+            exercises_root,
+            exercises,
+            by_id: HashMap::new(),
+            by_path: HashMap::new(),
+        };
+        index.reindex();
+        Ok(index)
+    }
+
+    /// Root directory containing the chapter folders
+    pub fn exercises_root(&self) -> &Path {
+        &self.exercises_root
+    }
+
+    /// Every exercise with its directory, in course order
+    pub fn exercises(&self) -> &[(ExerciseMetadata, PathBuf)] {
+        &self.exercises
+    }
+
+    pub fn len(&self) -> usize {
+        self.exercises.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.exercises.is_empty()
+    }
+
+    /// Metadata of an exercise by id
+    pub fn get(&self, exercise_id: &str) -> Option<&ExerciseMetadata> {
+        self.by_id.get(exercise_id).map(|&i| &self.exercises[i].0)
+    }
+
+    /// Directory of an exercise by id
+    pub fn path_of(&self, exercise_id: &str) -> Option<&Path> {
+        self.by_id.get(exercise_id).map(|&i| self.exercises[i].1.as_path())
+    }
+
+    /// Metadata of the exercise in a directory
+    pub fn at<P: AsRef<Path>>(&self, relative_path: P) -> Option<&ExerciseMetadata> {
+        self.by_path.get(relative_path.as_ref()).map(|&i| &self.exercises[i].0)
+    }
+
+    /// Exercises of one chapter, in order
+    pub fn chapter(&self, chapter: u32) -> impl Iterator<Item = &ExerciseMetadata> {
+        self.exercises
+            .iter()
+            .map(|(metadata, _)| metadata)
+            .skip_while(move |metadata| metadata.chapter < chapter)
+            .take_while(move |metadata| metadata.chapter == chapter)
+    }
+
+    /// Chapter numbers with at least one exercise, in order
+    pub fn chapters(&self) -> Vec<u32> {
+        let mut chapters: Vec<u32> = self.exercises.iter().map(|(metadata, _)| metadata.chapter).collect();
+        chapters.dedup();
+        chapters
+    }
+
+    /// Whether `changed` (an absolute path under the exercises root) being
+    /// created, modified or removed may add, remove or change an exercise
+    pub fn affected_by(&self, changed: &Path) -> bool {
+        !self.affected_dirs(changed).is_empty()
+    }
+
+    /// Bring the index up to date after `changed` (an absolute path under
+    /// the exercises root) was created, modified or removed. Returns
+    /// whether any exercise was added, removed or changed.
+    pub fn refresh(&mut self, changed: &Path) -> bool {
+        let mut changed_any = false;
+        for dir in self.affected_dirs(changed) {
+            changed_any |= self.reload(&dir);
+        }
+        if changed_any {
+            self.reindex();
+        }
+        changed_any
+    }
+
+    /// Exercise directories, relative to the exercises root, to re-read
+    /// after `changed` was created, modified or removed
+    fn affected_dirs(&self, changed: &Path) -> Vec<PathBuf> {
+        let Ok(relative) = changed.strip_prefix(&self.exercises_root) else {
+            return Vec::new();
+        };
+        if relative.components().any(|component| component.as_os_str() == "target") {
+            return Vec::new();
+        }
+
+        // A metadata file affects its own exercise; a directory that
+        // appeared, or anything that disappeared, may add or remove
+        // exercises below it. Other files do not change the index.
+        let mut dirs: Vec<PathBuf> = Vec::new();
+        if is_metadata_file(changed) {
+            dirs.push(relative.parent().unwrap_or(Path::new("")).to_path_buf());
+        } else if changed.is_dir() || !changed.exists() {
+            dirs.extend(
+                self.exercises
+                    .iter()
+                    .map(|(_, path)| path)
+                    .filter(|path| path.starts_with(relative))
+                    .cloned(),
+            );
+            if changed.is_dir() {
+                dirs.extend(
+                    WalkDir::new(changed)
+                        .into_iter()
+                        .filter_entry(|entry| entry.file_name() != "target")
+                        .filter_map(|entry| entry.ok())
+                        .filter(|entry| is_metadata_file(entry.path()))
+                        .filter_map(|entry| {
+                            let dir = entry.path().parent()?;
+                            dir.strip_prefix(&self.exercises_root).ok().map(Path::to_path_buf)
+                        }),
+                );
+            }
+        }
+        dirs.sort();
+        dirs.dedup();
+        dirs
+    }
+
+    /// Re-read one exercise directory; exercises whose metadata is gone or
+    /// no longer loads are dropped, as a full scan would
+    fn reload(&mut self, relative_path: &Path) -> bool {
+        let loaded = ExerciseMetadata::load_from_dir(&self.exercises_root.join(relative_path)).ok();
+        let position = self.exercises.iter().position(|(_, path)| path == relative_path);
+
+        match (position, loaded) {
+            (Some(i), Some(metadata)) => {
+                self.exercises[i].0 = metadata;
+                true
+            }
+            (Some(i), None) => {
+                self.exercises.remove(i);
+                true
+            }
+            (None, Some(metadata)) => {
+                self.exercises.push((metadata, relative_path.to_path_buf()));
+                true
+            }
+            (None, None) => false,
+        }
+    }
+
+    /// Restore course order and rebuild the lookup tables
+    fn reindex(&mut self) {
+        self.exercises.sort_by(|(a, a_path), (b, b_path)| {
+            (a.chapter, a.exercise_number, a_path).cmp(&(b.chapter, b.exercise_number, b_path))
+        });
+
+        self.by_id.clear();
+        self.by_path.clear();
+        for (i, (metadata, path)) in self.exercises.iter().enumerate() {
+            self.by_id.entry(metadata.id.clone()).or_insert(i);
+            self.by_path.insert(path.clone(), i);
+        }
+    }
+}
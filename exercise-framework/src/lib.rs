@@ -1,62 +1,268 @@
+pub mod achievements;
+pub mod activity;
+pub mod book;
+pub mod curriculum;
+pub mod diagnostics;
 pub mod exercise;
+pub mod files;
+pub mod libtest;
+pub mod mastery;
 pub mod metadata;
 pub mod progress;
+pub mod progress_store;
+pub mod recommendations;
+pub mod sandbox;
+pub mod scaffold;
+pub mod snapshots;
 pub mod testing;
 pub mod validation;
+pub mod verification;
 pub mod hints;
+pub mod hint_rules;
+pub mod index;
+pub mod workspace;
 
 // Re-export main types
+pub use achievements::AchievementCatalog;
+pub use curriculum::{CurriculumReport, CurriculumValidator};
 pub use exercise::{Exercise, ExerciseType, ExerciseDifficulty};
+pub use files::{ExerciseFiles, FileError};
+pub use index::ExerciseIndex;
 pub use metadata::ExerciseMetadata;
-pub use progress::{ProgressTracker, UserProgress};
+pub use progress::{Achievement, ProgressTracker, SessionStats, UserProgress, DEFAULT_USER};
+pub use progress_store::{HistoryQuery, JsonProgressStore, ProgressStore};
+pub use recommendations::{PrerequisiteGraph, Recommendation};
+pub use mastery::ReviewItem;
+pub use sandbox::{Sandbox, SandboxLimits};
+pub use scaffold::ExerciseScaffold;
+pub use snapshots::{Snapshot, SnapshotStore};
 pub use testing::{TestRunner, TestResult};
 pub use validation::{ExerciseValidator, ValidationResult};
-pub use hints::{HintSystem, HintLevel};
+pub use verification::SolutionVerifier;
+pub use hints::{
+    Hint, HintEffectiveness, HintLevel, HintReveal, HintStatus, HintSystem, HintUsageTracker,
+    LevelEffectiveness,
+};
+pub use hint_rules::{ContextualHint, HintCatalog};
+pub use workspace::WorkspaceManager;
 
 use anyhow::Result;
+use progress::ExerciseCompletion;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
+use std::sync::{Arc, Mutex};
 
 /// Main entry point for the exercise framework
 pub struct Framework {
     exercises_root: std::path::PathBuf,
-    progress_tracker: ProgressTracker,
+    index: ExerciseIndex,
+    progress_store: Arc<dyn ProgressStore>,
+    chapter_totals: Arc<HashMap<u32, u32>>,
+    prerequisites: Arc<PrerequisiteGraph>, // shared by every user's progress tracker
+    synced_users: Mutex<HashSet<String>>, // users whose stored totals match the exercises on disk
+    workspaces: WorkspaceManager,
+    snapshots: SnapshotStore,
+    hints: HintSystem, // refreshed from hints.md whenever an exercise's hints are asked for
+    achievements: Arc<AchievementCatalog>,
     test_runner: TestRunner,
 }
 
 impl Framework {
-    /// Create a new framework instance
+    /// Create a new framework instance, keeping progress in JSON files
     pub fn new<P: AsRef<Path>>(exercises_root: P) -> Result<Self> {
+        let store = JsonProgressStore::for_exercises(exercises_root.as_ref());
+        Self::with_progress_store(exercises_root, Arc::new(store))
+    }
+
+    /// Create a framework instance that keeps progress in `progress_store`
+    pub fn with_progress_store<P: AsRef<Path>>(
+        exercises_root: P,
+        progress_store: Arc<dyn ProgressStore>,
+    ) -> Result<Self> {
         let exercises_root = exercises_root.as_ref().to_path_buf();
         
-        Ok(Self {
+        let mut framework = Self {
             exercises_root: exercises_root.clone(),
-            progress_tracker: ProgressTracker::new(&exercises_root)?,
+            index: ExerciseIndex::build(&exercises_root)?,
+            progress_store,
+            chapter_totals: Arc::default(),
+            prerequisites: Arc::default(),
+            synced_users: Mutex::new(HashSet::new()),
+            workspaces: WorkspaceManager::new(&exercises_root),
+            snapshots: SnapshotStore::new(&exercises_root),
+            hints: HintSystem::new(),
+            achievements: Arc::new(AchievementCatalog::builtin()),
             test_runner: TestRunner::new(),
-        })
+        };
+        framework.refresh_exercise_totals()?;
+
+        Ok(framework)
+    }
+
+    /// Root directory containing the chapter folders
+    pub fn exercises_root(&self) -> &Path {
+        &self.exercises_root
     }
 
     /// Load an exercise by ID
     pub fn load_exercise(&self, exercise_id: &str) -> Result<Exercise> {
-        Exercise::load(&self.exercises_root, exercise_id)
+        let path = self
+            .index
+            .path_of(exercise_id)
+            .ok_or_else(|| anyhow::anyhow!("Exercise not found: {}", exercise_id))?;
+        Exercise::load_from_path(self.exercises_root.join(path))
+    }
+
+    /// Load an exercise by its directory relative to the exercises root
+    pub fn load_exercise_at<P: AsRef<Path>>(&self, relative_path: P) -> Result<Exercise> {
+        Exercise::load_from_path(self.exercises_root.join(relative_path))
+    }
+
+    /// Load an exercise as a user sees it, from their workspace copy if they have one
+    pub fn load_user_exercise(&self, user_id: &str, exercise_id: &str) -> Result<Exercise> {
+        let relative = self
+            .index
+            .path_of(exercise_id)
+            .ok_or_else(|| anyhow::anyhow!("Exercise not found: {}", exercise_id))?;
+        self.load_user_exercise_at(user_id, relative)
+    }
+
+    /// Like [`Framework::load_user_exercise`], by directory relative to the exercises root
+    pub fn load_user_exercise_at<P: AsRef<Path>>(&self, user_id: &str, relative_path: P) -> Result<Exercise> {
+        Exercise::load_from_path(self.user_exercise_dir(user_id, relative_path)?)
+    }
+
+    /// A user's copy of an exercise, relative to the exercises root, made
+    /// from the exercise's starter on first use
+    pub fn user_exercise_dir<P: AsRef<Path>>(&self, user_id: &str, relative_path: P) -> Result<std::path::PathBuf> {
+        let relative_path = relative_path.as_ref();
+        let metadata = self
+            .index
+            .at(relative_path)
+            .ok_or_else(|| anyhow::anyhow!("Exercise not found: {:?}", relative_path))?;
+        if self.workspaces.has_copy(user_id, relative_path) {
+            return self.workspaces.user_root(user_id).map(|root| root.join(relative_path));
+        }
+
+        let shared = ExerciseFiles::new(self.exercises_root.join(relative_path), metadata);
+        self.snapshots.ensure_starter(relative_path, &shared)?;
+        let starter = self
+            .snapshots
+            .starter(relative_path)?
+            .ok_or_else(|| anyhow::anyhow!("No starter code for {:?}", relative_path))?;
+        self.workspaces.exercise_dir(user_id, relative_path, metadata, &starter.files)
+    }
+
+    /// Per-user exercise workspaces
+    pub fn workspaces(&self) -> &WorkspaceManager {
+        &self.workspaces
+    }
+
+    /// Starter code and per-user snapshots of exercise code
+    pub fn snapshots(&self) -> &SnapshotStore {
+        &self.snapshots
+    }
+
+    /// Keep the starter of every exercise that has none yet, before any
+    /// learner can change it. Returns how many were written.
+    ///
+    /// Earlier releases saved the local learner's code into the shared
+    /// tree; where it differs from the starter it becomes their workspace.
+    pub fn capture_starters(&self) -> Result<usize> {
+        let mut captured = 0;
+        for (metadata, path) in self.index.exercises() {
+            let shared = ExerciseFiles::new(self.exercises_root.join(path), metadata);
+            if self.snapshots.ensure_starter(path, &shared)? {
+                captured += 1;
+            }
+
+            if self.workspaces.has_copy(DEFAULT_USER, path) {
+                continue;
+            }
+            let starter = self.snapshots.starter(path)?.map(|starter| starter.files);
+            if starter.as_ref() != Some(&shared.contents()?) {
+                self.workspaces.adopt_shared(DEFAULT_USER, path)?;
+            }
+        }
+        Ok(captured)
+    }
+
+    /// Exercises on disk, indexed by id, directory and chapter
+    pub fn exercise_index(&self) -> &ExerciseIndex {
+        &self.index
     }
 
     /// List all available exercises
     pub fn list_exercises(&self) -> Result<Vec<ExerciseMetadata>> {
-        Exercise::list_all(&self.exercises_root)
+        Ok(self.index.exercises().iter().map(|(metadata, _)| metadata.clone()).collect())
+    }
+
+    /// List all available exercises with their directories relative to the exercises root
+    pub fn list_exercises_with_paths(&self) -> Result<Vec<(ExerciseMetadata, std::path::PathBuf)>> {
+        Ok(self.index.exercises().to_vec())
+    }
+
+    /// Whether `changed` being created, modified or removed would change
+    /// the exercise index
+    pub fn affects_exercise_index(&self, changed: &Path) -> bool {
+        self.index.affected_by(changed)
+    }
+
+    /// Update the exercise index after `changed` was created, modified or
+    /// removed, recounting progress totals if the set of exercises changed
+    pub fn refresh_exercise_index(&mut self, changed: &Path) -> Result<bool> {
+        if !self.index.refresh(changed) {
+            return Ok(false);
+        }
+        self.refresh_exercise_totals()?;
+        Ok(true)
+    }
+
+    /// Recount exercises so progress percentages stay accurate; each
+    /// user's stored totals are brought up to date on their next visit
+    pub fn refresh_exercise_totals(&mut self) -> Result<()> {
+        let exercises = self.list_exercises()?;
+        let mut chapter_totals = HashMap::new();
+        for exercise in &exercises {
+            *chapter_totals.entry(exercise.chapter).or_insert(0) += 1;
+        }
+        self.chapter_totals = Arc::new(chapter_totals);
+        self.prerequisites = Arc::new(PrerequisiteGraph::new(&exercises));
+        self.synced_users.get_mut().unwrap_or_else(|e| e.into_inner()).clear();
+        Ok(())
+    }
+
+    /// Award achievements from a definitions file instead of the built-in
+    /// ones; past progress earns them with the learner's next activity.
+    /// Returns how many are defined.
+    pub fn load_achievements<P: AsRef<Path>>(&mut self, path: P) -> Result<usize> {
+        self.achievements = Arc::new(AchievementCatalog::load(path)?);
+        Ok(self.achievements.len())
+    }
+
+    /// Progress tracker for a user, syncing their stored totals the first
+    /// time they are seen since the exercises last changed
+    fn progress_tracker(&self, user_id: &str) -> Result<ProgressTracker> {
+        let mut tracker = ProgressTracker::with_store(self.progress_store.clone(), user_id)?;
+        tracker.set_achievements(self.achievements.clone());
+        tracker.set_curriculum(self.chapter_totals.clone(), self.prerequisites.clone());
+
+        let mut synced_users = self.synced_users.lock().unwrap_or_else(|e| e.into_inner());
+        if !synced_users.contains(user_id) {
+            tracker.sync_stored_totals()?;
+            synced_users.insert(user_id.to_string());
+        }
+        Ok(tracker)
     }
 
     /// Get exercises for a specific chapter
     pub fn list_chapter_exercises(&self, chapter: u32) -> Result<Vec<ExerciseMetadata>> {
-        self.list_exercises()?
-            .into_iter()
-            .filter(|ex| ex.chapter == chapter)
-            .collect::<Vec<_>>()
-            .pipe(Ok)
+        Ok(self.index.chapter(chapter).cloned().collect())
     }
 
-    /// Run tests for an exercise
-    pub fn test_exercise(&self, exercise_id: &str) -> Result<TestResult> {
-        let exercise = self.load_exercise(exercise_id)?;
+    /// Run tests for an exercise against the user's code
+    pub fn test_exercise(&self, user_id: &str, exercise_id: &str) -> Result<TestResult> {
+        let exercise = self.load_user_exercise(user_id, exercise_id)?;
         self.test_runner.run_tests(&exercise)
     }
 
@@ -67,31 +273,164 @@ impl Framework {
         validator.validate(&exercise)
     }
 
+    /// Validate every exercise and the curriculum they form together
+    pub fn validate_curriculum(&self) -> Result<CurriculumReport> {
+        CurriculumValidator::new().validate(&self.exercises_root)
+    }
+
     /// Get user's progress
-    pub fn get_progress(&self) -> Result<UserProgress> {
-        self.progress_tracker.get_progress()
+    pub fn get_progress(&self, user_id: &str) -> Result<UserProgress> {
+        self.progress_tracker(user_id)?.get_progress()
     }
 
-    /// Update progress for completed exercise
-    pub fn complete_exercise(&mut self, exercise_id: &str, time_taken_minutes: u32) -> Result<()> {
-        self.progress_tracker.complete_exercise(exercise_id, time_taken_minutes)
+    /// Get the user's completed exercises matching a query, most recent first
+    pub fn progress_history(&self, user_id: &str, query: &HistoryQuery) -> Result<Vec<ExerciseCompletion>> {
+        self.progress_tracker(user_id)?.history(query)
     }
-}
 
-// Helper trait for functional programming style
-trait Pipe<T> {
-    fn pipe<F, R>(self, f: F) -> R
-    where
-        Self: Sized,
-        F: FnOnce(Self) -> R;
-}
+    /// Rank the exercises a user should do next
+    pub fn recommendations(&self, user_id: &str, limit: usize) -> Result<Vec<Recommendation>> {
+        self.progress_tracker(user_id)?.get_recommendations(limit)
+    }
+
+    /// Completed exercises a user should redo because their concepts are due for review
+    pub fn review_queue(&self, user_id: &str) -> Result<Vec<ReviewItem>> {
+        self.progress_tracker(user_id)?.review_queue()
+    }
+
+    /// Record whether a user's test run for an exercise passed; returns the
+    /// achievements it earned
+    pub fn record_test_run(&self, user_id: &str, exercise_id: &str, passed: bool) -> Result<Vec<Achievement>> {
+        self.progress_tracker(user_id)?.record_test_run(exercise_id, passed)
+    }
+
+    /// Check whether a user has completed every prerequisite of an exercise
+    pub fn is_exercise_unlocked(&self, user_id: &str, exercise_id: &str) -> Result<bool> {
+        self.progress_tracker(user_id)?.is_exercise_unlocked(exercise_id)
+    }
+
+    /// Update progress for completed exercise; returns the achievements it earned
+    pub fn complete_exercise(
+        &self,
+        user_id: &str,
+        exercise_id: &str,
+        time_taken_minutes: u32,
+    ) -> Result<Vec<Achievement>> {
+        match self.load_exercise(exercise_id) {
+            Ok(exercise) => self.progress_tracker(user_id)?
+                .complete_exercise_with_metadata(&exercise.metadata, time_taken_minutes),
+            Err(_) => self.progress_tracker(user_id)?.complete_exercise(exercise_id, time_taken_minutes),
+        }
+    }
+
+    /// Record that an exercise was opened; returns the achievements it earned
+    pub fn record_exercise_view(&self, user_id: &str, exercise_id: &str) -> Result<Vec<Achievement>> {
+        self.progress_tracker(user_id)?.record_view(exercise_id)
+    }
+
+    /// Record that a user saved code, as activity for their streak; returns
+    /// the achievements it earned
+    pub fn record_code_save(&self, user_id: &str) -> Result<Vec<Achievement>> {
+        self.progress_tracker(user_id)?.record_save()
+    }
+
+    /// Set the IANA timezone a user's days and streaks are counted in
+    pub fn set_timezone(&self, user_id: &str, timezone: &str) -> Result<()> {
+        self.progress_tracker(user_id)?.set_timezone(timezone)
+    }
 
-impl<T> Pipe<T> for T {
-    fn pipe<F, R>(self, f: F) -> R
-    where
-        Self: Sized,
-        F: FnOnce(Self) -> R,
-    {
-        f(self)
+    /// Record that a hint level was revealed for an exercise
+    pub fn record_hint_usage(&self, user_id: &str, exercise_id: &str, hint_level: u32) -> Result<()> {
+        self.progress_tracker(user_id)?.record_hint(exercise_id, hint_level)
     }
-}
\ No newline at end of file
+
+    /// Reveal the next hint level of an exercise to a user, as far as the
+    /// exercise's hint settings, the user's hint preference and their
+    /// failed test runs allow, and record it in their progress
+    pub fn reveal_next_hint(&mut self, user_id: &str, exercise_id: &str) -> Result<HintReveal> {
+        let config = self.load_exercise_hints(exercise_id)?;
+        let progress = self.get_progress(user_id)?;
+        let attempt = progress.active_exercises.get(exercise_id);
+        let revealed = attempt.and_then(|a| a.hints_used.iter().max().copied()).unwrap_or(0);
+        let failed_runs = attempt.map_or(0, |a| a.test_failures);
+
+        let reveal = self.hints.next_hint(
+            exercise_id,
+            &config,
+            &progress.preferences.hint_usage,
+            revealed,
+            failed_runs,
+        );
+        if let Some(hint) = &reveal.hint {
+            self.record_hint_usage(user_id, exercise_id, hint.level.number())?;
+        }
+        Ok(reveal)
+    }
+
+    /// Record whether a revealed hint level helped a user; returns whether
+    /// they had revealed that level
+    pub fn record_hint_feedback(
+        &self,
+        user_id: &str,
+        exercise_id: &str,
+        hint_level: u32,
+        helpful: bool,
+    ) -> Result<bool> {
+        self.progress_tracker(user_id)?
+            .record_hint_feedback(exercise_id, hint_level, helpful)
+    }
+
+    /// Hint effectiveness of every exercise, from the progress of every user
+    pub fn hint_report(&self) -> Result<Vec<HintEffectiveness>> {
+        Ok(HintUsageTracker::from_store(self.progress_store.as_ref())?.report())
+    }
+
+    /// The hints of an exercise a user has revealed so far, by level
+    pub fn revealed_hints(&mut self, user_id: &str, exercise_id: &str) -> Result<Vec<Hint>> {
+        let config = self.load_exercise_hints(exercise_id)?;
+        let progress = self.get_progress(user_id)?;
+        let revealed = progress
+            .active_exercises
+            .get(exercise_id)
+            .and_then(|a| a.hints_used.iter().max().copied())
+            .unwrap_or(0)
+            .min(self.hints.max_level(exercise_id, &config, &progress.preferences.hint_usage));
+
+        let mut hints: Vec<Hint> = self
+            .hints
+            .get_all_hints(exercise_id)
+            .into_iter()
+            .flatten()
+            .filter(|hint| hint.level.number() <= revealed)
+            .cloned()
+            .collect();
+        hints.sort_by_key(|hint| hint.level.number());
+        Ok(hints)
+    }
+
+    /// Load extra contextual hint rules from a rule file or directory; they
+    /// take precedence over the built-in ones. Returns how many were added.
+    pub fn add_hint_rules<P: AsRef<Path>>(&mut self, path: P) -> Result<usize> {
+        let rules = HintCatalog::load(path)?;
+        let added = rules.len();
+        self.hints.add_rules(rules);
+        Ok(added)
+    }
+
+    /// Contextual hints for what went wrong in a build, run or check of an exercise
+    pub fn contextual_hints(
+        &self,
+        exercise: &ExerciseMetadata,
+        diagnostics: &[diagnostics::Diagnostic],
+        panics: &[String],
+    ) -> Vec<ContextualHint> {
+        self.hints.contextual_hints(exercise, diagnostics, panics)
+    }
+
+    /// Parse an exercise's hints.md into the hint system; returns its hint settings
+    fn load_exercise_hints(&mut self, exercise_id: &str) -> Result<metadata::HintConfig> {
+        let exercise = self.load_exercise(exercise_id)?;
+        self.hints.set_exercise_hints(exercise_id, exercise.hints);
+        Ok(exercise.metadata.hints)
+    }
+}
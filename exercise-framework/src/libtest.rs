@@ -0,0 +1,227 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Outcome of a single libtest test case
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TestStatus {
+    Passed,
+    Failed,
+    Ignored,
+}
+
+/// One test case reported by a libtest harness
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestCase {
+    pub suite: String,   // test binary, e.g. "unit_tests"
+    pub name: String,
+    pub status: TestStatus,
+    pub stdout: String,  // output captured by the harness
+    pub panic_message: Option<String>,
+    pub duration: Duration,
+}
+
+/// Raw event emitted by `--format json`
+#[derive(Debug, Deserialize)]
+struct JsonEvent {
+    #[serde(rename = "type")]
+    kind: String,
+    event: String,
+    name: Option<String>,
+    stdout: Option<String>,
+    message: Option<String>,
+    exec_time: Option<f64>,
+}
+
+/// Parse `cargo test -- -Z unstable-options --format json` output.
+///
+/// `stderr` is used to recover suite names from cargo's "Running" lines, which
+/// are printed once per test binary in the same order as the suite events.
+/// Returns `None` when stdout contains no JSON events at all.
+pub fn parse_json_output(stdout: &str, stderr: &str) -> Option<Vec<TestCase>> {
+    let suites = suite_names(stderr);
+    let mut suite_index: Option<usize> = None;
+    let mut saw_event = false;
+    let mut cases = Vec::new();
+
+    for line in stdout.lines() {
+        let Ok(event) = serde_json::from_str::<JsonEvent>(line.trim()) else {
+            continue;
+        };
+        saw_event = true;
+
+        match (event.kind.as_str(), event.event.as_str()) {
+            ("suite", "started") => {
+                suite_index = Some(suite_index.map_or(0, |i| i + 1));
+            }
+            ("test", "ok" | "failed" | "ignored") => {
+                let status = match event.event.as_str() {
+                    "ok" => TestStatus::Passed,
+                    "failed" => TestStatus::Failed,
+                    _ => TestStatus::Ignored,
+                };
+                let stdout = event.stdout.unwrap_or_default();
+                let panic_message = if status == TestStatus::Failed {
+                    extract_panic_message(&stdout).or(event.message)
+                } else {
+                    None
+                };
+
+                cases.push(TestCase {
+                    suite: suite_index
+                        .and_then(|i| suites.get(i).cloned())
+                        .unwrap_or_default(),
+                    name: event.name.unwrap_or_default(),
+                    status,
+                    stdout,
+                    panic_message,
+                    duration: event
+                        .exec_time
+                        .map(Duration::from_secs_f64)
+                        .unwrap_or_default(),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    saw_event.then_some(cases)
+}
+
+/// Parse the default human-readable libtest output.
+///
+/// Stable libtest does not report per-test timings, so durations are zero.
+pub fn parse_human_output(stdout: &str, stderr: &str) -> Vec<TestCase> {
+    let suites = suite_names(stderr);
+    let mut suite_index: Option<usize> = None;
+    let mut cases: Vec<TestCase> = Vec::new();
+    let mut lines = stdout.lines().peekable();
+
+    while let Some(line) = lines.next() {
+        if line.starts_with("running ") && (line.ends_with(" test") || line.ends_with(" tests")) {
+            suite_index = Some(suite_index.map_or(0, |i| i + 1));
+            continue;
+        }
+
+        if let Some(rest) = line.strip_prefix("test ") {
+            if let Some((name, outcome)) = rest.split_once(" ... ") {
+                let status = if outcome.starts_with("ok") {
+                    TestStatus::Passed
+                } else if outcome.starts_with("FAILED") {
+                    TestStatus::Failed
+                } else if outcome.starts_with("ignored") {
+                    TestStatus::Ignored
+                } else {
+                    continue;
+                };
+
+                cases.push(TestCase {
+                    suite: suite_index
+                        .and_then(|i| suites.get(i).cloned())
+                        .unwrap_or_default(),
+                    name: name.trim().to_string(),
+                    status,
+                    stdout: String::new(),
+                    panic_message: None,
+                    duration: Duration::ZERO,
+                });
+            }
+            continue;
+        }
+
+        // Captured output of a test: "---- name stdout ----"
+        if let Some(name) = line
+            .strip_prefix("---- ")
+            .and_then(|rest| rest.strip_suffix(" stdout ----"))
+        {
+            let mut captured = String::new();
+            while let Some(next) = lines.peek() {
+                if next.starts_with("---- ") || matches!(next.trim(), "failures:" | "successes:") {
+                    break;
+                }
+                captured.push_str(next);
+                captured.push('\n');
+                lines.next();
+            }
+            let captured = captured.trim_end().to_string();
+
+            if let Some(case) = cases.iter_mut().rev().find(|c| c.name == name) {
+                if case.status == TestStatus::Failed {
+                    case.panic_message = extract_panic_message(&captured);
+                }
+                case.stdout = captured;
+            }
+        }
+    }
+
+    cases
+}
+
+/// Extract the panic payload from captured test output.
+///
+/// Handles both the current format (message on the lines after
+/// "panicked at file:line:col:") and the pre-1.73 inline format
+/// ("panicked at 'message', file:line:col").
+pub fn extract_panic_message(output: &str) -> Option<String> {
+    let mut lines = output.lines();
+
+    while let Some(line) = lines.next() {
+        let Some(idx) = line.find("panicked at ") else {
+            continue;
+        };
+        let rest = &line[idx + "panicked at ".len()..];
+
+        if let Some(inline) = rest.strip_prefix('\'') {
+            if let Some(end) = inline.rfind("', ") {
+                return Some(inline[..end].to_string());
+            }
+        }
+
+        let message: Vec<&str> = lines
+            .by_ref()
+            .take_while(|l| !l.starts_with("note: ") && !l.starts_with("stack backtrace:"))
+            .collect();
+        let message = message.join("\n").trim().to_string();
+        return Some(if message.is_empty() { rest.to_string() } else { message });
+    }
+
+    None
+}
+
+/// Every panic message in the output, in order, e.g. one per failed
+/// test when tests run with `--nocapture`
+pub fn extract_panic_messages(output: &str) -> Vec<String> {
+    let mut messages = Vec::new();
+    let mut offset = 0;
+    for line in output.split_inclusive('\n') {
+        if line.contains("panicked at ") {
+            messages.extend(extract_panic_message(&output[offset..]));
+        }
+        offset += line.len();
+    }
+    messages
+}
+
+/// Collect test binary names from cargo's "Running"/"Doc-tests" stderr lines
+fn suite_names(stderr: &str) -> Vec<String> {
+    stderr
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            if let Some(rest) = line.strip_prefix("Running ") {
+                // "Running tests/unit_tests.rs (target/debug/deps/unit_tests-abc123)"
+                // "Running unittests src/main.rs (target/debug/deps/hello-abc123)"
+                let rest = rest.strip_prefix("unittests ").unwrap_or(rest);
+                let target = rest.split_whitespace().next().unwrap_or(rest);
+                let stem = std::path::Path::new(target)
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                    .unwrap_or(target);
+                Some(stem.to_string())
+            } else {
+                line.strip_prefix("Doc-tests ")
+                    .map(|krate| format!("doc-tests {}", krate.trim()))
+            }
+        })
+        .collect()
+}
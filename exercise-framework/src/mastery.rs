@@ -0,0 +1,204 @@
+use crate::progress::{ConceptMastery, UserProgress};
+use crate::recommendations::PrerequisiteGraph;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// SM-2 ease factor for a concept practised for the first time
+pub const INITIAL_EASE: f64 = 2.5;
+
+/// SM-2 floor, so hard concepts still get spaced out a little
+const MIN_EASE: f64 = 1.3;
+
+/// Recall grade (0-5) from which a practice counts as remembered
+const PASSING_GRADE: f64 = 3.0;
+
+/// Weight of the newest practice in the learned-mastery estimate
+const LEARNING_RATE: f64 = 0.4;
+
+/// Spaced-repetition state of one concept, scheduled with SM-2
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewSchedule {
+    pub ease_factor: f64,
+    pub interval_days: u32,
+    pub repetitions: u32, // remembered practices in a row
+    pub due_at: Option<DateTime<Utc>>,
+    pub learned_level: f64, // mastery right after the last practice, before forgetting
+}
+
+impl Default for ReviewSchedule {
+    fn default() -> Self {
+        Self {
+            ease_factor: INITIAL_EASE,
+            interval_days: 0,
+            repetitions: 0,
+            due_at: None,
+            learned_level: 0.0,
+        }
+    }
+}
+
+/// How one go at an exercise went, as evidence about the concepts it teaches
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PracticeOutcome {
+    pub attempts: u32,      // test runs, including the passing one
+    pub test_failures: u32, // test runs that failed
+    pub hints_used: u32,
+}
+
+impl PracticeOutcome {
+    /// SM-2 recall grade from 0 to 5: every hint level revealed costs a
+    /// point and every failed test run half a point, at most two
+    pub fn grade(&self) -> f64 {
+        let hint_penalty = self.hints_used.min(3) as f64;
+        let failure_penalty = (self.test_failures as f64 * 0.5).min(2.0);
+        (5.0 - hint_penalty - failure_penalty).max(0.0)
+    }
+}
+
+impl ConceptMastery {
+    /// A concept the learner has not practised yet
+    pub fn new(concept: &str, now: DateTime<Utc>) -> Self {
+        Self {
+            concept: concept.to_string(),
+            mastery_level: 0.0,
+            confidence_level: 0.0,
+            last_practiced: now,
+            practice_count: 0,
+            needs_review: false,
+            schedule: ReviewSchedule::default(),
+        }
+    }
+
+    /// Update mastery and the review schedule after practising the concept
+    pub fn record_practice(&mut self, outcome: &PracticeOutcome, now: DateTime<Utc>) {
+        let grade = outcome.grade();
+        let performance = grade / 5.0;
+
+        // Blend the new result into whatever survived forgetting since last time
+        let learned = if self.practice_count == 0 {
+            performance
+        } else {
+            let retained = self.schedule.learned_level * self.retrievability(now);
+            retained + LEARNING_RATE * (performance - retained)
+        };
+
+        let schedule = &mut self.schedule;
+        if grade >= PASSING_GRADE {
+            schedule.interval_days = match schedule.repetitions {
+                0 => 1,
+                1 => 6,
+                _ => (schedule.interval_days as f64 * schedule.ease_factor).round() as u32,
+            };
+            schedule.repetitions += 1;
+        } else {
+            schedule.repetitions = 0;
+            schedule.interval_days = 1;
+        }
+        let miss = 5.0 - grade;
+        schedule.ease_factor = (schedule.ease_factor + 0.1 - miss * (0.08 + miss * 0.02)).max(MIN_EASE);
+        schedule.due_at = Some(now + chrono::Duration::days(schedule.interval_days.into()));
+        schedule.learned_level = learned.clamp(0.0, 1.0);
+
+        self.practice_count += 1;
+        self.last_practiced = now;
+        self.confidence_level = self.practice_count as f64 / (self.practice_count + 2) as f64;
+        self.mastery_level = schedule.learned_level;
+        self.needs_review = false;
+    }
+
+    /// Probability the learner still recalls the concept, on the FSRS
+    /// forgetting curve with the current interval as stability (so recall
+    /// has dropped to 90% when the review falls due)
+    pub fn retrievability(&self, now: DateTime<Utc>) -> f64 {
+        if self.practice_count == 0 {
+            return 0.0;
+        }
+        let stability = self.schedule.interval_days.max(1) as f64;
+        let elapsed_days = (now - self.last_practiced).num_seconds().max(0) as f64 / 86_400.0;
+        1.0 / (1.0 + elapsed_days / (9.0 * stability))
+    }
+
+    /// Whether the scheduled review has come round
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.schedule.due_at.is_some_and(|due| due <= now)
+    }
+
+    /// Decay mastery for the time since the last practice and flag the
+    /// concept once its review is due
+    pub fn refresh(&mut self, now: DateTime<Utc>) {
+        self.mastery_level = self.schedule.learned_level * self.retrievability(now);
+        self.needs_review = self.is_due(now);
+    }
+}
+
+/// A concept that is due, as listed in the review queue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConceptReview {
+    pub concept: String,
+    pub mastery_level: f64, // after decay
+    pub retrievability: f64,
+    pub due_at: DateTime<Utc>,
+}
+
+/// An exercise to redo because concepts it teaches are due for review
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewItem {
+    pub exercise_id: String,
+    pub title: String,
+    pub chapter: u32,
+    pub concepts: Vec<ConceptReview>,
+    pub due_at: DateTime<Utc>, // earliest among `concepts`
+    pub retrievability: f64,   // lowest among `concepts`
+}
+
+/// Exercises covering every concept that is due, most forgotten first.
+/// Each concept is reviewed through the exercise the learner completed
+/// most recently among those that teach it.
+pub fn review_queue(graph: &PrerequisiteGraph, progress: &UserProgress, now: DateTime<Utc>) -> Vec<ReviewItem> {
+    let mut covered: HashSet<(u32, &str)> = HashSet::new();
+    let mut queue = Vec::new();
+
+    for completion in progress.exercise_history.iter().rev() {
+        let Some(exercise) = graph.get(&completion.exercise_id) else {
+            continue;
+        };
+        let Some(chapter) = progress.chapters.get(&exercise.chapter) else {
+            continue;
+        };
+
+        let concepts: Vec<ConceptReview> = exercise
+            .concepts
+            .iter()
+            .filter(|concept| !covered.contains(&(exercise.chapter, concept.as_str())))
+            .filter_map(|concept| chapter.concept_mastery.get(concept))
+            .filter(|mastery| mastery.is_due(now))
+            .map(|mastery| ConceptReview {
+                concept: mastery.concept.clone(),
+                mastery_level: mastery.schedule.learned_level * mastery.retrievability(now),
+                retrievability: mastery.retrievability(now),
+                due_at: mastery.schedule.due_at.unwrap_or(now),
+            })
+            .collect();
+        if concepts.is_empty() {
+            continue;
+        }
+
+        for review in &concepts {
+            if let Some(concept) = exercise.concepts.iter().find(|c| **c == review.concept) {
+                covered.insert((exercise.chapter, concept.as_str()));
+            }
+        }
+        queue.push(ReviewItem {
+            exercise_id: exercise.id.clone(),
+            title: exercise.title.clone(),
+            chapter: exercise.chapter,
+            due_at: concepts.iter().map(|c| c.due_at).min().unwrap_or(now),
+            retrievability: concepts.iter().map(|c| c.retrievability).fold(1.0, f64::min),
+            concepts,
+        });
+    }
+
+    queue.sort_by(|a, b| a.retrievability.total_cmp(&b.retrievability));
+    queue
+}
@@ -1,23 +1,40 @@
-use serde::{Deserialize, Serialize};
+use crate::exercise::{ExerciseDifficulty, ExerciseType};
+use anyhow::Context;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
 use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Version of the `metadata.json` format this crate reads and writes.
+///
+/// 1. Untyped: no `schema_version`, difficulty and type as free strings
+/// 2. `schema_version`, difficulty, type, relevance and clippy level as enums
+pub const METADATA_SCHEMA_VERSION: u32 = 2;
+
+/// File names exercise metadata may be written under, in order of preference
+pub const METADATA_FILE_NAMES: [&str; 4] = ["metadata.json", "metadata.toml", "metadata.yaml", "metadata.yml"];
 
 /// Exercise metadata structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExerciseMetadata {
+    pub schema_version: u32,
     pub id: String,
     pub title: String,
     pub description: String,
     pub chapter: u32,
     pub exercise_number: u32,
-    pub difficulty: String,
+    pub difficulty: ExerciseDifficulty,
     pub estimated_time_minutes: u32,
     pub concepts: Vec<String>,
     pub prerequisites: Vec<String>,
-    pub exercise_type: String,
+    pub exercise_type: ExerciseType,
     pub rust_book_refs: RustBookRefs,
     pub hints: HintConfig,
     pub testing: TestConfig,
     pub validation: ValidationConfig,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub editable_files: Vec<String>, // learner-editable paths outside src/, e.g. "Cargo.toml"
 }
 
 /// Rust Book integration references
@@ -34,7 +51,16 @@ pub struct BookSection {
     pub chapter: String,
     pub title: String,
     pub url: String,
-    pub relevance: String, // "core_concept", "supporting", "advanced"
+    pub relevance: BookRelevance,
+}
+
+/// How closely a book section relates to the exercise
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BookRelevance {
+    CoreConcept,
+    Supporting,
+    Advanced,
 }
 
 /// Hint system configuration
@@ -42,6 +68,7 @@ pub struct BookSection {
 pub struct HintConfig {
     pub available: u32,
     pub auto_unlock: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub custom_hints: Option<Vec<String>>,
 }
 
@@ -50,19 +77,28 @@ pub struct HintConfig {
 pub struct TestConfig {
     pub timeout_seconds: u32,
     pub memory_limit_mb: u32,
-    pub allow_std_only: bool,
+    pub allow_std_only: bool, // builds fail while Cargo.toml declares dependencies
     pub custom_checks: Vec<String>,
 }
 
 /// Validation configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ValidationConfig {
-    pub clippy_level: String, // "warn", "deny"
+    pub clippy_level: ClippyLevel,
     pub format_required: bool,
     pub custom_checks: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub performance_requirements: Option<PerformanceRequirements>,
 }
 
+/// How clippy findings are treated when checking a solution
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClippyLevel {
+    Warn,
+    Deny,
+}
+
 /// Performance requirements for optimization exercises
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PerformanceRequirements {
@@ -79,15 +115,191 @@ pub struct BenchmarkTarget {
     pub tolerance_percent: f64,
 }
 
+/// Formats exercise metadata can be written in; all hold the same fields
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetadataFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+/// Why a metadata file could not be loaded, and where
+#[derive(Debug, Clone)]
+pub struct MetadataError {
+    pub path: Option<PathBuf>,
+    pub line: Option<usize>,   // 1-based
+    pub column: Option<usize>, // 1-based
+    pub message: String,
+}
+
+impl MetadataFormat {
+    pub const ALL: [MetadataFormat; 3] = [Self::Json, Self::Toml, Self::Yaml];
+
+    /// Name of a metadata file in this format
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Self::Json => "metadata.json",
+            Self::Toml => "metadata.toml",
+            Self::Yaml => "metadata.yaml",
+        }
+    }
+
+    /// Format of a metadata file, from its name
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.file_name()?.to_str()? {
+            "metadata.json" => Some(Self::Json),
+            "metadata.toml" => Some(Self::Toml),
+            "metadata.yaml" | "metadata.yml" => Some(Self::Yaml),
+            _ => None,
+        }
+    }
+
+    /// Deserialize `content`, locating errors in it
+    fn deserialize<T: DeserializeOwned>(self, content: &str) -> Result<T, MetadataError> {
+        match self {
+            Self::Json => serde_json::from_str(content).map_err(|e| {
+                // Line 0 means the error has no position
+                let line = Some(e.line()).filter(|line| *line > 0);
+                MetadataError::at(line, line.map(|_| e.column()), strip_position(e.to_string()))
+            }),
+            Self::Toml => toml::from_str(content).map_err(|e| {
+                let (line, column) = e.span().map(|span| line_column(content, span.start)).unzip();
+                MetadataError::at(line, column, e.message().to_string())
+            }),
+            Self::Yaml => serde_yaml_ng::from_str(content).map_err(|e| {
+                let location = e.location();
+                MetadataError::at(
+                    location.as_ref().map(|l| l.line() + 1),
+                    location.as_ref().map(|l| l.column() + 1),
+                    strip_position(e.to_string()),
+                )
+            }),
+        }
+    }
+
+    /// Write metadata out in this format
+    pub fn render(self, metadata: &ExerciseMetadata) -> anyhow::Result<String> {
+        Ok(match self {
+            Self::Json => serde_json::to_string_pretty(metadata)? + "\n",
+            Self::Toml => toml::to_string_pretty(metadata)?,
+            Self::Yaml => serde_yaml_ng::to_string(metadata)?,
+        })
+    }
+}
+
+impl fmt::Display for MetadataFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Json => "json",
+            Self::Toml => "toml",
+            Self::Yaml => "yaml",
+        })
+    }
+}
+
+impl std::str::FromStr for MetadataFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "toml" => Ok(Self::Toml),
+            "yaml" | "yml" => Ok(Self::Yaml),
+            _ => anyhow::bail!("Unknown metadata format '{}'; expected json, toml or yaml", s),
+        }
+    }
+}
+
+impl MetadataError {
+    fn at(line: Option<usize>, column: Option<usize>, message: String) -> Self {
+        Self {
+            path: None,
+            line,
+            column,
+            message,
+        }
+    }
+
+    pub(crate) fn in_file(path: &Path, message: String) -> Self {
+        Self {
+            path: Some(path.to_path_buf()),
+            ..Self::at(None, None, message)
+        }
+    }
+}
+
+impl fmt::Display for MetadataError {
+    /// `path:line:column: message`, leaving out what is unknown
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(path) = &self.path {
+            write!(f, "{}:", path.display())?;
+        }
+        if let Some(line) = self.line {
+            write!(f, "{}:", line)?;
+        }
+        if let Some(column) = self.column {
+            write!(f, "{}:", column)?;
+        }
+        if self.path.is_some() || self.line.is_some() {
+            f.write_str(" ")?;
+        }
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for MetadataError {}
+
+/// Parser messages without the " at line L column C" the location fields hold
+fn strip_position(message: String) -> String {
+    match message.rfind(" at line ") {
+        Some(index) => message[..index].to_string(),
+        None => message,
+    }
+}
+
+/// 1-based line and column of a byte offset
+fn line_column(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset.min(content.len())];
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    (before.matches('\n').count() + 1, before[line_start..].chars().count() + 1)
+}
+
 impl ExerciseMetadata {
-    /// Load metadata from JSON file
-    pub fn load_from_file(path: &std::path::Path) -> anyhow::Result<Self> {
-        let content = std::fs::read_to_string(path)?;
-        let metadata: Self = serde_json::from_str(&content)?;
-        metadata.validate()?;
+    /// Load metadata from a `metadata.{json,toml,yaml}` file, upgrading
+    /// older formats in memory. Errors are [`MetadataError`]s.
+    pub fn load_from_file(path: &Path) -> anyhow::Result<Self> {
+        let format = MetadataFormat::from_path(path)
+            .ok_or_else(|| MetadataError::in_file(path, "Not a metadata file".to_string()))?;
+        let content = std::fs::read_to_string(path).map_err(|e| MetadataError::in_file(path, e.to_string()))?;
+        let metadata = Self::parse(&content, format).map_err(|e| MetadataError {
+            path: Some(path.to_path_buf()),
+            ..e
+        })?;
+        metadata
+            .validate()
+            .map_err(|e| MetadataError::in_file(path, e.to_string()))?;
         Ok(metadata)
     }
 
+    /// Load the metadata of the exercise in `dir`, whichever format it is in
+    pub fn load_from_dir(dir: &Path) -> anyhow::Result<Self> {
+        let path = find_metadata_file(dir)?
+            .ok_or_else(|| MetadataError::in_file(dir, "No metadata file".to_string()))?;
+        Self::load_from_file(&path)
+    }
+
+    /// Parse metadata written in any known schema version
+    pub fn parse(content: &str, format: MetadataFormat) -> Result<Self, MetadataError> {
+        let mut value: Value = format.deserialize(content)?;
+        let version = upgrade_metadata(&mut value).map_err(|e| MetadataError::at(None, None, e.to_string()))?;
+        if version == METADATA_SCHEMA_VERSION {
+            // Parse the original text so errors point at a line and column
+            format.deserialize(content)
+        } else {
+            serde_json::from_value(value).map_err(|e| MetadataError::at(None, None, e.to_string()))
+        }
+    }
+
     /// Validate metadata for consistency and completeness
     pub fn validate(&self) -> anyhow::Result<()> {
         // Validate ID format: chXX-exYY-name
@@ -95,15 +307,12 @@ impl ExerciseMetadata {
             anyhow::bail!("Exercise ID must start with ch{:02}", self.chapter);
         }
 
-        // Validate difficulty
-        if !["beginner", "intermediate", "advanced"].contains(&self.difficulty.as_str()) {
-            anyhow::bail!("Difficulty must be beginner, intermediate, or advanced");
-        }
-
-        // Validate exercise type
-        let valid_types = ["code_completion", "bug_fixing", "from_scratch", "code_review", "performance"];
-        if !valid_types.contains(&self.exercise_type.as_str()) {
-            anyhow::bail!("Invalid exercise type: {}", self.exercise_type);
+        if self.schema_version != METADATA_SCHEMA_VERSION {
+            anyhow::bail!(
+                "Unsupported schema_version {}; expected {}",
+                self.schema_version,
+                METADATA_SCHEMA_VERSION
+            );
         }
 
         // Validate estimated time (reasonable bounds)
@@ -121,29 +330,14 @@ impl ExerciseMetadata {
             anyhow::bail!("Primary Rust Book chapter reference required");
         }
 
-        Ok(())
-    }
-
-    /// Get the difficulty as an enum
-    pub fn difficulty_enum(&self) -> super::ExerciseDifficulty {
-        match self.difficulty.as_str() {
-            "beginner" => super::ExerciseDifficulty::Beginner,
-            "intermediate" => super::ExerciseDifficulty::Intermediate,
-            "advanced" => super::ExerciseDifficulty::Advanced,
-            _ => super::ExerciseDifficulty::Beginner, // Default fallback
+        // Editable paths must stay inside the exercise directory
+        for entry in &self.editable_files {
+            if entry.is_empty() || entry.starts_with('/') || entry.split('/').any(|c| c == "..") {
+                anyhow::bail!("Invalid editable_files entry '{}'", entry);
+            }
         }
-    }
 
-    /// Get the exercise type as an enum
-    pub fn exercise_type_enum(&self) -> super::ExerciseType {
-        match self.exercise_type.as_str() {
-            "code_completion" => super::ExerciseType::CodeCompletion,
-            "bug_fixing" => super::ExerciseType::BugFixing,
-            "from_scratch" => super::ExerciseType::FromScratch,
-            "code_review" => super::ExerciseType::CodeReview,
-            "performance" => super::ExerciseType::Performance,
-            _ => super::ExerciseType::FromScratch, // Default fallback
-        }
+        Ok(())
     }
 
     /// Check if exercise has prerequisites
@@ -174,21 +368,19 @@ impl ExerciseMetadata {
             .all(|prereq| completed_exercises.contains(prereq))
     }
 
-    /// Get primary Rust Book URL
+    /// Get primary Rust Book URL, or the book's front page for an unknown chapter
     pub fn primary_book_url(&self) -> String {
-        format!(
-            "https://doc.rust-lang.org/book/ch{}.html",
-            self.rust_book_refs.primary_chapter
-        )
+        crate::book::section(&self.rust_book_refs.primary_chapter)
+            .map(|page| page.online_url())
+            .unwrap_or_else(|| crate::book::ONLINE_BOOK_URL.to_string())
     }
 
     /// Estimate points value based on difficulty and concepts
     pub fn point_value(&self) -> u32 {
-        let base_points = match self.difficulty.as_str() {
-            "beginner" => 10,
-            "intermediate" => 25,
-            "advanced" => 50,
-            _ => 10,
+        let base_points = match self.difficulty {
+            ExerciseDifficulty::Beginner => 10,
+            ExerciseDifficulty::Intermediate => 25,
+            ExerciseDifficulty::Advanced => 50,
         };
 
         let concept_bonus = self.concepts.len() as u32 * 5;
@@ -196,4 +388,251 @@ impl ExerciseMetadata {
 
         base_points + concept_bonus + time_factor
     }
-}
\ No newline at end of file
+}
+
+/// Upgrade parsed metadata to [`METADATA_SCHEMA_VERSION`] in place,
+/// returning the version it was written in
+pub fn upgrade_metadata(value: &mut Value) -> anyhow::Result<u32> {
+    let object = value
+        .as_object_mut()
+        .context("Metadata must be a JSON object")?;
+    let version = match object.get("schema_version") {
+        None => 1,
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .context("schema_version must be a positive integer")?,
+    };
+    if version == 0 || version > METADATA_SCHEMA_VERSION {
+        anyhow::bail!(
+            "Unsupported schema_version {}; this version of Rust Tour reads up to {}",
+            version,
+            METADATA_SCHEMA_VERSION
+        );
+    }
+
+    if version < 2 {
+        // Version 1 accepted any spelling; the enums only take snake_case
+        for key in ["difficulty", "exercise_type"] {
+            normalize_name(object.get_mut(key));
+        }
+        if let Some(sections) = object
+            .get_mut("rust_book_refs")
+            .and_then(|refs| refs.get_mut("specific_sections"))
+            .and_then(Value::as_array_mut)
+        {
+            for section in sections {
+                normalize_name(section.get_mut("relevance"));
+            }
+        }
+        normalize_name(object.get_mut("validation").and_then(|v| v.get_mut("clippy_level")));
+    }
+
+    object.insert("schema_version".to_string(), json!(METADATA_SCHEMA_VERSION));
+    Ok(version)
+}
+
+/// `"Bug Fixing"` / `"bug-fixing"` to `"bug_fixing"`
+fn normalize_name(value: Option<&mut Value>) {
+    if let Some(Value::String(name)) = value {
+        *name = name.trim().to_lowercase().replace(['-', ' '], "_");
+    }
+}
+
+/// Whether `path` names a metadata file, in any format
+pub fn is_metadata_file(path: &Path) -> bool {
+    MetadataFormat::from_path(path).is_some()
+}
+
+/// The metadata file of the exercise in `dir`, if it has one. Having more
+/// than one is an error, since they could disagree.
+pub fn find_metadata_file(dir: &Path) -> Result<Option<PathBuf>, MetadataError> {
+    let found: Vec<PathBuf> = METADATA_FILE_NAMES
+        .iter()
+        .map(|name| dir.join(name))
+        .filter(|path| path.is_file())
+        .collect();
+    if found.len() > 1 {
+        let names: Vec<String> = found
+            .iter()
+            .filter_map(|path| path.file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .collect();
+        return Err(MetadataError::in_file(
+            dir,
+            format!("Found {}; keep only one metadata file", names.join(" and ")),
+        ));
+    }
+    Ok(found.into_iter().next())
+}
+
+/// Schema version a metadata file is written in
+pub fn file_schema_version(path: &Path) -> anyhow::Result<u32> {
+    read_value(path).map(|(_, _, version)| version)
+}
+
+/// Parse a metadata file into a value upgraded to the current schema
+/// version, with its format and the version it was written in
+fn read_value(path: &Path) -> anyhow::Result<(Value, MetadataFormat, u32)> {
+    let format = MetadataFormat::from_path(path).with_context(|| format!("{:?} is not a metadata file", path))?;
+    let content = std::fs::read_to_string(path)?;
+    let mut value: Value = format
+        .deserialize(&content)
+        .map_err(|e| MetadataError { path: Some(path.to_path_buf()), ..e })?;
+    let version = upgrade_metadata(&mut value).with_context(|| format!("Cannot migrate {:?}", path))?;
+    Ok((value, format, version))
+}
+
+/// Rewrite a metadata file in the current schema version, keeping its
+/// format. Returns the version it was written in when it had to be
+/// upgraded, or `None` if it was current.
+pub fn migrate_file(path: &Path) -> anyhow::Result<Option<u32>> {
+    let (value, format, version) = read_value(path)?;
+    if version == METADATA_SCHEMA_VERSION {
+        return Ok(None);
+    }
+
+    let metadata: ExerciseMetadata =
+        serde_json::from_value(value).with_context(|| format!("Invalid metadata in {:?}", path))?;
+    std::fs::write(path, format.render(&metadata)?)?;
+    Ok(Some(version))
+}
+
+/// Rewrite a metadata file in another format, replacing the original.
+/// Returns the path of the new file.
+pub fn convert_file(path: &Path, to: MetadataFormat) -> anyhow::Result<PathBuf> {
+    let metadata = ExerciseMetadata::load_from_file(path)?;
+    let target = path.with_file_name(to.file_name());
+    if target != path && target.exists() {
+        anyhow::bail!("{:?} already exists", target);
+    }
+
+    std::fs::write(&target, to.render(&metadata)?)?;
+    if target != path {
+        std::fs::remove_file(path)?;
+    }
+    Ok(target)
+}
+
+/// JSON Schema (draft-07) for the current metadata format, used by editors
+/// for completion and checking
+pub fn json_schema() -> Value {
+    let names = |names: Vec<&str>| json!({ "type": "string", "enum": names });
+    let strings = json!({ "type": "array", "items": { "type": "string" } });
+    let exercise_ids = json!({
+        "type": "array",
+        "items": { "type": "string", "pattern": "^ch[0-9]{2}-ex[0-9]{2}-[a-z0-9-]+$" }
+    });
+    let book_chapter = json!({ "type": "string", "pattern": "^[0-9]+(\\.[0-9]+)?$" });
+    let positive = json!({ "type": "integer", "minimum": 1 });
+
+    let rust_book_refs = json!({
+        "type": "object",
+        "required": ["primary_chapter", "supporting_chapters", "specific_sections"],
+        "properties": {
+            "primary_chapter": book_chapter,
+            "supporting_chapters": { "type": "array", "items": book_chapter },
+            "specific_sections": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": ["chapter", "title", "url", "relevance"],
+                    "properties": {
+                        "chapter": book_chapter,
+                        "title": { "type": "string" },
+                        "url": { "type": "string", "format": "uri" },
+                        "relevance": names(vec!["core_concept", "supporting", "advanced"])
+                    }
+                }
+            }
+        }
+    });
+    let hints = json!({
+        "type": "object",
+        "required": ["available", "auto_unlock"],
+        "properties": {
+            "available": { "type": "integer", "minimum": 0 },
+            "auto_unlock": { "type": "boolean" },
+            "custom_hints": strings
+        }
+    });
+    let testing = json!({
+        "type": "object",
+        "required": ["timeout_seconds", "memory_limit_mb", "allow_std_only", "custom_checks"],
+        "properties": {
+            "timeout_seconds": positive,
+            "memory_limit_mb": positive,
+            "allow_std_only": {
+                "type": "boolean",
+                "description": "Refuse to build the exercise while Cargo.toml declares any dependency"
+            },
+            "custom_checks": strings
+        }
+    });
+    let validation = json!({
+        "type": "object",
+        "required": ["clippy_level", "format_required", "custom_checks"],
+        "properties": {
+            "clippy_level": names(vec!["warn", "deny"]),
+            "format_required": { "type": "boolean" },
+            "custom_checks": strings,
+            "performance_requirements": {
+                "type": "object",
+                "required": ["max_execution_time_ms", "max_memory_usage_mb", "benchmark_targets"],
+                "properties": {
+                    "max_execution_time_ms": positive,
+                    "max_memory_usage_mb": positive,
+                    "benchmark_targets": {
+                        "type": "object",
+                        "additionalProperties": {
+                            "type": "object",
+                            "required": ["target_time_ns", "target_memory_bytes", "tolerance_percent"],
+                            "properties": {
+                                "target_time_ns": { "type": "integer", "minimum": 0 },
+                                "target_memory_bytes": { "type": "integer", "minimum": 0 },
+                                "tolerance_percent": { "type": "number", "minimum": 0 }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    });
+
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "Rust Tour exercise metadata",
+        "type": "object",
+        "required": [
+            "schema_version", "id", "title", "description", "chapter", "exercise_number",
+            "difficulty", "estimated_time_minutes", "concepts", "prerequisites",
+            "exercise_type", "rust_book_refs", "hints", "testing", "validation"
+        ],
+        "properties": {
+            "schema_version": { "const": METADATA_SCHEMA_VERSION },
+            "id": {
+                "type": "string",
+                "pattern": "^ch[0-9]{2}-ex[0-9]{2}-[a-z0-9-]+$",
+                "description": "chXX-exYY-name, matching the chapter and exercise number"
+            },
+            "title": { "type": "string", "minLength": 1 },
+            "description": { "type": "string", "minLength": 1 },
+            "chapter": positive,
+            "exercise_number": positive,
+            "difficulty": names(ExerciseDifficulty::ALL.iter().map(|d| d.as_str()).collect()),
+            "estimated_time_minutes": { "type": "integer", "minimum": 1, "maximum": 180 },
+            "concepts": { "type": "array", "items": { "type": "string" }, "minItems": 1 },
+            "prerequisites": exercise_ids,
+            "exercise_type": names(ExerciseType::ALL.iter().map(|t| t.as_str()).collect()),
+            "rust_book_refs": rust_book_refs,
+            "hints": hints,
+            "testing": testing,
+            "validation": validation,
+            "editable_files": {
+                "type": "array",
+                "items": { "type": "string", "minLength": 1 },
+                "description": "Paths outside src/ that learners may edit, relative to the exercise directory"
+            }
+        }
+    })
+}
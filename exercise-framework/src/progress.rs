@@ -1,7 +1,34 @@
-use anyhow::Result;
+use crate::achievements::AchievementCatalog;
+use crate::activity::{self, ActivityCalendar, ActivityKind, DEFAULT_TIMEZONE};
+use crate::hints::{HintLevel, HintRequest, HintUsage};
+use crate::mastery::{self, PracticeOutcome, ReviewItem, ReviewSchedule};
+use crate::metadata::ExerciseMetadata;
+use crate::progress_store::{HistoryQuery, JsonProgressStore, ProgressStore};
+use crate::recommendations::{self, PrerequisiteGraph, Recommendation};
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::Path;
+use std::sync::Arc;
+
+/// User id for single-learner installs, whose progress predates accounts
+pub const DEFAULT_USER: &str = "default";
+
+/// Check that a user id is safe to use as a directory name
+pub fn validate_user_id(user_id: &str) -> Result<()> {
+    let valid = !user_id.is_empty()
+        && user_id.len() <= 64
+        && user_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if !valid {
+        anyhow::bail!(
+            "Invalid user id {:?}: use 1-64 letters, digits, '-' or '_'",
+            user_id
+        );
+    }
+    Ok(())
+}
 
 /// User's overall progress tracking
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,9 +45,17 @@ pub struct UserProgress {
     pub longest_streak: u32,
     pub chapters: HashMap<u32, ChapterProgress>,
     pub exercise_history: Vec<ExerciseCompletion>,
+    #[serde(default)]
+    pub active_exercises: HashMap<String, ExerciseAttempt>,
     pub achievements: Vec<Achievement>,
     pub preferences: UserPreferences,
     pub analytics: LearningAnalytics,
+    #[serde(default)]
+    pub session_stats: SessionStats,
+    #[serde(default)]
+    pub hint_usage: HashMap<String, HintUsage>, // by exercise id, kept after completion
+    #[serde(default)]
+    pub activity: ActivityCalendar, // by day in the user's timezone
 }
 
 /// Progress for a specific chapter
@@ -47,6 +82,8 @@ pub struct ConceptMastery {
     pub last_practiced: chrono::DateTime<chrono::Utc>,
     pub practice_count: u32,
     pub needs_review: bool,
+    #[serde(default)]
+    pub schedule: ReviewSchedule,
 }
 
 /// Individual exercise completion record
@@ -64,6 +101,29 @@ pub struct ExerciseCompletion {
     pub concepts_learned: Vec<String>,
 }
 
+/// Work on an exercise that has been opened but not yet completed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExerciseAttempt {
+    pub exercise_id: String,
+    pub first_viewed: chrono::DateTime<chrono::Utc>,
+    pub last_viewed: chrono::DateTime<chrono::Utc>,
+    pub views: u32,
+    pub hints_used: Vec<u32>, // hint levels revealed, in request order
+    #[serde(default)]
+    pub test_runs: u32,
+    #[serde(default)]
+    pub test_failures: u32, // test runs that did not pass
+}
+
+/// Running activity counters shown in the UI
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionStats {
+    pub exercises_viewed: u32,
+    pub exercises_completed: u32,
+    pub hints_used: u32,
+    pub time_spent: u32, // minutes
+}
+
 /// User achievements and badges
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Achievement {
@@ -96,6 +156,8 @@ pub struct UserPreferences {
     pub notifications_enabled: bool,
     pub auto_advance: bool,
     pub practice_reminders: bool,
+    #[serde(default = "default_timezone")]
+    pub timezone: String, // IANA name; days and streaks are counted in it
 }
 
 /// Difficulty preference settings
@@ -129,51 +191,21 @@ pub struct LearningAnalytics {
     pub predicted_completion_time: u32, // days to full completion
 }
 
-/// Progress tracker manages user learning progress
-pub struct ProgressTracker {
-    progress_file: std::path::PathBuf,
-    current_progress: UserProgress,
+/// A completion reported by the learner, before it is scored and recorded
+#[derive(Debug, Clone)]
+pub struct NewCompletion {
+    pub exercise_id: String,
+    pub time_taken_minutes: u32,
+    pub concepts_learned: Vec<String>,
 }
 
-impl ProgressTracker {
-    /// Create a new progress tracker
-    pub fn new<P: AsRef<Path>>(exercises_root: P) -> Result<Self> {
-        let progress_file = exercises_root.as_ref()
-            .parent()
-            .unwrap_or_else(|| Path::new("."))
-            .join("progress")
-            .join("user_progress.json");
-
-        // Create progress directory if it doesn't exist
-        if let Some(parent) = progress_file.parent() {
-            std::fs::create_dir_all(parent)?;
-        }
-
-        let current_progress = if progress_file.exists() {
-            Self::load_progress(&progress_file)?
-        } else {
-            Self::create_default_progress()
-        };
-
-        Ok(Self {
-            progress_file,
-            current_progress,
-        })
-    }
-
-    /// Load progress from file
-    fn load_progress(path: &Path) -> Result<UserProgress> {
-        let content = std::fs::read_to_string(path)?;
-        let progress: UserProgress = serde_json::from_str(&content)?;
-        Ok(progress)
-    }
-
+impl UserProgress {
     /// Create default progress for new user
-    fn create_default_progress() -> UserProgress {
+    pub fn new(user_id: &str) -> Self {
         let now = chrono::Utc::now();
         
         UserProgress {
-            user_id: "default".to_string(),
+            user_id: user_id.to_string(),
             created_at: now,
             last_updated: now,
             overall_progress: 0.0,
@@ -185,6 +217,7 @@ impl ProgressTracker {
             longest_streak: 0,
             chapters: HashMap::new(),
             exercise_history: Vec::new(),
+            active_exercises: HashMap::new(),
             achievements: Vec::new(),
             preferences: UserPreferences {
                 difficulty_preference: DifficultyPreference::Adaptive,
@@ -194,6 +227,7 @@ impl ProgressTracker {
                 notifications_enabled: true,
                 auto_advance: false,
                 practice_reminders: true,
+                timezone: DEFAULT_TIMEZONE.to_string(),
             },
             analytics: LearningAnalytics {
                 learning_velocity: 0.0,
@@ -205,209 +239,708 @@ impl ProgressTracker {
                 improvement_trends: HashMap::new(),
                 predicted_completion_time: 0,
             },
+            session_stats: SessionStats::default(),
+            hint_usage: HashMap::new(),
+            activity: ActivityCalendar::default(),
         }
     }
 
-    /// Save current progress to file
-    pub fn save(&self) -> Result<()> {
-        let content = serde_json::to_string_pretty(&self.current_progress)?;
-        std::fs::write(&self.progress_file, content)?;
-        Ok(())
+    /// Parse progress JSON, upgrading the flat format written by older
+    /// web-server releases
+    pub fn from_json(content: &str) -> Result<Self> {
+        let value: serde_json::Value = serde_json::from_str(content)?;
+        match serde_json::from_value::<UserProgress>(value.clone()) {
+            Ok(progress) => Ok(progress),
+            Err(e) if value.get("session_stats").is_some() && value.get("preferences").is_none() => {
+                Self::upgrade_legacy_progress(&value)
+                    .with_context(|| format!("Failed to upgrade legacy progress: {}", e))
+            }
+            Err(e) => Err(e.into()),
+        }
     }
 
-    /// Get current progress
-    pub fn get_progress(&self) -> Result<UserProgress> {
-        Ok(self.current_progress.clone())
+    /// Convert the flat progress file written by older web-server releases
+    fn upgrade_legacy_progress(value: &serde_json::Value) -> Result<UserProgress> {
+        let mut progress = Self::new(DEFAULT_USER);
+        let parse_time = |v: Option<&serde_json::Value>| {
+            v.and_then(|v| v.as_str())
+                .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+                .map(|t| t.with_timezone(&chrono::Utc))
+        };
+        let get_u32 = |key: &str| value.get(key).and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+
+        if let Some(user_id) = value.get("user_id").and_then(|v| v.as_str()) {
+            progress.user_id = user_id.to_string();
+        }
+        if let Some(created_at) = parse_time(value.get("created_at")) {
+            progress.created_at = created_at;
+        }
+        progress.total_exercises = get_u32("total_exercises");
+        progress.current_streak = get_u32("current_streak");
+        progress.longest_streak = get_u32("longest_streak");
+        if let Some(stats) = value.get("session_stats") {
+            progress.session_stats = serde_json::from_value(stats.clone()).unwrap_or_default();
+        }
+
+        let entries = value.get("exercise_history")
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default();
+
+        for entry in entries {
+            let Some(exercise_id) = entry.get("exercise_id").and_then(|v| v.as_str()) else {
+                continue;
+            };
+            let hints_used: Vec<u32> = entry.get("hints_used")
+                .and_then(|v| serde_json::from_value(v.clone()).ok())
+                .unwrap_or_default();
+
+            if let Some(completed_at) = parse_time(entry.get("completed_at")) {
+                let time_taken_minutes = entry.get("time_taken_minutes")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(0) as u32;
+                progress.exercise_history.push(ExerciseCompletion {
+                    exercise_id: exercise_id.to_string(),
+                    chapter: chapter_from_id(exercise_id),
+                    completed_at,
+                    time_taken_minutes,
+                    attempts: 1,
+                    hints_used: hints_used.len() as u32,
+                    test_passes: 1,
+                    test_failures: 0,
+                    code_quality_score: 0.8,
+                    concepts_learned: Vec::new(),
+                });
+            } else {
+                let viewed_at = parse_time(entry.get("viewed_at")).unwrap_or(progress.created_at);
+                progress.active_exercises.insert(exercise_id.to_string(), ExerciseAttempt {
+                    exercise_id: exercise_id.to_string(),
+                    first_viewed: viewed_at,
+                    last_viewed: viewed_at,
+                    views: 1,
+                    hints_used,
+                    test_runs: 0,
+                    test_failures: 0,
+                });
+            }
+        }
+
+        for completion in progress.exercise_history.clone() {
+            progress.exercises_completed += 1;
+            progress.total_time_minutes += completion.time_taken_minutes;
+            let chapter_progress = progress.chapters
+                .entry(completion.chapter)
+                .or_insert_with(|| new_chapter_progress(completion.chapter, 0, completion.completed_at));
+            chapter_progress.exercises_completed += 1;
+            chapter_progress.time_spent_minutes += completion.time_taken_minutes;
+        }
+
+        Ok(progress)
+    }
+
+    /// Check whether an exercise has already been completed
+    pub fn is_completed(&self, exercise_id: &str) -> bool {
+        self.exercise_history
+            .iter()
+            .any(|c| c.exercise_id == exercise_id)
     }
 
-    /// Complete an exercise and update progress
-    pub fn complete_exercise(&mut self, exercise_id: &str, time_taken_minutes: u32) -> Result<()> {
+    /// Update exercise totals from per-chapter counts of the exercises on
+    /// disk; returns whether anything changed
+    pub(crate) fn sync_exercise_totals(&mut self, chapter_totals: &HashMap<u32, u32>) -> bool {
+        let total_exercises: u32 = chapter_totals.values().sum();
+        let mut changed = self.total_exercises != total_exercises;
+        self.total_exercises = total_exercises;
+        self.overall_progress = ratio(self.exercises_completed, total_exercises);
+
+        for (chapter, chapter_progress) in self.chapters.iter_mut() {
+            let total = chapter_totals.get(chapter).copied().unwrap_or(0);
+            if chapter_progress.total_exercises != total {
+                chapter_progress.total_exercises = total;
+                chapter_progress.completion_percentage =
+                    ratio(chapter_progress.exercises_completed, total);
+                changed = true;
+            }
+        }
+
+        changed
+    }
+
+    /// Record that the user opened an exercise
+    pub(crate) fn record_view(&mut self, exercise_id: &str) {
+        let now = chrono::Utc::now();
+        self.session_stats.exercises_viewed += 1;
+        self.record_activity(ActivityKind::View, now);
+
+        if !self.is_completed(exercise_id) {
+            let attempt = self.active_exercises
+                .entry(exercise_id.to_string())
+                .or_insert_with(|| ExerciseAttempt {
+                    exercise_id: exercise_id.to_string(),
+                    first_viewed: now,
+                    last_viewed: now,
+                    views: 0,
+                    hints_used: Vec::new(),
+                    test_runs: 0,
+                    test_failures: 0,
+                });
+            attempt.views += 1;
+            attempt.last_viewed = now;
+        }
+
+        self.last_updated = now;
+    }
+
+    /// Record that the user revealed a hint level for an exercise
+    pub(crate) fn record_hint(&mut self, exercise_id: &str, hint_level: u32) {
+        let now = chrono::Utc::now();
+        self.session_stats.hints_used += 1;
+
+        let attempt = self.current_attempt(exercise_id, now);
+        if !attempt.hints_used.contains(&hint_level) {
+            attempt.hints_used.push(hint_level);
+        }
+        let started = attempt.first_viewed;
+
+        if let Some(level) = HintLevel::from_number(hint_level) {
+            let usage = self
+                .hint_usage
+                .entry(exercise_id.to_string())
+                .or_insert_with(|| HintUsage {
+                    exercise_id: exercise_id.to_string(),
+                    user_id: self.user_id.clone(),
+                    hints_requested: Vec::new(),
+                    total_hints_used: 0,
+                    time_before_first_hint: (now - started).num_seconds().max(0) as u32,
+                    solved_after_hints: false,
+                });
+            usage.hints_requested.push(HintRequest {
+                level,
+                requested_at: now,
+                was_helpful: None,
+            });
+            usage.total_hints_used += 1;
+        }
+
+        self.last_updated = now;
+    }
+
+    /// Record whether a revealed hint level helped; the latest request of
+    /// that level gets the answer. Returns whether the level was revealed.
+    pub(crate) fn record_hint_feedback(&mut self, exercise_id: &str, hint_level: u32, helpful: bool) -> bool {
+        let request = self
+            .hint_usage
+            .get_mut(exercise_id)
+            .and_then(|usage| {
+                usage
+                    .hints_requested
+                    .iter_mut()
+                    .rev()
+                    .find(|request| request.level.number() == hint_level)
+            });
+        let Some(request) = request else {
+            return false;
+        };
+        request.was_helpful = Some(helpful);
+        self.last_updated = chrono::Utc::now();
+        true
+    }
+
+    /// Record the outcome of running an exercise's tests
+    pub(crate) fn record_test_run(&mut self, exercise_id: &str, passed: bool) {
         let now = chrono::Utc::now();
 
-        // Parse chapter from exercise ID
-        let chapter = exercise_id
-            .strip_prefix("ch")
-            .and_then(|s| s[..2].parse::<u32>().ok())
-            .unwrap_or(1);
+        let attempt = self.current_attempt(exercise_id, now);
+        attempt.test_runs += 1;
+        if !passed {
+            attempt.test_failures += 1;
+        }
+        self.record_activity(ActivityKind::TestRun, now);
+
+        self.last_updated = now;
+    }
+
+    /// Record that the user saved code of an exercise
+    pub(crate) fn record_save(&mut self) {
+        let now = chrono::Utc::now();
+        self.record_activity(ActivityKind::Save, now);
+        self.last_updated = now;
+    }
+
+    /// Count activity on the user's local day and bring the streaks up to date
+    fn record_activity(&mut self, kind: ActivityKind, now: chrono::DateTime<chrono::Utc>) {
+        self.backfill_activity();
+        self.activity
+            .record(kind, activity::local_date(now, &self.preferences.timezone));
+        self.refresh_streaks(now);
+    }
+
+    /// Recompute the streaks from the activity calendar, as of `now` in the
+    /// user's timezone
+    pub fn refresh_streaks(&mut self, now: chrono::DateTime<chrono::Utc>) {
+        self.backfill_activity();
+        let streaks = self
+            .activity
+            .streaks(activity::local_date(now, &self.preferences.timezone));
+        self.current_streak = streaks.current;
+        self.longest_streak = self.longest_streak.max(streaks.longest);
+    }
+
+    /// Progress from before the calendar existed still has its completions
+    fn backfill_activity(&mut self) {
+        if !self.activity.is_empty() {
+            return;
+        }
+        for completion in &self.exercise_history {
+            let date = activity::local_date(completion.completed_at, &self.preferences.timezone);
+            self.activity.record(ActivityKind::Completion, date);
+        }
+    }
+
+    /// The open attempt at an exercise, started now if there is none
+    fn current_attempt(&mut self, exercise_id: &str, now: chrono::DateTime<chrono::Utc>) -> &mut ExerciseAttempt {
+        self.active_exercises
+            .entry(exercise_id.to_string())
+            .or_insert_with(|| ExerciseAttempt {
+                exercise_id: exercise_id.to_string(),
+                first_viewed: now,
+                last_viewed: now,
+                views: 1,
+                hints_used: Vec::new(),
+                test_runs: 0,
+                test_failures: 0,
+            })
+    }
+
+    /// Decay concept mastery for time passed and flag concepts due for review
+    pub fn refresh_mastery(&mut self, now: chrono::DateTime<chrono::Utc>) {
+        for chapter in self.chapters.values_mut() {
+            for mastery in chapter.concept_mastery.values_mut() {
+                mastery.refresh(now);
+            }
+        }
+    }
+
+    /// Credit practice of an exercise's concepts to their chapter's mastery
+    fn practice_concepts(
+        &mut self,
+        chapter: u32,
+        concepts: &[String],
+        outcome: &PracticeOutcome,
+        now: chrono::DateTime<chrono::Utc>,
+    ) {
+        let Some(chapter_progress) = self.chapters.get_mut(&chapter) else {
+            return;
+        };
+        for concept in concepts {
+            chapter_progress
+                .concept_mastery
+                .entry(concept.clone())
+                .or_insert_with(|| ConceptMastery::new(concept, now))
+                .record_practice(outcome, now);
+        }
+    }
+
+    /// Add a completion record and update the derived counters; returns
+    /// whether anything changed
+    pub(crate) fn record_completion(
+        &mut self,
+        completion: &NewCompletion,
+        chapter_totals: &HashMap<u32, u32>,
+    ) -> bool {
+        let exercise_id = completion.exercise_id.as_str();
+        let now = chrono::Utc::now();
+        let chapter = chapter_from_id(exercise_id);
+
+        // Completing an exercise twice must not inflate the counters, but
+        // redoing it for due concepts is a review
+        if self.is_completed(exercise_id) {
+            return self.record_review(exercise_id, chapter, now);
+        }
+
+        self.record_activity(ActivityKind::Completion, now);
+        let outcome = self.take_attempt_outcome(exercise_id);
+        if let Some(usage) = self.hint_usage.get_mut(exercise_id) {
+            usage.solved_after_hints = true;
+        }
+        let time_taken_minutes = completion.time_taken_minutes;
 
         // Create exercise completion record
-        let completion = ExerciseCompletion {
+        self.exercise_history.push(ExerciseCompletion {
             exercise_id: exercise_id.to_string(),
             chapter,
             completed_at: now,
             time_taken_minutes,
-            attempts: 1, // TODO: Track actual attempts
-            hints_used: 0, // TODO: Track actual hint usage
-            test_passes: 1, // TODO: Track actual test results
-            test_failures: 0,
+            attempts: outcome.attempts,
+            hints_used: outcome.hints_used,
+            test_passes: 1,
+            test_failures: outcome.test_failures,
             code_quality_score: 0.8, // TODO: Calculate from clippy/fmt
-            concepts_learned: Vec::new(), // TODO: Get from exercise metadata
-        };
-
-        // Add to history
-        self.current_progress.exercise_history.push(completion);
+            concepts_learned: completion.concepts_learned.clone(),
+        });
 
         // Update overall progress
-        self.current_progress.exercises_completed += 1;
-        self.current_progress.total_time_minutes += time_taken_minutes;
-        self.current_progress.overall_progress = 
-            self.current_progress.exercises_completed as f64 / 
-            self.current_progress.total_exercises as f64;
+        self.exercises_completed += 1;
+        self.session_stats.exercises_completed += 1;
+        self.session_stats.time_spent += time_taken_minutes;
+        self.total_time_minutes += time_taken_minutes;
+        self.overall_progress = ratio(self.exercises_completed, self.total_exercises);
 
         // Update chapter progress
-        let chapter_progress = self.current_progress.chapters
+        let chapter_total = chapter_totals.get(&chapter).copied().unwrap_or(0);
+        let chapter_progress = self.chapters
             .entry(chapter)
-            .or_insert_with(|| ChapterProgress {
-                chapter_number: chapter,
-                title: format!("Chapter {}", chapter),
-                exercises_completed: 0,
-                total_exercises: 10, // TODO: Get from metadata
-                completion_percentage: 0.0,
-                time_spent_minutes: 0,
-                first_started: Some(now),
-                completed_at: None,
-                concept_mastery: HashMap::new(),
-                is_unlocked: true,
-            });
+            .or_insert_with(|| new_chapter_progress(chapter, chapter_total, now));
 
         chapter_progress.exercises_completed += 1;
         chapter_progress.time_spent_minutes += time_taken_minutes;
-        chapter_progress.completion_percentage = 
-            chapter_progress.exercises_completed as f64 / 
-            chapter_progress.total_exercises as f64;
+        chapter_progress.completion_percentage = ratio(
+            chapter_progress.exercises_completed,
+            chapter_progress.total_exercises,
+        );
 
         // Check if chapter is complete
-        if chapter_progress.exercises_completed >= chapter_progress.total_exercises {
+        if chapter_progress.total_exercises > 0
+            && chapter_progress.completed_at.is_none()
+            && chapter_progress.exercises_completed >= chapter_progress.total_exercises
+        {
             chapter_progress.completed_at = Some(now);
-            self.current_progress.chapters_completed += 1;
+            self.chapters_completed += 1;
         }
 
-        // Update streak
-        self.update_streak();
-
-        // Check for new achievements
-        self.check_achievements();
+        // Update concept mastery and review schedules
+        self.practice_concepts(chapter, &completion.concepts_learned, &outcome, now);
 
         // Update analytics
         self.update_analytics();
 
         // Update timestamp
-        self.current_progress.last_updated = now;
-
-        // Save progress
-        self.save()?;
+        self.last_updated = now;
 
-        Ok(())
+        true
     }
 
-    /// Update learning streak
-    fn update_streak(&mut self) {
-        let now = chrono::Utc::now().date_naive();
-        
-        if let Some(last_completion) = self.current_progress.exercise_history.last() {
-            let last_date = last_completion.completed_at.date_naive();
-            let days_diff = (now - last_date).num_days();
-
-            if days_diff <= 1 {
-                // Continuing or maintaining streak
-                if days_diff == 1 {
-                    self.current_progress.current_streak += 1;
-                }
-                // days_diff == 0 means same day, don't increment
-            } else {
-                // Streak broken
-                self.current_progress.current_streak = 1;
-            }
-
-            // Update longest streak
-            if self.current_progress.current_streak > self.current_progress.longest_streak {
-                self.current_progress.longest_streak = self.current_progress.current_streak;
-            }
+    /// Close the open attempt at an exercise and summarise how it went
+    fn take_attempt_outcome(&mut self, exercise_id: &str) -> PracticeOutcome {
+        let attempt = self.active_exercises.remove(exercise_id);
+        PracticeOutcome {
+            attempts: attempt.as_ref().map_or(1, |a| a.test_runs.max(1)),
+            test_failures: attempt.as_ref().map_or(0, |a| a.test_failures),
+            hints_used: attempt.as_ref().map_or(0, |a| a.hints_used.len() as u32),
         }
     }
 
-    /// Check for new achievements
-    fn check_achievements(&mut self) {
-        let mut new_achievements = Vec::new();
-
-        // First exercise achievement
-        if self.current_progress.exercises_completed == 1 {
-            new_achievements.push(Achievement {
-                id: "first_exercise".to_string(),
-                title: "Hello, Rust!".to_string(),
-                description: "Completed your first exercise".to_string(),
-                icon: "🦀".to_string(),
-                earned_at: chrono::Utc::now(),
-                category: AchievementCategory::Progress,
-                points: 10,
-            });
-        }
-
-        // Streak achievements
-        if self.current_progress.current_streak == 7 {
-            new_achievements.push(Achievement {
-                id: "week_streak".to_string(),
-                title: "Consistent Learner".to_string(),
-                description: "Maintained a 7-day learning streak".to_string(),
-                icon: "🔥".to_string(),
-                earned_at: chrono::Utc::now(),
-                category: AchievementCategory::Progress,
-                points: 25,
-            });
+    /// Redo of a completed exercise: practise whichever of its concepts are
+    /// due for review; returns whether any were
+    fn record_review(&mut self, exercise_id: &str, chapter: u32, now: chrono::DateTime<chrono::Utc>) -> bool {
+        let due: Vec<String> = self
+            .exercise_history
+            .iter()
+            .filter(|c| c.exercise_id == exercise_id)
+            .flat_map(|c| c.concepts_learned.iter())
+            .filter(|concept| {
+                self.chapters
+                    .get(&chapter)
+                    .and_then(|ch| ch.concept_mastery.get(*concept))
+                    .is_some_and(|mastery| mastery.is_due(now))
+            })
+            .cloned()
+            .collect();
+        if due.is_empty() {
+            return false;
         }
 
-        // Add new achievements to the list
-        self.current_progress.achievements.extend(new_achievements);
+        let outcome = self.take_attempt_outcome(exercise_id);
+        self.practice_concepts(chapter, &due, &outcome, now);
+        self.record_activity(ActivityKind::Completion, now);
+        self.last_updated = now;
+        true
     }
 
     /// Update learning analytics
     fn update_analytics(&mut self) {
-        let analytics = &mut self.current_progress.analytics;
+        let analytics = &mut self.analytics;
 
         // Calculate learning velocity (exercises per week)
-        if !self.current_progress.exercise_history.is_empty() {
-            let first_exercise = self.current_progress.exercise_history.first().unwrap();
+        if let Some(first_exercise) = self.exercise_history.first() {
             let days_learning = (chrono::Utc::now() - first_exercise.completed_at).num_days();
             
             if days_learning > 0 {
                 analytics.learning_velocity = 
-                    (self.current_progress.exercises_completed as f64 * 7.0) / days_learning as f64;
+                    (self.exercises_completed as f64 * 7.0) / days_learning as f64;
             }
         }
 
         // Calculate average session time
-        if !self.current_progress.exercise_history.is_empty() {
+        if !self.exercise_history.is_empty() {
             analytics.average_session_time = 
-                self.current_progress.total_time_minutes as f64 / 
-                self.current_progress.exercise_history.len() as f64;
+                self.total_time_minutes as f64 / 
+                self.exercise_history.len() as f64;
         }
 
         // Update predicted completion time
         if analytics.learning_velocity > 0.0 {
-            let remaining_exercises = 
-                self.current_progress.total_exercises - self.current_progress.exercises_completed;
+            let remaining_exercises = self.total_exercises
+                .saturating_sub(self.exercises_completed);
             analytics.predicted_completion_time = 
                 (remaining_exercises as f64 / analytics.learning_velocity * 7.0) as u32;
         }
     }
+}
 
-    /// Get next recommended exercises
-    pub fn get_recommendations(&self, _limit: usize) -> Result<Vec<String>> {
-        // TODO: Implement sophisticated recommendation algorithm
-        // For now, return simple sequential progression
-        let next_chapter = self.current_progress.chapters_completed + 1;
-        Ok(vec![format!("ch{:02}-ex01", next_chapter)])
+/// Create an empty progress record for a chapter
+fn new_chapter_progress(
+    chapter: u32,
+    total_exercises: u32,
+    started: chrono::DateTime<chrono::Utc>,
+) -> ChapterProgress {
+    ChapterProgress {
+        chapter_number: chapter,
+        title: format!("Chapter {}", chapter),
+        exercises_completed: 0,
+        total_exercises,
+        completion_percentage: 0.0,
+        time_spent_minutes: 0,
+        first_started: Some(started),
+        completed_at: None,
+        concept_mastery: HashMap::new(),
+        is_unlocked: true,
     }
+}
 
-    /// Check if exercise is unlocked for user
-    pub fn is_exercise_unlocked(&self, exercise_id: &str) -> bool {
-        // TODO: Implement based on prerequisites and current progress
-        // For now, allow first 3 chapters
-        let chapter = exercise_id
-            .strip_prefix("ch")
-            .and_then(|s| s[..2].parse::<u32>().ok())
-            .unwrap_or(1);
-        
-        chapter <= 3 || chapter <= self.current_progress.chapters_completed + 2
+/// Parse chapter number from an exercise ID (e.g. "ch03-ex02-variables" -> 3)
+fn chapter_from_id(exercise_id: &str) -> u32 {
+    exercise_id
+        .strip_prefix("ch")
+        .and_then(|s| s.get(..2))
+        .and_then(|s| s.parse::<u32>().ok())
+        .unwrap_or(1)
+}
+
+fn default_timezone() -> String {
+    DEFAULT_TIMEZONE.to_string()
+}
+
+/// Completed / total, treating an empty total as no progress
+fn ratio(completed: u32, total: u32) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        completed as f64 / total as f64
+    }
+}
+
+/// Progress tracker manages one user's learning progress on top of a
+/// [`ProgressStore`], which owns persistence and concurrency
+pub struct ProgressTracker {
+    store: Arc<dyn ProgressStore>,
+    user_id: String,
+    chapter_totals: Arc<HashMap<u32, u32>>,
+    prerequisites: Arc<PrerequisiteGraph>,
+    achievements: Arc<AchievementCatalog>,
+}
+
+impl ProgressTracker {
+    /// Create a new progress tracker for the default user
+    pub fn new<P: AsRef<Path>>(exercises_root: P) -> Result<Self> {
+        Self::for_user(exercises_root, DEFAULT_USER)
+    }
+
+    /// Create a progress tracker for a specific user, backed by JSON files
+    /// in `progress/` next to the exercises directory
+    pub fn for_user<P: AsRef<Path>>(exercises_root: P, user_id: &str) -> Result<Self> {
+        let store = JsonProgressStore::for_exercises(exercises_root);
+        Self::with_store(Arc::new(store), user_id)
+    }
+
+    /// Create a progress tracker for a user on an existing store
+    pub fn with_store(store: Arc<dyn ProgressStore>, user_id: &str) -> Result<Self> {
+        validate_user_id(user_id)?;
+        Ok(Self {
+            store,
+            user_id: user_id.to_string(),
+            chapter_totals: Arc::default(),
+            prerequisites: Arc::default(),
+            achievements: Arc::new(AchievementCatalog::builtin()),
+        })
+    }
+
+    /// Award achievements from `achievements` instead of the built-in ones
+    pub fn set_achievements(&mut self, achievements: Arc<AchievementCatalog>) {
+        self.achievements = achievements;
+    }
+
+    /// User whose progress this tracker records
+    pub fn user_id(&self) -> &str {
+        &self.user_id
+    }
+
+    /// Update exercise totals from the exercises actually present on disk
+    pub fn sync_exercise_totals(&mut self, exercises: &[ExerciseMetadata]) -> Result<()> {
+        let mut chapter_totals = HashMap::new();
+        for exercise in exercises {
+            *chapter_totals.entry(exercise.chapter).or_insert(0) += 1;
+        }
+        self.set_curriculum(Arc::new(chapter_totals), Arc::new(PrerequisiteGraph::new(exercises)));
+        self.sync_stored_totals()
+    }
+
+    /// Count exercises per chapter and order them by prerequisites as
+    /// given, shared with the trackers of other users
+    pub(crate) fn set_curriculum(
+        &mut self,
+        chapter_totals: Arc<HashMap<u32, u32>>,
+        prerequisites: Arc<PrerequisiteGraph>,
+    ) {
+        self.chapter_totals = chapter_totals;
+        self.prerequisites = prerequisites;
+    }
+
+    /// Bring the stored exercise totals up to date with the curriculum
+    pub(crate) fn sync_stored_totals(&self) -> Result<()> {
+        let chapter_totals = self.chapter_totals.as_ref();
+        self.store.update(&self.user_id, &mut |progress| progress.sync_exercise_totals(chapter_totals))?;
+        Ok(())
+    }
+
+    /// Get current progress, with concept mastery decayed and streaks
+    /// brought up to now
+    pub fn get_progress(&self) -> Result<UserProgress> {
+        let mut progress = self.store.load(&self.user_id)?;
+        let now = chrono::Utc::now();
+        progress.refresh_mastery(now);
+        progress.refresh_streaks(now);
+        Ok(progress)
+    }
+
+    /// Set the timezone days and streaks are counted in
+    pub fn set_timezone(&self, timezone: &str) -> Result<()> {
+        if activity::parse_timezone(timezone).is_none() {
+            anyhow::bail!("Unknown timezone '{}'", timezone);
+        }
+        self.store.update(&self.user_id, &mut |progress| {
+            if progress.preferences.timezone == timezone {
+                return false;
+            }
+            progress.preferences.timezone = timezone.to_string();
+            progress.refresh_streaks(chrono::Utc::now());
+            true
+        })?;
+        Ok(())
+    }
+
+    /// Completed exercises worth redoing because their concepts are due for review
+    pub fn review_queue(&self) -> Result<Vec<ReviewItem>> {
+        let progress = self.get_progress()?;
+        Ok(mastery::review_queue(&self.prerequisites, &progress, chrono::Utc::now()))
+    }
+
+    /// Completed exercises matching a query, most recent first
+    pub fn history(&self, query: &HistoryQuery) -> Result<Vec<ExerciseCompletion>> {
+        self.store.history(&self.user_id, query)
     }
-}
\ No newline at end of file
+
+    /// Complete an exercise and update progress; returns the achievements
+    /// it earned
+    pub fn complete_exercise(&self, exercise_id: &str, time_taken_minutes: u32) -> Result<Vec<Achievement>> {
+        self.record_completion(exercise_id, time_taken_minutes, Vec::new())
+    }
+
+    /// Complete an exercise, crediting the concepts it teaches; returns the
+    /// achievements it earned
+    pub fn complete_exercise_with_metadata(
+        &self,
+        metadata: &ExerciseMetadata,
+        time_taken_minutes: u32,
+    ) -> Result<Vec<Achievement>> {
+        self.record_completion(&metadata.id, time_taken_minutes, metadata.concepts.clone())
+    }
+
+    fn record_completion(
+        &self,
+        exercise_id: &str,
+        time_taken_minutes: u32,
+        concepts_learned: Vec<String>,
+    ) -> Result<Vec<Achievement>> {
+        let completion = NewCompletion {
+            exercise_id: exercise_id.to_string(),
+            time_taken_minutes,
+            concepts_learned,
+        };
+        self.store
+            .record_completion(&self.user_id, &completion, &self.chapter_totals)?;
+        self.award_achievements()
+    }
+
+    /// Award the achievements the user's progress now earns
+    pub fn award_achievements(&self) -> Result<Vec<Achievement>> {
+        let mut awarded = Vec::new();
+        self.store.update(&self.user_id, &mut |progress| {
+            awarded = self.achievements.evaluate(progress);
+            !awarded.is_empty()
+        })?;
+        Ok(awarded)
+    }
+
+    /// Check whether an exercise has already been completed
+    pub fn is_completed(&self, exercise_id: &str) -> bool {
+        self.get_progress()
+            .map(|progress| progress.is_completed(exercise_id))
+            .unwrap_or(false)
+    }
+
+    /// Record that the user opened an exercise; returns the achievements
+    /// it earned
+    pub fn record_view(&self, exercise_id: &str) -> Result<Vec<Achievement>> {
+        self.store.record_view(&self.user_id, exercise_id)?;
+        self.award_achievements()
+    }
+
+    /// Record the outcome of a test run, as evidence for concept mastery;
+    /// returns the achievements it earned
+    pub fn record_test_run(&self, exercise_id: &str, passed: bool) -> Result<Vec<Achievement>> {
+        self.store.record_test_run(&self.user_id, exercise_id, passed)?;
+        self.award_achievements()
+    }
+
+    /// Record that the user saved code, as activity for their streak;
+    /// returns the achievements it earned
+    pub fn record_save(&self) -> Result<Vec<Achievement>> {
+        self.store.record_save(&self.user_id)?;
+        self.award_achievements()
+    }
+
+    /// Record that the user revealed a hint level for an exercise
+    pub fn record_hint(&self, exercise_id: &str, hint_level: u32) -> Result<()> {
+        self.store.record_hint(&self.user_id, exercise_id, hint_level)?;
+        Ok(())
+    }
+
+    /// Record whether a revealed hint level helped; returns whether the
+    /// user had revealed that level
+    pub fn record_hint_feedback(&self, exercise_id: &str, hint_level: u32, helpful: bool) -> Result<bool> {
+        let mut found = false;
+        self.store.update(&self.user_id, &mut |progress| {
+            found = progress.record_hint_feedback(exercise_id, hint_level, helpful);
+            found
+        })?;
+        Ok(found)
+    }
+
+    /// Rank the exercises the user should do next
+    pub fn get_recommendations(&self, limit: usize) -> Result<Vec<Recommendation>> {
+        let progress = self.get_progress()?;
+        Ok(recommendations::recommend(&self.prerequisites, &progress, limit))
+    }
+
+    /// Check whether every prerequisite of an exercise has been completed
+    pub fn is_exercise_unlocked(&self, exercise_id: &str) -> Result<bool> {
+        let progress = self.get_progress()?;
+        let completed = progress
+            .exercise_history
+            .iter()
+            .map(|c| c.exercise_id.as_str())
+            .collect();
+        Ok(self.prerequisites.is_unlocked(exercise_id, &completed))
+    }
+}
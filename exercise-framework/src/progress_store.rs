@@ -0,0 +1,238 @@
+use crate::progress::{
+    validate_user_id, ExerciseCompletion, NewCompletion, UserProgress, DEFAULT_USER,
+};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "sqlite")]
+mod sqlite;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteProgressStore;
+
+/// Filter for a user's completed-exercise history
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HistoryQuery {
+    pub exercise_id: Option<String>,
+    pub chapter: Option<u32>,
+    pub since: Option<chrono::DateTime<chrono::Utc>>, // inclusive
+    pub limit: Option<usize>,
+}
+
+impl HistoryQuery {
+    /// Whether a completion passes the filters; the limit is applied by the caller
+    pub fn matches(&self, completion: &ExerciseCompletion) -> bool {
+        self.exercise_id
+            .as_ref()
+            .is_none_or(|id| *id == completion.exercise_id)
+            && self.chapter.is_none_or(|ch| ch == completion.chapter)
+            && self.since.is_none_or(|since| completion.completed_at >= since)
+    }
+}
+
+/// Persistent storage for learner progress.
+///
+/// Every change is a read-modify-write of one user's record through
+/// [`ProgressStore::update`], which the backend performs under its own lock
+/// so concurrent requests never lose each other's updates. The `record_*`
+/// and `history` methods are built on `load`/`update`; backends override
+/// them when they can do better.
+pub trait ProgressStore: Send + Sync {
+    /// Load a user's progress, or a fresh record if they have none yet
+    fn load(&self, user_id: &str) -> Result<UserProgress>;
+
+    /// Atomically apply `change` to a user's progress and return the result.
+    /// `change` reports whether it modified anything; unchanged records are
+    /// not written back.
+    fn update(
+        &self,
+        user_id: &str,
+        change: &mut dyn FnMut(&mut UserProgress) -> bool,
+    ) -> Result<UserProgress>;
+
+    /// Every user with stored progress, sorted
+    fn user_ids(&self) -> Result<Vec<String>>;
+
+    /// Record a completed exercise; completing it again is a no-op
+    fn record_completion(
+        &self,
+        user_id: &str,
+        completion: &NewCompletion,
+        chapter_totals: &HashMap<u32, u32>,
+    ) -> Result<UserProgress> {
+        self.update(user_id, &mut |progress| {
+            progress.record_completion(completion, chapter_totals)
+        })
+    }
+
+    /// Record that a hint level was revealed for an exercise
+    fn record_hint(&self, user_id: &str, exercise_id: &str, hint_level: u32) -> Result<UserProgress> {
+        self.update(user_id, &mut |progress| {
+            progress.record_hint(exercise_id, hint_level);
+            true
+        })
+    }
+
+    /// Record the outcome of running an exercise's tests
+    fn record_test_run(&self, user_id: &str, exercise_id: &str, passed: bool) -> Result<UserProgress> {
+        self.update(user_id, &mut |progress| {
+            progress.record_test_run(exercise_id, passed);
+            true
+        })
+    }
+
+    /// Record that the user saved code
+    fn record_save(&self, user_id: &str) -> Result<UserProgress> {
+        self.update(user_id, &mut |progress| {
+            progress.record_save();
+            true
+        })
+    }
+
+    /// Record that an exercise was opened
+    fn record_view(&self, user_id: &str, exercise_id: &str) -> Result<UserProgress> {
+        self.update(user_id, &mut |progress| {
+            progress.record_view(exercise_id);
+            true
+        })
+    }
+
+    /// Completed exercises matching `query`, most recent first
+    fn history(&self, user_id: &str, query: &HistoryQuery) -> Result<Vec<ExerciseCompletion>> {
+        let progress = self.load(user_id)?;
+        Ok(progress
+            .exercise_history
+            .into_iter()
+            .rev()
+            .filter(|completion| query.matches(completion))
+            .take(query.limit.unwrap_or(usize::MAX))
+            .collect())
+    }
+}
+
+/// Progress kept as one JSON file per user.
+///
+/// Updates hold an exclusive lock on a `.lock` file beside the progress file
+/// and replace it by renaming a fully written temporary file, so neither a
+/// second server process nor a crash mid-write can lose progress.
+#[derive(Debug, Clone)]
+pub struct JsonProgressStore {
+    progress_dir: PathBuf,
+}
+
+impl JsonProgressStore {
+    /// Store progress files under `progress_dir`
+    pub fn new<P: AsRef<Path>>(progress_dir: P) -> Self {
+        Self {
+            progress_dir: progress_dir.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Store progress files in `progress/` next to the exercises directory
+    pub fn for_exercises<P: AsRef<Path>>(exercises_root: P) -> Self {
+        Self::new(
+            exercises_root
+                .as_ref()
+                .parent()
+                .unwrap_or_else(|| Path::new("."))
+                .join("progress"),
+        )
+    }
+
+    /// `user_progress.json` for the default user (where single-learner
+    /// installs have always kept it), `users/<id>/` for everyone else
+    fn progress_file(&self, user_id: &str) -> Result<PathBuf> {
+        validate_user_id(user_id)?;
+        if user_id == DEFAULT_USER {
+            Ok(self.progress_dir.join("user_progress.json"))
+        } else {
+            Ok(self
+                .progress_dir
+                .join("users")
+                .join(user_id)
+                .join("user_progress.json"))
+        }
+    }
+
+    fn read(path: &Path, user_id: &str) -> Result<UserProgress> {
+        if !path.exists() {
+            return Ok(UserProgress::new(user_id));
+        }
+        let content = std::fs::read_to_string(path)?;
+        UserProgress::from_json(&content)
+            .with_context(|| format!("Failed to load progress file {:?}", path))
+    }
+
+    /// Take the writer lock for a progress file, creating its directory
+    fn lock(path: &Path) -> Result<File> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let lock = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path.with_extension("json.lock"))?;
+        lock.lock()
+            .with_context(|| format!("Failed to lock progress file {:?}", path))?;
+        Ok(lock)
+    }
+
+    fn write(path: &Path, progress: &UserProgress) -> Result<()> {
+        let parent = path.parent().unwrap_or_else(|| Path::new("."));
+        let mut staging = tempfile::NamedTempFile::new_in(parent)?;
+        staging.write_all(serde_json::to_string_pretty(progress)?.as_bytes())?;
+        staging.as_file().sync_all()?;
+        staging
+            .persist(path)
+            .with_context(|| format!("Failed to save progress file {:?}", path))?;
+        Ok(())
+    }
+}
+
+impl ProgressStore for JsonProgressStore {
+    fn load(&self, user_id: &str) -> Result<UserProgress> {
+        // Writers rename whole files into place, so readers need no lock
+        Self::read(&self.progress_file(user_id)?, user_id)
+    }
+
+    fn update(
+        &self,
+        user_id: &str,
+        change: &mut dyn FnMut(&mut UserProgress) -> bool,
+    ) -> Result<UserProgress> {
+        let path = self.progress_file(user_id)?;
+        let _lock = Self::lock(&path)?;
+
+        let mut progress = Self::read(&path, user_id)?;
+        if change(&mut progress) {
+            Self::write(&path, &progress)?;
+        }
+        Ok(progress)
+    }
+
+    fn user_ids(&self) -> Result<Vec<String>> {
+        let mut users = Vec::new();
+        if self.progress_dir.join("user_progress.json").exists() {
+            users.push(DEFAULT_USER.to_string());
+        }
+        let users_dir = self.progress_dir.join("users");
+        if users_dir.is_dir() {
+            for entry in std::fs::read_dir(&users_dir)
+                .with_context(|| format!("Failed to read {:?}", users_dir))?
+            {
+                let entry = entry?;
+                let user_id = entry.file_name().to_string_lossy().to_string();
+                if validate_user_id(&user_id).is_ok() && entry.path().join("user_progress.json").exists() {
+                    users.push(user_id);
+                }
+            }
+        }
+        users.sort();
+        users.dedup();
+        Ok(users)
+    }
+}
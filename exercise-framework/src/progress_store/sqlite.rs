@@ -0,0 +1,250 @@
+use super::{HistoryQuery, ProgressStore};
+use crate::progress::{validate_user_id, ExerciseCompletion, UserProgress};
+use anyhow::{Context, Result};
+use rusqlite::types::{Type, Value};
+use rusqlite::Error::FromSqlConversionFailure;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, TransactionBehavior};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Schema migrations in order; `PRAGMA user_version` counts how many have run
+const MIGRATIONS: &[&str] = &[
+    // 1: progress snapshot per user, completions as rows so history can be queried
+    "CREATE TABLE progress (
+        user_id TEXT PRIMARY KEY,
+        data TEXT NOT NULL,
+        updated_at TEXT NOT NULL
+    );
+    CREATE TABLE completions (
+        user_id TEXT NOT NULL,
+        exercise_id TEXT NOT NULL,
+        chapter INTEGER NOT NULL,
+        completed_at TEXT NOT NULL,
+        time_taken_minutes INTEGER NOT NULL,
+        attempts INTEGER NOT NULL,
+        hints_used INTEGER NOT NULL,
+        test_passes INTEGER NOT NULL,
+        test_failures INTEGER NOT NULL,
+        code_quality_score REAL NOT NULL,
+        concepts_learned TEXT NOT NULL,
+        PRIMARY KEY (user_id, exercise_id)
+    );
+    CREATE INDEX completions_by_time ON completions (user_id, completed_at);",
+];
+
+/// Progress kept in an embedded SQLite database.
+///
+/// The `progress` table holds each user's record without its exercise
+/// history, which lives in `completions`. Updates run in an immediate
+/// transaction, so they are serialized across connections and processes.
+pub struct SqliteProgressStore {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteProgressStore {
+    /// Open (or create) the database at `path` and bring its schema up to date
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut conn = Connection::open(path)
+            .with_context(|| format!("Failed to open progress database {:?}", path))?;
+        conn.busy_timeout(Duration::from_secs(5))?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        migrate(&mut conn)?;
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    fn connection(&self) -> Result<std::sync::MutexGuard<'_, Connection>> {
+        self.conn
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Progress database connection poisoned"))
+    }
+}
+
+impl ProgressStore for SqliteProgressStore {
+    fn load(&self, user_id: &str) -> Result<UserProgress> {
+        validate_user_id(user_id)?;
+        // One read transaction, so the snapshot and its completions agree
+        let mut conn = self.connection()?;
+        let tx = conn.transaction()?;
+        read_progress(&tx, user_id)
+    }
+
+    fn update(
+        &self,
+        user_id: &str,
+        change: &mut dyn FnMut(&mut UserProgress) -> bool,
+    ) -> Result<UserProgress> {
+        validate_user_id(user_id)?;
+        let mut conn = self.connection()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+
+        let mut progress = read_progress(&tx, user_id)?;
+        if change(&mut progress) {
+            write_progress(&tx, user_id, &progress)?;
+        }
+        tx.commit()?;
+
+        Ok(progress)
+    }
+
+    fn user_ids(&self) -> Result<Vec<String>> {
+        let conn = self.connection()?;
+        let mut statement = conn.prepare("SELECT user_id FROM progress ORDER BY user_id")?;
+        let users = statement
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(users)
+    }
+
+    fn history(&self, user_id: &str, query: &HistoryQuery) -> Result<Vec<ExerciseCompletion>> {
+        validate_user_id(user_id)?;
+        select_completions(&*self.connection()?, user_id, query, true)
+    }
+}
+
+fn migrate(conn: &mut Connection) -> Result<()> {
+    let tx = conn.transaction_with_behavior(TransactionBehavior::Exclusive)?;
+    let version: usize = tx.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    if version > MIGRATIONS.len() {
+        anyhow::bail!(
+            "Progress database has schema version {}, newer than this release supports ({})",
+            version,
+            MIGRATIONS.len()
+        );
+    }
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        tx.execute_batch(migration)
+            .with_context(|| format!("Failed to apply progress migration {}", index + 1))?;
+    }
+    tx.pragma_update(None, "user_version", MIGRATIONS.len())?;
+    tx.commit()?;
+    Ok(())
+}
+
+fn read_progress(conn: &Connection, user_id: &str) -> Result<UserProgress> {
+    let data: Option<String> = conn
+        .query_row(
+            "SELECT data FROM progress WHERE user_id = ?1",
+            [user_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    let mut progress = match data {
+        Some(data) => UserProgress::from_json(&data)?,
+        None => UserProgress::new(user_id),
+    };
+    progress.exercise_history = select_completions(conn, user_id, &HistoryQuery::default(), false)?;
+    Ok(progress)
+}
+
+fn write_progress(conn: &Connection, user_id: &str, progress: &UserProgress) -> Result<()> {
+    // Completions are append-only, so only new ones need inserting
+    let mut insert = conn.prepare_cached(
+        "INSERT OR IGNORE INTO completions (
+            user_id, exercise_id, chapter, completed_at, time_taken_minutes, attempts,
+            hints_used, test_passes, test_failures, code_quality_score, concepts_learned
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+    )?;
+    for completion in &progress.exercise_history {
+        insert.execute(params![
+            user_id,
+            completion.exercise_id,
+            completion.chapter,
+            format_time(&completion.completed_at),
+            completion.time_taken_minutes,
+            completion.attempts,
+            completion.hints_used,
+            completion.test_passes,
+            completion.test_failures,
+            completion.code_quality_score,
+            serde_json::to_string(&completion.concepts_learned)?,
+        ])?;
+    }
+
+    let mut snapshot = serde_json::to_value(progress)?;
+    snapshot["exercise_history"] = serde_json::Value::Array(Vec::new());
+    conn.execute(
+        "INSERT INTO progress (user_id, data, updated_at) VALUES (?1, ?2, ?3)
+         ON CONFLICT (user_id) DO UPDATE SET data = excluded.data, updated_at = excluded.updated_at",
+        params![
+            user_id,
+            snapshot.to_string(),
+            format_time(&progress.last_updated),
+        ],
+    )?;
+    Ok(())
+}
+
+fn select_completions(
+    conn: &Connection,
+    user_id: &str,
+    query: &HistoryQuery,
+    newest_first: bool,
+) -> Result<Vec<ExerciseCompletion>> {
+    let mut sql = String::from(
+        "SELECT exercise_id, chapter, completed_at, time_taken_minutes, attempts, hints_used,
+                test_passes, test_failures, code_quality_score, concepts_learned
+         FROM completions WHERE user_id = ?",
+    );
+    let mut values = vec![Value::Text(user_id.to_string())];
+
+    if let Some(exercise_id) = &query.exercise_id {
+        sql.push_str(" AND exercise_id = ?");
+        values.push(Value::Text(exercise_id.clone()));
+    }
+    if let Some(chapter) = query.chapter {
+        sql.push_str(" AND chapter = ?");
+        values.push(Value::Integer(chapter.into()));
+    }
+    if let Some(since) = &query.since {
+        sql.push_str(" AND completed_at >= ?");
+        values.push(Value::Text(format_time(since)));
+    }
+    sql.push_str(if newest_first {
+        " ORDER BY completed_at DESC"
+    } else {
+        " ORDER BY completed_at ASC"
+    });
+    if let Some(limit) = query.limit {
+        sql.push_str(" LIMIT ?");
+        values.push(Value::Integer(i64::try_from(limit).unwrap_or(i64::MAX)));
+    }
+
+    let mut statement = conn.prepare(&sql)?;
+    let rows = statement.query_map(params_from_iter(values), |row| {
+        let completed_at: String = row.get(2)?;
+        let concepts_learned: String = row.get(9)?;
+        Ok(ExerciseCompletion {
+            exercise_id: row.get(0)?,
+            chapter: row.get(1)?,
+            completed_at: chrono::DateTime::parse_from_rfc3339(&completed_at)
+                .map_err(|e| FromSqlConversionFailure(2, Type::Text, Box::new(e)))?
+                .with_timezone(&chrono::Utc),
+            time_taken_minutes: row.get(3)?,
+            attempts: row.get(4)?,
+            hints_used: row.get(5)?,
+            test_passes: row.get(6)?,
+            test_failures: row.get(7)?,
+            code_quality_score: row.get(8)?,
+            concepts_learned: serde_json::from_str(&concepts_learned)
+                .map_err(|e| FromSqlConversionFailure(9, Type::Text, Box::new(e)))?,
+        })
+    })?;
+
+    Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+}
+
+/// Fixed-width UTC timestamps, so text order is time order
+fn format_time(time: &chrono::DateTime<chrono::Utc>) -> String {
+    time.to_rfc3339_opts(chrono::SecondsFormat::Micros, true)
+}
@@ -0,0 +1,371 @@
+use crate::exercise::ExerciseDifficulty;
+use crate::mastery;
+use crate::metadata::ExerciseMetadata;
+use crate::progress::{DifficultyPreference, UserProgress};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+// Weights for ranking exercises the learner has not done yet; they sum to 1
+const WEAKNESS_WEIGHT: f64 = 0.4;
+const DIFFICULTY_WEIGHT: f64 = 0.3;
+const SEQUENCE_WEIGHT: f64 = 0.3;
+
+// Reviews top out below a strong new exercise so they never crowd out progress
+const REVIEW_WEIGHT: f64 = 0.6;
+
+/// Concept weakness at which an exercise counts as practising a weak spot
+const WEAK_CONCEPT_THRESHOLD: f64 = 0.5;
+
+/// Why an exercise was recommended
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RecommendationReason {
+    WeakConcepts { concepts: Vec<String> }, // practises concepts the learner struggled with
+    DifficultyFit,                          // matches the learner's difficulty preference
+    NextInCourse,                           // first unlocked exercise in course order
+    Review { concepts: Vec<String> },       // revisits concepts due for spaced review
+}
+
+/// An exercise suggested to a learner, with the reasons behind its rank
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Recommendation {
+    pub exercise_id: String,
+    pub title: String,
+    pub chapter: u32,
+    pub difficulty: ExerciseDifficulty,
+    pub score: f64, // 0.0 to 1.0
+    pub is_review: bool,
+    pub reasons: Vec<RecommendationReason>,
+}
+
+/// Prerequisite DAG over every exercise on disk.
+///
+/// Prerequisites may name an exercise by its full id or by a unique prefix
+/// such as `ch02-ex03`. References that match no exercise are ignored, so a
+/// typo in one metadata file cannot lock learners out; exercises on a
+/// prerequisite cycle can never be unlocked and are reported by
+/// [`PrerequisiteGraph::cyclic`].
+#[derive(Debug, Clone, Default)]
+pub struct PrerequisiteGraph {
+    exercises: Vec<ExerciseMetadata>, // in course order
+    prerequisites: HashMap<String, Vec<String>>, // exercise id -> resolved prerequisite ids
+    unresolved: Vec<(String, String)>, // (exercise id, prerequisite) naming no exercise
+    cyclic: Vec<String>,
+}
+
+impl PrerequisiteGraph {
+    /// Build the graph and order exercises so prerequisites come first,
+    /// breaking ties by chapter and exercise number
+    pub fn new(exercises: &[ExerciseMetadata]) -> Self {
+        let ids: Vec<&str> = exercises.iter().map(|e| e.id.as_str()).collect();
+        let mut prerequisites = HashMap::new();
+        let mut unresolved = Vec::new();
+
+        for exercise in exercises {
+            let mut resolved = Vec::new();
+            for prerequisite in &exercise.prerequisites {
+                match resolve_prerequisite(prerequisite, &ids) {
+                    Some(id) if !resolved.contains(&id) => resolved.push(id),
+                    Some(_) => {}
+                    None => unresolved.push((exercise.id.clone(), prerequisite.clone())),
+                }
+            }
+            prerequisites.insert(exercise.id.clone(), resolved);
+        }
+
+        // Kahn's algorithm; whatever is never freed sits on a cycle
+        let index: HashMap<&str, usize> = ids.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+        let mut remaining: Vec<usize> = exercises
+            .iter()
+            .map(|e| prerequisites[&e.id].len())
+            .collect();
+        let mut dependents: Vec<Vec<usize>> = vec![Vec::new(); exercises.len()];
+        for (i, exercise) in exercises.iter().enumerate() {
+            for prerequisite in &prerequisites[&exercise.id] {
+                dependents[index[prerequisite.as_str()]].push(i);
+            }
+        }
+
+        let course_key = |i: usize| Reverse((exercises[i].chapter, exercises[i].exercise_number, i));
+        let mut ready: BinaryHeap<_> = (0..exercises.len())
+            .filter(|&i| remaining[i] == 0)
+            .map(course_key)
+            .collect();
+        let mut order = Vec::with_capacity(exercises.len());
+        while let Some(Reverse((_, _, i))) = ready.pop() {
+            order.push(i);
+            for &dependent in &dependents[i] {
+                remaining[dependent] -= 1;
+                if remaining[dependent] == 0 {
+                    ready.push(course_key(dependent));
+                }
+            }
+        }
+
+        let placed: HashSet<usize> = order.iter().copied().collect();
+        let mut cyclic: Vec<usize> = (0..exercises.len()).filter(|i| !placed.contains(i)).collect();
+        cyclic.sort_by_key(|&i| course_key(i).0);
+
+        Self {
+            exercises: order
+                .iter()
+                .chain(&cyclic)
+                .map(|&i| exercises[i].clone())
+                .collect(),
+            prerequisites,
+            unresolved,
+            cyclic: cyclic.iter().map(|&i| exercises[i].id.clone()).collect(),
+        }
+    }
+
+    /// All exercises, prerequisites before the exercises that need them
+    pub fn exercises(&self) -> &[ExerciseMetadata] {
+        &self.exercises
+    }
+
+    /// Look up an exercise by id
+    pub fn get(&self, exercise_id: &str) -> Option<&ExerciseMetadata> {
+        self.exercises.iter().find(|e| e.id == exercise_id)
+    }
+
+    /// Resolved prerequisite ids of an exercise
+    pub fn prerequisites_of(&self, exercise_id: &str) -> &[String] {
+        self.prerequisites
+            .get(exercise_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Prerequisite references that name no exercise, as (exercise, prerequisite)
+    pub fn unresolved(&self) -> &[(String, String)] {
+        &self.unresolved
+    }
+
+    /// Exercises on a prerequisite cycle
+    pub fn cyclic(&self) -> &[String] {
+        &self.cyclic
+    }
+
+    /// Whether every prerequisite of an exercise has been completed.
+    /// Exercises the graph does not know about have nothing to wait for.
+    pub fn is_unlocked(&self, exercise_id: &str, completed: &HashSet<&str>) -> bool {
+        !self.cyclic.iter().any(|id| id == exercise_id)
+            && self
+                .prerequisites_of(exercise_id)
+                .iter()
+                .all(|prerequisite| completed.contains(prerequisite.as_str()))
+    }
+
+    /// Exercises the learner may open, in course order
+    pub fn unlocked<'a>(&'a self, completed: &'a HashSet<&str>) -> impl Iterator<Item = &'a ExerciseMetadata> {
+        self.exercises
+            .iter()
+            .filter(move |exercise| self.is_unlocked(&exercise.id, completed))
+    }
+}
+
+/// Match a prerequisite to an exercise id, exactly or as a unique `<prefix>-` match
+fn resolve_prerequisite(prerequisite: &str, ids: &[&str]) -> Option<String> {
+    if ids.contains(&prerequisite) {
+        return Some(prerequisite.to_string());
+    }
+
+    let prefix = format!("{}-", prerequisite);
+    let mut matches = ids.iter().filter(|id| id.starts_with(&prefix));
+    match (matches.next(), matches.next()) {
+        (Some(id), None) => Some(id.to_string()),
+        _ => None,
+    }
+}
+
+/// Rank what the learner should do next: unlocked exercises they have not
+/// completed, scored on how much they practise weak concepts, how well their
+/// difficulty suits the learner's preference and how early they come in the
+/// course, plus completed exercises whose concepts are due for review
+pub fn recommend(graph: &PrerequisiteGraph, progress: &UserProgress, limit: usize) -> Vec<Recommendation> {
+    let completed: HashSet<&str> = progress
+        .exercise_history
+        .iter()
+        .map(|c| c.exercise_id.as_str())
+        .collect();
+    let weakness = concept_weakness(graph, progress);
+    let target_level = target_difficulty(graph, progress);
+
+    let mut recommendations: Vec<Recommendation> = graph
+        .unlocked(&completed)
+        .filter(|exercise| !completed.contains(exercise.id.as_str()))
+        .enumerate()
+        .map(|(position, exercise)| {
+            let mut reasons = Vec::new();
+
+            let weak_concepts: Vec<String> = exercise
+                .concepts
+                .iter()
+                .filter(|c| weakness.get(*c).copied().unwrap_or(0.0) >= WEAK_CONCEPT_THRESHOLD)
+                .cloned()
+                .collect();
+            let weakness_score = exercise
+                .concepts
+                .iter()
+                .filter_map(|c| weakness.get(c))
+                .fold(0.0, |max: f64, w| max.max(*w));
+            if !weak_concepts.is_empty() {
+                reasons.push(RecommendationReason::WeakConcepts { concepts: weak_concepts });
+            }
+
+            let difficulty_fit = 1.0 - (difficulty_level(exercise) - target_level).abs() / 2.0;
+            if difficulty_fit >= 0.75 {
+                reasons.push(RecommendationReason::DifficultyFit);
+            }
+
+            let sequence_score = 1.0 / (1.0 + position as f64);
+            if position == 0 {
+                reasons.push(RecommendationReason::NextInCourse);
+            }
+
+            Recommendation {
+                exercise_id: exercise.id.clone(),
+                title: exercise.title.clone(),
+                chapter: exercise.chapter,
+                difficulty: exercise.difficulty,
+                score: WEAKNESS_WEIGHT * weakness_score
+                    + DIFFICULTY_WEIGHT * difficulty_fit
+                    + SEQUENCE_WEIGHT * sequence_score,
+                is_review: false,
+                reasons,
+            }
+        })
+        .collect();
+
+    recommendations.extend(reviews(graph, progress));
+
+    // Stable sort keeps course order among equal scores
+    recommendations.sort_by(|a, b| b.score.total_cmp(&a.score));
+    recommendations.truncate(limit);
+    recommendations
+}
+
+/// Concepts an exercise teaches, from its metadata when it is still on disk
+fn exercise_concepts<'a>(graph: &'a PrerequisiteGraph, exercise_id: &str, recorded: &'a [String]) -> &'a [String] {
+    graph
+        .get(exercise_id)
+        .map(|e| e.concepts.as_slice())
+        .unwrap_or(recorded)
+}
+
+/// How much the learner has struggled with each concept, 0.0 to 1.0
+fn concept_weakness(graph: &PrerequisiteGraph, progress: &UserProgress) -> HashMap<String, f64> {
+    let mut weakness: HashMap<String, f64> = HashMap::new();
+    let mut raise = |concept: &str, value: f64| {
+        let entry = weakness.entry(concept.to_string()).or_insert(0.0);
+        *entry = entry.max(value.clamp(0.0, 1.0));
+    };
+
+    // Each of the three hint levels needed counts as a third of a struggle
+    for completion in &progress.exercise_history {
+        for concept in exercise_concepts(graph, &completion.exercise_id, &completion.concepts_learned) {
+            raise(concept, completion.hints_used as f64 / 3.0);
+        }
+    }
+    for attempt in progress.active_exercises.values() {
+        if let Some(exercise) = graph.get(&attempt.exercise_id) {
+            for concept in &exercise.concepts {
+                raise(concept, attempt.hints_used.len() as f64 / 3.0);
+            }
+        }
+    }
+
+    for chapter in progress.chapters.values() {
+        for (concept, mastery) in &chapter.concept_mastery {
+            raise(concept, 1.0 - mastery.mastery_level);
+            if mastery.needs_review {
+                raise(concept, WEAK_CONCEPT_THRESHOLD);
+            }
+        }
+    }
+    for concept in &progress.analytics.concept_weaknesses {
+        raise(concept, 0.8);
+    }
+
+    weakness
+}
+
+/// Difficulty as a number: beginner 0, intermediate 1, advanced 2
+fn difficulty_level(exercise: &ExerciseMetadata) -> f64 {
+    match exercise.difficulty {
+        ExerciseDifficulty::Beginner => 0.0,
+        ExerciseDifficulty::Intermediate => 1.0,
+        ExerciseDifficulty::Advanced => 2.0,
+    }
+}
+
+/// Difficulty level the learner should be offered next
+fn target_difficulty(graph: &PrerequisiteGraph, progress: &UserProgress) -> f64 {
+    let history: Vec<(f64, u32)> = progress
+        .exercise_history
+        .iter()
+        .filter_map(|c| graph.get(&c.exercise_id).map(|e| (difficulty_level(e), c.hints_used)))
+        .collect();
+    if history.is_empty() {
+        return match progress.preferences.difficulty_preference {
+            DifficultyPreference::Challenge => 2.0,
+            _ => 0.0,
+        };
+    }
+
+    let target = match progress.preferences.difficulty_preference {
+        // Follow recent work, stepping up when it came easily and down when it took hints
+        DifficultyPreference::Adaptive => {
+            let recent = &history[history.len().saturating_sub(5)..];
+            let level = recent.iter().map(|(l, _)| l).sum::<f64>() / recent.len() as f64;
+            let hints = recent.iter().map(|(_, h)| *h as f64).sum::<f64>() / recent.len() as f64;
+            if hints < 1.0 {
+                level + 0.5
+            } else if hints >= 2.0 {
+                level - 0.5
+            } else {
+                level
+            }
+        }
+        // Stay at the hardest level reached or move one step beyond it
+        DifficultyPreference::Progressive => {
+            history.iter().map(|(l, _)| *l).fold(0.0, f64::max) + 0.5
+        }
+        // Keep to the level the learner completes most often
+        DifficultyPreference::Consistent => {
+            let mut counts = [0usize; 3];
+            for (level, _) in &history {
+                counts[*level as usize] += 1;
+            }
+            (0..3).max_by_key(|&l| (counts[l], Reverse(l))).unwrap_or(0) as f64
+        }
+        DifficultyPreference::Challenge => 2.0,
+    };
+
+    target.clamp(0.0, 2.0)
+}
+
+/// Completed exercises from the spaced-repetition review queue
+fn reviews(graph: &PrerequisiteGraph, progress: &UserProgress) -> Vec<Recommendation> {
+    mastery::review_queue(graph, progress, chrono::Utc::now())
+        .into_iter()
+        .filter_map(|item| {
+            let exercise = graph.get(&item.exercise_id)?;
+            // Urgency is 0.5 when recall has fallen to 90% (the due date) and
+            // reaches 1.0 at 80%
+            let urgency = ((1.0 - item.retrievability) * 5.0).min(1.0);
+            Some(Recommendation {
+                exercise_id: item.exercise_id,
+                title: item.title,
+                chapter: item.chapter,
+                difficulty: exercise.difficulty,
+                score: REVIEW_WEIGHT * urgency,
+                is_review: true,
+                reasons: vec![RecommendationReason::Review {
+                    concepts: item.concepts.into_iter().map(|c| c.concept).collect(),
+                }],
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,514 @@
+use crate::metadata::TestConfig;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::path::Path;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Default wall-clock budget for compiling an exercise
+pub const DEFAULT_BUILD_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Address space for cargo and each rustc while compiling an exercise;
+/// rustc maps several hundred MB before compiling anything
+pub const BUILD_MEMORY_LIMIT_MB: u64 = 4096;
+
+/// Resource limits applied to a sandboxed process and all of its children
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SandboxLimits {
+    pub wall_timeout: Duration,
+    pub cpu_seconds: Option<u64>,      // RLIMIT_CPU, per process
+    pub memory_limit_mb: Option<u64>,  // RLIMIT_AS, per process
+    pub allow_network: bool,
+}
+
+impl SandboxLimits {
+    /// Limits for running a learner's compiled program directly
+    pub fn for_program(config: &TestConfig) -> Self {
+        let timeout = u64::from(config.timeout_seconds.max(1));
+        Self {
+            wall_timeout: Duration::from_secs(timeout),
+            cpu_seconds: Some(timeout),
+            memory_limit_mb: Some(u64::from(config.memory_limit_mb.max(1))),
+            allow_network: false,
+        }
+    }
+
+    /// Limits for running an exercise's test harnesses directly.
+    ///
+    /// Harnesses get the same limits as the program. Exercise tests that
+    /// shell out to `cargo run` or `cargo check` still fit, as long as
+    /// everything is built beforehand: rustc itself does not fit in a
+    /// learner-sized address space.
+    pub fn for_tests(config: &TestConfig) -> Self {
+        Self::for_program(config)
+    }
+
+    /// Limits for cargo compiling an exercise. Build scripts and proc
+    /// macros run here too, so there is no network: dependencies are
+    /// fetched beforehand under [`SandboxLimits::for_fetch`] and the build
+    /// runs offline (see [`offline`]).
+    pub fn for_build(timeout: Duration) -> Self {
+        Self {
+            wall_timeout: timeout,
+            cpu_seconds: Some(timeout.as_secs().max(1)),
+            memory_limit_mb: Some(BUILD_MEMORY_LIMIT_MB),
+            allow_network: false,
+        }
+    }
+
+    /// Limits for `cargo fetch` downloading an exercise's dependencies; no
+    /// code from the exercise or its dependencies runs
+    pub fn for_fetch(timeout: Duration) -> Self {
+        Self {
+            wall_timeout: timeout,
+            cpu_seconds: Some(timeout.as_secs().max(1)),
+            memory_limit_mb: Some(BUILD_MEMORY_LIMIT_MB),
+            allow_network: true,
+        }
+    }
+}
+
+/// Captured result of a sandboxed process
+#[derive(Debug)]
+pub struct SandboxOutput {
+    pub status: ExitStatus,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    pub elapsed: Duration,
+    pub timed_out: bool,
+    pub cancelled: bool,
+    pub network_isolated: bool,
+}
+
+impl SandboxOutput {
+    /// Whether the process exited successfully within its limits
+    pub fn success(&self) -> bool {
+        !self.timed_out && !self.cancelled && self.status.success()
+    }
+
+    /// Explanation for a run that was stopped by one of the sandbox limits
+    pub fn limit_message(&self, limits: &SandboxLimits) -> Option<String> {
+        if self.timed_out {
+            return Some(format!(
+                "Execution timed out after {} seconds",
+                limits.wall_timeout.as_secs()
+            ));
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::ExitStatusExt;
+            if self.status.signal() == Some(libc::SIGXCPU) {
+                return Some(format!(
+                    "CPU time limit of {} seconds exceeded",
+                    limits.cpu_seconds.unwrap_or_default()
+                ));
+            }
+        }
+
+        // Exceeding RLIMIT_AS makes allocations fail, which Rust reports on stderr
+        let stderr = String::from_utf8_lossy(&self.stderr);
+        match limits.memory_limit_mb {
+            Some(mb) if stderr.contains("memory allocation of") => {
+                Some(format!("Memory limit of {} MB exceeded", mb))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Runs commands under a set of resource limits.
+///
+/// On Unix every process gets its own process group so a timeout can kill
+/// anything it spawned, plus rlimits for CPU time and address space. On
+/// Linux, network access is removed by moving the process into fresh user
+/// and network namespaces when unprivileged namespaces are available.
+#[derive(Debug, Clone)]
+pub struct Sandbox {
+    limits: SandboxLimits,
+}
+
+impl Sandbox {
+    /// Create a sandbox with the given limits
+    pub fn new(limits: SandboxLimits) -> Self {
+        Self { limits }
+    }
+
+    /// Limits enforced by this sandbox
+    pub fn limits(&self) -> &SandboxLimits {
+        &self.limits
+    }
+
+    /// Whether spawned processes will actually be cut off from the network
+    pub fn isolates_network(&self) -> bool {
+        !self.limits.allow_network && network_isolation_available()
+    }
+
+    /// Apply process-level limits to a command before it is spawned.
+    ///
+    /// The wall-clock timeout is enforced by [`Sandbox::run`] (or `run_async`),
+    /// not by the command itself.
+    pub fn confine(&self, command: &mut Command) {
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+
+            command.process_group(0);
+
+            let setup = ChildSetup::new(&self.limits, self.isolates_network());
+            // SAFETY: the closure only calls async-signal-safe libc functions
+            // on data prepared before the fork
+            unsafe {
+                command.pre_exec(move || setup.apply());
+            }
+        }
+    }
+
+    /// Spawn a confined command, wait for it within the wall-clock timeout
+    /// and collect its output
+    pub fn run(&self, mut command: Command) -> Result<SandboxOutput> {
+        self.confine(&mut command);
+        command.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped());
+
+        let start = Instant::now();
+        let mut child = command.spawn().context("Failed to spawn sandboxed process")?;
+        let stdout = read_in_background(child.stdout.take());
+        let stderr = read_in_background(child.stderr.take());
+
+        let deadline = start + self.limits.wall_timeout;
+        let mut timed_out = false;
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if Instant::now() >= deadline {
+                timed_out = true;
+                kill_child(&mut child);
+                break child.wait()?;
+            }
+            thread::sleep(Duration::from_millis(20));
+        };
+
+        // Background processes left in the group would keep the pipes open
+        kill_process_group(child.id());
+
+        Ok(SandboxOutput {
+            status,
+            stdout: stdout.join().unwrap_or_default(),
+            stderr: stderr.join().unwrap_or_default(),
+            elapsed: start.elapsed(),
+            timed_out,
+            cancelled: false,
+            network_isolated: self.isolates_network(),
+        })
+    }
+
+    /// Async variant of [`Sandbox::run`] for use inside a tokio runtime
+    #[cfg(feature = "async")]
+    pub async fn run_async(&self, command: Command) -> Result<SandboxOutput> {
+        self.run_streaming(command, None, std::future::pending()).await
+    }
+
+    /// Like [`Sandbox::run_async`], but forwards each output line as soon as
+    /// it is produced and kills the process group early once `cancel` resolves
+    #[cfg(feature = "async")]
+    pub async fn run_streaming<F>(
+        &self,
+        mut command: Command,
+        lines: Option<tokio::sync::mpsc::UnboundedSender<OutputLine>>,
+        cancel: F,
+    ) -> Result<SandboxOutput>
+    where
+        F: std::future::Future<Output = ()>,
+    {
+        self.confine(&mut command);
+        command.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped());
+
+        let start = Instant::now();
+        let mut child = tokio::process::Command::from(command)
+            .kill_on_drop(true)
+            .spawn()
+            .context("Failed to spawn sandboxed process")?;
+        let pid = child.id();
+
+        let stdout = tokio::spawn(forward_lines(child.stdout.take(), OutputStream::Stdout, lines.clone()));
+        let stderr = tokio::spawn(forward_lines(child.stderr.take(), OutputStream::Stderr, lines));
+
+        let (timed_out, cancelled) = tokio::select! {
+            result = tokio::time::timeout(self.limits.wall_timeout, child.wait()) => (result.is_err(), false),
+            _ = cancel => (false, true),
+        };
+        if timed_out || cancelled {
+            if let Some(pid) = pid {
+                kill_process_group(pid);
+            }
+            let _ = child.start_kill();
+        }
+        let status = child.wait().await?;
+
+        // Background processes left in the group would keep the pipes open
+        if let Some(pid) = pid {
+            kill_process_group(pid);
+        }
+
+        Ok(SandboxOutput {
+            status,
+            stdout: stdout.await.unwrap_or_default(),
+            stderr: stderr.await.unwrap_or_default(),
+            elapsed: start.elapsed(),
+            timed_out,
+            cancelled,
+            network_isolated: self.isolates_network(),
+        })
+    }
+}
+
+/// Make a cargo command use only dependencies that are already fetched
+pub fn offline(command: &mut Command) -> &mut Command {
+    command.env("CARGO_NET_OFFLINE", "true")
+}
+
+/// Crates an exercise's `Cargo.toml` depends on, from every dependency
+/// table including target-specific ones, sorted and deduplicated
+pub fn declared_dependencies(package_dir: &Path) -> Result<Vec<String>> {
+    let path = package_dir.join("Cargo.toml");
+    let content = std::fs::read_to_string(&path).with_context(|| format!("Failed to read {:?}", path))?;
+    let manifest: toml::Table = toml::from_str(&content).with_context(|| format!("Invalid manifest {:?}", path))?;
+
+    let mut tables = vec![&manifest];
+    if let Some(targets) = manifest.get("target").and_then(toml::Value::as_table) {
+        tables.extend(targets.values().filter_map(toml::Value::as_table));
+    }
+
+    let mut crates: Vec<String> = tables
+        .into_iter()
+        .flat_map(|table| {
+            ["dependencies", "dev-dependencies", "build-dependencies"]
+                .into_iter()
+                .filter_map(|key| table.get(key).and_then(toml::Value::as_table))
+                .flat_map(|deps| deps.keys().cloned())
+        })
+        .collect();
+    crates.sort();
+    crates.dedup();
+    Ok(crates)
+}
+
+/// Why an exercise with these dependencies may not be built under its
+/// `TestConfig`, if it may not: `allow_std_only` exercises must not depend
+/// on any crate
+pub fn dependency_violation(config: &TestConfig, crates: &[String]) -> Option<String> {
+    if !config.allow_std_only || crates.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "This exercise only allows the standard library, but Cargo.toml depends on: {}",
+        crates.join(", ")
+    ))
+}
+
+/// Which pipe a line of output came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// One line of output from a streaming sandboxed process
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputLine {
+    pub stream: OutputStream,
+    pub text: String, // without the trailing newline
+}
+
+/// Kill every process in the group led by `pid`
+pub fn kill_process_group(pid: u32) {
+    #[cfg(unix)]
+    {
+        if let Ok(pid) = libc::pid_t::try_from(pid) {
+            // SAFETY: plain syscall; a negative pid addresses the process group
+            unsafe {
+                libc::kill(-pid, libc::SIGKILL);
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = pid;
+    }
+}
+
+/// Whether this host lets unprivileged processes create network namespaces
+pub fn network_isolation_available() -> bool {
+    static AVAILABLE: OnceLock<bool> = OnceLock::new();
+
+    *AVAILABLE.get_or_init(|| {
+        #[cfg(target_os = "linux")]
+        {
+            use std::os::unix::process::CommandExt;
+
+            let setup = ChildSetup::network_probe();
+            let mut probe = Command::new("sh");
+            probe.args(["-c", "exit 0"])
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null());
+            // SAFETY: see `Sandbox::confine`
+            unsafe {
+                probe.pre_exec(move || setup.apply());
+            }
+            probe.status().map(|s| s.success()).unwrap_or(false)
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            false
+        }
+    })
+}
+
+fn kill_child(child: &mut Child) {
+    kill_process_group(child.id());
+    let _ = child.kill();
+}
+
+/// Collect a pipe's output, forwarding each line to `lines` along the way
+#[cfg(feature = "async")]
+async fn forward_lines<R>(
+    pipe: Option<R>,
+    stream: OutputStream,
+    lines: Option<tokio::sync::mpsc::UnboundedSender<OutputLine>>,
+) -> Vec<u8>
+where
+    R: tokio::io::AsyncRead + Unpin,
+{
+    use tokio::io::AsyncBufReadExt;
+
+    let mut buf = Vec::new();
+    let Some(pipe) = pipe else {
+        return buf;
+    };
+
+    let mut reader = tokio::io::BufReader::new(pipe);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        match reader.read_until(b'\n', &mut line).await {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {
+                buf.extend_from_slice(&line);
+                if let Some(lines) = &lines {
+                    let text = String::from_utf8_lossy(&line);
+                    let _ = lines.send(OutputLine {
+                        stream,
+                        text: text.trim_end_matches(['\n', '\r']).to_string(),
+                    });
+                }
+            }
+        }
+    }
+    buf
+}
+
+fn read_in_background<R: Read + Send + 'static>(pipe: Option<R>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    })
+}
+
+/// Everything the child needs between fork and exec, prepared up front
+/// because allocating after fork is not safe
+#[cfg(unix)]
+struct ChildSetup {
+    cpu_seconds: Option<u64>,
+    memory_bytes: Option<u64>,
+    isolate_network: bool,
+    uid_map: Vec<u8>,
+    gid_map: Vec<u8>,
+}
+
+#[cfg(unix)]
+impl ChildSetup {
+    fn new(limits: &SandboxLimits, isolate_network: bool) -> Self {
+        // SAFETY: getuid/getgid cannot fail
+        let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+        Self {
+            cpu_seconds: limits.cpu_seconds,
+            memory_bytes: limits.memory_limit_mb.map(|mb| mb.saturating_mul(1024 * 1024)),
+            isolate_network,
+            // Map our own ids so files created inside the namespace keep their owner
+            uid_map: format!("{uid} {uid} 1").into_bytes(),
+            gid_map: format!("{gid} {gid} 1").into_bytes(),
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn network_probe() -> Self {
+        Self::new(&SandboxLimits::for_build(Duration::ZERO), true)
+    }
+
+    fn apply(&self) -> std::io::Result<()> {
+        set_rlimit(libc::RLIMIT_CORE as _, 0, 0)?;
+        if let Some(seconds) = self.cpu_seconds {
+            // The soft limit delivers SIGXCPU; the hard limit one second later SIGKILL
+            set_rlimit(libc::RLIMIT_CPU as _, seconds, seconds + 1)?;
+        }
+        if let Some(bytes) = self.memory_bytes {
+            set_rlimit(libc::RLIMIT_AS as _, bytes, bytes)?;
+        }
+        #[cfg(target_os = "linux")]
+        if self.isolate_network {
+            self.unshare_network()?;
+        }
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    fn unshare_network(&self) -> std::io::Result<()> {
+        // SAFETY: unshare only affects the calling (single-threaded, forked) process
+        if unsafe { libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNET) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        write_proc_file(b"/proc/self/setgroups\0", b"deny")?;
+        write_proc_file(b"/proc/self/uid_map\0", &self.uid_map)?;
+        write_proc_file(b"/proc/self/gid_map\0", &self.gid_map)?;
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+fn set_rlimit(resource: i32, soft: u64, hard: u64) -> std::io::Result<()> {
+    let limit = libc::rlimit {
+        rlim_cur: soft as libc::rlim_t,
+        rlim_max: hard as libc::rlim_t,
+    };
+    // SAFETY: `limit` is a valid rlimit for the duration of the call
+    if unsafe { libc::setrlimit(resource as _, &limit) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn write_proc_file(path: &[u8], contents: &[u8]) -> std::io::Result<()> {
+    // SAFETY: `path` is NUL-terminated and `contents` outlives the write
+    unsafe {
+        let fd = libc::open(path.as_ptr().cast(), libc::O_WRONLY | libc::O_CLOEXEC);
+        if fd < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        let written = libc::write(fd, contents.as_ptr().cast(), contents.len());
+        libc::close(fd);
+        if written < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
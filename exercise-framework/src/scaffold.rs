@@ -0,0 +1,441 @@
+use crate::exercise::{Exercise, ExerciseDifficulty, ExerciseType};
+use crate::metadata::{
+    ClippyLevel, ExerciseMetadata, HintConfig, RustBookRefs, TestConfig, ValidationConfig, METADATA_SCHEMA_VERSION,
+};
+use crate::validation::{ExerciseValidator, ValidationResult};
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+/// What an author decides about a new exercise; everything else in the
+/// skeleton (number, id, directory, package name) is derived from it
+#[derive(Debug, Clone)]
+pub struct ExerciseScaffold {
+    pub chapter: u32,
+    pub chapter_topic: Option<String>, // names the chapter directory if it does not exist yet
+    pub name: String,                  // snake_case, e.g. "temperature_conversion"
+    pub title: String,
+    pub description: String,
+    pub difficulty: ExerciseDifficulty,
+    pub exercise_type: ExerciseType,
+    pub estimated_time_minutes: u32,
+    pub concepts: Vec<String>,
+    pub prerequisites: Vec<String>,
+    pub primary_book_chapter: String, // e.g. "3.2"
+}
+
+/// A generated exercise, with the validator's verdict on the skeleton
+#[derive(Debug, Clone)]
+pub struct ScaffoldedExercise {
+    pub path: PathBuf,
+    pub metadata: ExerciseMetadata,
+    pub validation: ValidationResult,
+    pub registered_member: Option<String>, // added to the workspace `members`, if no entry covered it
+}
+
+impl ExerciseScaffold {
+    /// Where the exercise will go and the metadata it will get, without
+    /// touching the filesystem
+    pub fn plan<P: AsRef<Path>>(&self, exercises_root: P) -> Result<(PathBuf, ExerciseMetadata)> {
+        let exercises_root = exercises_root.as_ref();
+        validate_name(&self.name)?;
+
+        let chapter_dir = match find_chapter_dir(exercises_root, self.chapter)? {
+            Some(dir) => dir,
+            None => {
+                let topic = self.chapter_topic.as_deref().with_context(|| {
+                    format!("Chapter {} has no directory yet; give it a topic", self.chapter)
+                })?;
+                validate_name(topic)?;
+                exercises_root.join(format!("ch{:02}_{}", self.chapter, topic))
+            }
+        };
+
+        let exercise_number = next_exercise_number(exercises_root, self.chapter)?;
+        let exercise_dir = chapter_dir.join(format!("ex{:02}_{}", exercise_number, self.name));
+        if exercise_dir.exists() {
+            anyhow::bail!("Exercise directory {:?} already exists", exercise_dir);
+        }
+
+        let metadata = ExerciseMetadata {
+            schema_version: METADATA_SCHEMA_VERSION,
+            id: format!(
+                "ch{:02}-ex{:02}-{}",
+                self.chapter,
+                exercise_number,
+                self.name.replace('_', "-")
+            ),
+            title: self.title.clone(),
+            description: self.description.clone(),
+            chapter: self.chapter,
+            exercise_number,
+            difficulty: self.difficulty,
+            estimated_time_minutes: self.estimated_time_minutes,
+            concepts: self.concepts.clone(),
+            prerequisites: self.prerequisites.clone(),
+            exercise_type: self.exercise_type,
+            rust_book_refs: RustBookRefs {
+                primary_chapter: self.primary_book_chapter.clone(),
+                supporting_chapters: Vec::new(),
+                specific_sections: Vec::new(),
+            },
+            hints: HintConfig {
+                available: 3,
+                auto_unlock: false,
+                custom_hints: None,
+            },
+            testing: TestConfig {
+                timeout_seconds: 10,
+                memory_limit_mb: 50,
+                allow_std_only: true,
+                custom_checks: Vec::new(),
+            },
+            validation: ValidationConfig {
+                clippy_level: ClippyLevel::Warn,
+                format_required: true,
+                custom_checks: Vec::new(),
+                performance_requirements: None,
+            },
+            editable_files: Vec::new(),
+        };
+        metadata.validate()?;
+
+        Ok((exercise_dir, metadata))
+    }
+
+    /// Write the exercise skeleton, register it with the workspace next to
+    /// `exercises_root` and run the validator over the result
+    pub fn create<P: AsRef<Path>>(&self, exercises_root: P) -> Result<ScaffoldedExercise> {
+        let exercises_root = exercises_root.as_ref();
+        let (path, metadata) = self.plan(exercises_root)?;
+
+        let files = [
+            ("metadata.json", serde_json::to_string_pretty(&metadata)?),
+            ("Cargo.toml", cargo_toml(&self.name)),
+            ("README.md", readme(&metadata)),
+            ("hints.md", hints(&metadata)),
+            ("src/main.rs", main_template(&metadata)),
+            ("tests/unit_tests.rs", unit_tests()),
+            ("solutions/reference.rs", reference_solution(&metadata)),
+            ("solutions/reference.md", reference_explanation(&metadata)),
+        ];
+        for (name, content) in files {
+            let file = path.join(name);
+            if let Some(parent) = file.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&file, content)
+                .with_context(|| format!("Failed to write {:?}", file))?;
+        }
+
+        let workspace_root = exercises_root.parent().unwrap_or_else(|| Path::new("."));
+        let manifest = workspace_root.join("Cargo.toml");
+        let registered_member = if manifest.exists() {
+            let member = path
+                .strip_prefix(workspace_root)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .replace('\\', "/");
+            register_workspace_member(&manifest, &member)?.then_some(member)
+        } else {
+            None
+        };
+
+        let exercise = Exercise::load_from_path(&path)?;
+        let validation = ExerciseValidator::new().validate(&exercise)?;
+
+        Ok(ScaffoldedExercise {
+            path,
+            metadata,
+            validation,
+            registered_member,
+        })
+    }
+}
+
+/// The `chXX_topic` directory of a chapter, if it exists
+pub fn find_chapter_dir<P: AsRef<Path>>(exercises_root: P, chapter: u32) -> Result<Option<PathBuf>> {
+    let prefix = format!("ch{:02}_", chapter);
+    for entry in std::fs::read_dir(exercises_root.as_ref())? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() && entry.file_name().to_string_lossy().starts_with(&prefix) {
+            return Ok(Some(entry.path()));
+        }
+    }
+    Ok(None)
+}
+
+/// The `exercise_number` the next exercise in a chapter should get
+pub fn next_exercise_number<P: AsRef<Path>>(exercises_root: P, chapter: u32) -> Result<u32> {
+    let last = Exercise::list_all(exercises_root)?
+        .into_iter()
+        .filter(|metadata| metadata.chapter == chapter)
+        .map(|metadata| metadata.exercise_number)
+        .max()
+        .unwrap_or(0);
+    Ok(last + 1)
+}
+
+/// Add `member` to the `[workspace]` members of `manifest` unless an
+/// existing entry (glob or path) already covers it. Returns whether the
+/// manifest was changed.
+pub fn register_workspace_member(manifest: &Path, member: &str) -> Result<bool> {
+    let content = std::fs::read_to_string(manifest)
+        .with_context(|| format!("Failed to read {:?}", manifest))?;
+
+    let members_start = content
+        .find("[workspace]")
+        .and_then(|workspace| content[workspace..].find("members").map(|i| workspace + i))
+        .and_then(|members| content[members..].find('[').map(|i| members + i + 1))
+        .with_context(|| format!("No [workspace] members list in {:?}", manifest))?;
+    let members_end = content[members_start..]
+        .find(']')
+        .map(|i| members_start + i)
+        .with_context(|| format!("Unterminated members list in {:?}", manifest))?;
+
+    let list = &content[members_start..members_end];
+    let covered = list
+        .split(',')
+        .map(|entry| entry.trim().trim_matches('"'))
+        .filter(|entry| !entry.is_empty())
+        .any(|pattern| glob_matches(pattern, member));
+    if covered {
+        return Ok(false);
+    }
+
+    let head = content[..members_end].trim_end();
+    let separator = if head.ends_with('[') || head.ends_with(',') { "" } else { "," };
+    let updated = format!(
+        "{}{}\n    \"{}\"\n{}",
+        head,
+        separator,
+        member,
+        &content[members_end..]
+    );
+    std::fs::write(manifest, updated).with_context(|| format!("Failed to update {:?}", manifest))?;
+    Ok(true)
+}
+
+/// Cargo's member globs, as far as this workspace uses them: `*` within a
+/// path segment
+fn glob_matches(pattern: &str, path: &str) -> bool {
+    fn segment_matches(pattern: &str, name: &str) -> bool {
+        match pattern.split_once('*') {
+            None => pattern == name,
+            Some((prefix, rest)) => {
+                name.starts_with(prefix)
+                    && (prefix.len()..=name.len())
+                        .filter(|&i| name.is_char_boundary(i))
+                        .any(|i| segment_matches(rest, &name[i..]))
+            }
+        }
+    }
+
+    let patterns: Vec<&str> = pattern.trim_end_matches('/').split('/').collect();
+    let segments: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+    patterns.len() == segments.len()
+        && patterns
+            .iter()
+            .zip(&segments)
+            .all(|(pattern, segment)| segment_matches(pattern, segment))
+}
+
+/// Exercise and chapter names become directory and crate names
+pub fn validate_name(name: &str) -> Result<()> {
+    let valid = name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if !valid {
+        anyhow::bail!(
+            "Invalid name '{}': use lowercase letters, digits and underscores, starting with a letter",
+            name
+        );
+    }
+    Ok(())
+}
+
+fn cargo_toml(name: &str) -> String {
+    format!(
+        r#"[package]
+name = "{name}"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+"#
+    )
+}
+
+fn readme(metadata: &ExerciseMetadata) -> String {
+    let concepts: String = metadata
+        .concepts
+        .iter()
+        .map(|concept| format!("- **{}**: TODO: how this exercise uses it\n", concept))
+        .collect();
+
+    format!(
+        r#"# Exercise {chapter}.{number}: {title}
+
+{description}
+
+## 🎯 Learning Objectives
+
+By completing this exercise, you will:
+- TODO: List what the learner will be able to do
+
+## 📖 Rust Book References
+
+Before starting, read Chapter {book} of [the Rust Book](https://doc.rust-lang.org/book/).
+
+## ✅ Your Task
+
+TODO: Describe what to implement in `src/main.rs` and the expected output.
+
+## 🧪 Testing Your Solution
+
+Run the tests to check your solution:
+```bash
+cargo test
+```
+
+Run your program to see the output:
+```bash
+cargo run
+```
+
+## 💡 Hints Available
+
+If you get stuck, hints are available at three levels:
+1. **Conceptual**: Understanding what you need to do
+2. **Strategic**: How to approach the problem
+3. **Implementation**: Specific code guidance
+
+## 🌟 Key Concepts
+
+{concepts}"#,
+        chapter = metadata.chapter,
+        number = metadata.exercise_number,
+        title = metadata.title,
+        description = metadata.description,
+        book = metadata.rust_book_refs.primary_chapter,
+        concepts = concepts,
+    )
+}
+
+fn hints(metadata: &ExerciseMetadata) -> String {
+    format!(
+        r#"# Hints for {title}
+
+## Level 1: Conceptual Hint
+
+TODO: Explain the idea behind the exercise without giving away the approach.
+
+## Level 2: Strategic Hint
+
+TODO: Outline how to approach the problem.
+
+## Level 3: Implementation Hint
+
+TODO: Show the code the learner needs.
+"#,
+        title = metadata.title
+    )
+}
+
+fn main_template(metadata: &ExerciseMetadata) -> String {
+    format!(
+        r#"// {title}
+//
+// TODO: Explain what the learner should implement
+
+fn main() {{
+    // TODO: Your code here
+}}
+"#,
+        title = metadata.title.replace('\n', " ")
+    )
+}
+
+fn unit_tests() -> String {
+    r#"use std::process::Command;
+
+#[test]
+fn test_code_compiles_successfully() {
+    let output = Command::new("cargo")
+        .args(["check"])
+        .current_dir(".")
+        .output()
+        .expect("Failed to execute cargo check");
+
+    assert!(
+        output.status.success(),
+        "Code should compile without errors. Compiler output:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn test_program_runs_successfully() {
+    let output = Command::new("cargo")
+        .args(["run"])
+        .current_dir(".")
+        .output()
+        .expect("Failed to execute cargo run");
+
+    assert!(
+        output.status.success(),
+        "Program should run without panicking. Error output:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+// TODO: Replace with tests for the behaviour this exercise teaches
+#[test]
+fn test_program_produces_output() {
+    let output = Command::new("cargo")
+        .args(["run"])
+        .current_dir(".")
+        .output()
+        .expect("Failed to execute cargo run");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        !stdout.trim().is_empty(),
+        "Program should print its result"
+    );
+}
+"#
+    .to_string()
+}
+
+fn reference_solution(metadata: &ExerciseMetadata) -> String {
+    format!(
+        r#"// Reference solution for {title}
+//
+// TODO: Replace with the canonical solution
+
+fn main() {{
+    println!("{{}}", {literal:?});
+}}
+"#,
+        title = metadata.title.replace('\n', " "),
+        literal = metadata.title, // an escaped argument, never read as a format string
+    )
+}
+
+fn reference_explanation(metadata: &ExerciseMetadata) -> String {
+    format!(
+        r#"# Solution Explanation: {title}
+
+## The Solution
+
+TODO: Walk through the reference solution.
+
+## Key Concepts Demonstrated
+
+TODO: Connect the solution to {concepts}.
+"#,
+        title = metadata.title,
+        concepts = metadata.concepts.join(", ")
+    )
+}
@@ -0,0 +1,282 @@
+//! Starter code and per-learner history of exercise code.
+//!
+//! The first time an exercise's code is about to change, its editable files
+//! (see [`crate::ExerciseFiles`]) as committed to git are kept as the
+//! pristine starter. Every save then records a timestamped snapshot of the
+//! learner's files, so work can be compared, restored or reset. Everything
+//! lives in `snapshots/` next to the exercises directory:
+//!
+//! ```text
+//! snapshots/starters/<chapter>/<exercise>.json
+//! snapshots/users/<user>/<chapter>/<exercise>/<snapshot id>.json
+//! ```
+
+use crate::files::ExerciseFiles;
+use crate::progress::validate_user_id;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use similar::TextDiff;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Id under which the starter can be used wherever a snapshot id is accepted
+pub const STARTER_ID: &str = "starter";
+
+/// Snapshots kept per learner and exercise; older ones are pruned
+const MAX_SNAPSHOTS: usize = 50;
+
+/// Why a snapshot was taken
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotTrigger {
+    Starter, // the pristine exercise
+    Save,    // the learner saved a file
+    Restore, // the code a restore or reset replaced
+}
+
+/// The editable files of an exercise at one point in time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub id: String,
+    pub created_at: DateTime<Utc>,
+    pub trigger: SnapshotTrigger,
+    pub files: BTreeMap<String, String>, // path -> content
+}
+
+/// A snapshot without its file contents, for listings
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapshotSummary {
+    pub id: String,
+    pub created_at: DateTime<Utc>,
+    pub trigger: SnapshotTrigger,
+    pub files: usize,
+}
+
+/// How one file differs between two snapshots
+#[derive(Debug, Clone, Serialize)]
+pub struct FileDiff {
+    pub path: String,
+    pub change: FileChange,
+    pub diff: String, // unified diff
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileChange {
+    Added,
+    Removed,
+    Modified,
+}
+
+impl From<&Snapshot> for SnapshotSummary {
+    fn from(snapshot: &Snapshot) -> Self {
+        Self {
+            id: snapshot.id.clone(),
+            created_at: snapshot.created_at,
+            trigger: snapshot.trigger,
+            files: snapshot.files.len(),
+        }
+    }
+}
+
+/// Storage for starters and learner snapshots
+#[derive(Debug, Clone)]
+pub struct SnapshotStore {
+    root: PathBuf,
+}
+
+impl SnapshotStore {
+    /// Snapshots live in `snapshots/` next to the exercises directory
+    pub fn new<P: AsRef<Path>>(exercises_root: P) -> Self {
+        let root = exercises_root
+            .as_ref()
+            .parent()
+            .unwrap_or_else(|| Path::new("."))
+            .join("snapshots");
+        Self { root }
+    }
+
+    /// Keep the files of the shared exercise as its starter, unless a
+    /// starter was kept already. Returns whether one was written.
+    ///
+    /// The starter is what git has committed, so edits made to the shared
+    /// tree do not leak into it; exercises outside git, or not committed
+    /// yet, are kept as they are on disk.
+    pub fn ensure_starter(&self, exercise_dir: &Path, shared: &ExerciseFiles) -> Result<bool> {
+        let path = self.starter_path(exercise_dir);
+        if path.exists() {
+            return Ok(false);
+        }
+
+        let files = match shared.committed_contents()? {
+            Some(files) => files,
+            None => shared.contents()?,
+        };
+        let starter = Snapshot {
+            id: STARTER_ID.to_string(),
+            created_at: Utc::now(),
+            trigger: SnapshotTrigger::Starter,
+            files,
+        };
+        write_snapshot(&path, &starter)?;
+        Ok(true)
+    }
+
+    pub fn starter(&self, exercise_dir: &Path) -> Result<Option<Snapshot>> {
+        read_snapshot(&self.starter_path(exercise_dir))
+    }
+
+    /// Record the learner's current files, unless they match the latest
+    /// snapshot. Returns the new snapshot, if any.
+    pub fn record(
+        &self,
+        user_id: &str,
+        exercise_dir: &Path,
+        files: &ExerciseFiles,
+        trigger: SnapshotTrigger,
+    ) -> Result<Option<SnapshotSummary>> {
+        let contents = files.contents()?;
+        let dir = self.user_dir(user_id, exercise_dir)?;
+        let ids = snapshot_ids(&dir)?;
+
+        if let Some(latest) = ids.last() {
+            if let Some(latest) = read_snapshot(&dir.join(format!("{}.json", latest)))? {
+                if latest.files == contents {
+                    return Ok(None);
+                }
+            }
+        }
+
+        let created_at = Utc::now();
+        let mut id = created_at.format("%Y%m%dT%H%M%S%6fZ").to_string();
+        while ids.contains(&id) {
+            id.push('0');
+        }
+        let snapshot = Snapshot {
+            id,
+            created_at,
+            trigger,
+            files: contents,
+        };
+        write_snapshot(&dir.join(format!("{}.json", snapshot.id)), &snapshot)?;
+
+        // Prune the oldest beyond the limit
+        let excess = (ids.len() + 1).saturating_sub(MAX_SNAPSHOTS);
+        for old in ids.iter().take(excess) {
+            let _ = std::fs::remove_file(dir.join(format!("{}.json", old)));
+        }
+
+        Ok(Some(SnapshotSummary::from(&snapshot)))
+    }
+
+    /// The learner's snapshots, newest first
+    pub fn list(&self, user_id: &str, exercise_dir: &Path) -> Result<Vec<SnapshotSummary>> {
+        let dir = self.user_dir(user_id, exercise_dir)?;
+        let mut summaries = Vec::new();
+        for id in snapshot_ids(&dir)?.iter().rev() {
+            if let Some(snapshot) = read_snapshot(&dir.join(format!("{}.json", id)))? {
+                summaries.push(SnapshotSummary::from(&snapshot));
+            }
+        }
+        Ok(summaries)
+    }
+
+    /// A snapshot by id; [`STARTER_ID`] gives the starter
+    pub fn get(&self, user_id: &str, exercise_dir: &Path, snapshot_id: &str) -> Result<Option<Snapshot>> {
+        if snapshot_id == STARTER_ID {
+            return self.starter(exercise_dir);
+        }
+        if !is_snapshot_id(snapshot_id) {
+            return Ok(None);
+        }
+        let dir = self.user_dir(user_id, exercise_dir)?;
+        read_snapshot(&dir.join(format!("{}.json", snapshot_id)))
+    }
+
+    fn starter_path(&self, exercise_dir: &Path) -> PathBuf {
+        let mut path = self.root.join("starters").join(exercise_dir);
+        path.as_mut_os_string().push(".json");
+        path
+    }
+
+    fn user_dir(&self, user_id: &str, exercise_dir: &Path) -> Result<PathBuf> {
+        validate_user_id(user_id)?;
+        Ok(self.root.join("users").join(user_id).join(exercise_dir))
+    }
+}
+
+/// Per-file differences going from `from` to `to`; unchanged files are left out
+pub fn diff(from: &BTreeMap<String, String>, to: &BTreeMap<String, String>) -> Vec<FileDiff> {
+    let mut paths: Vec<&String> = from.keys().chain(to.keys()).collect();
+    paths.sort();
+    paths.dedup();
+
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let (old, new) = (from.get(path), to.get(path));
+            let change = match (old, new) {
+                (None, Some(_)) => FileChange::Added,
+                (Some(_), None) => FileChange::Removed,
+                (Some(old), Some(new)) if old != new => FileChange::Modified,
+                _ => return None,
+            };
+            let old = old.map(String::as_str).unwrap_or("");
+            let new = new.map(String::as_str).unwrap_or("");
+            let diff = TextDiff::from_lines(old, new)
+                .unified_diff()
+                .header(&format!("a/{}", path), &format!("b/{}", path))
+                .to_string();
+            Some(FileDiff {
+                path: path.clone(),
+                change,
+                diff,
+            })
+        })
+        .collect()
+}
+
+/// Ids are timestamps, so sorting them puts snapshots in order
+fn snapshot_ids(dir: &Path) -> Result<Vec<String>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut ids: Vec<String> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            name.strip_suffix(".json").map(str::to_string)
+        })
+        .filter(|id| is_snapshot_id(id))
+        .collect();
+    ids.sort();
+    Ok(ids)
+}
+
+fn is_snapshot_id(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+fn read_snapshot(path: &Path) -> Result<Option<Snapshot>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(path)?;
+    let snapshot = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse snapshot {:?}", path))?;
+    Ok(Some(snapshot))
+}
+
+fn write_snapshot(path: &Path, snapshot: &Snapshot) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    // Written aside and renamed into place, so a crash never leaves a
+    // partial file that later reads would take for the real one
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    std::fs::write(&temp, serde_json::to_string_pretty(snapshot)?)?;
+    std::fs::rename(&temp, path)?;
+    Ok(())
+}
@@ -1,6 +1,10 @@
+use crate::diagnostics::{self, Diagnostic};
 use crate::exercise::{Exercise, TestType};
-use anyhow::{Context, Result};
+use crate::libtest::{self, TestCase, TestStatus};
+use crate::sandbox::{self, Sandbox, SandboxLimits, SandboxOutput};
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 use std::process::Command;
 use std::time::{Duration, Instant};
 
@@ -21,9 +25,10 @@ pub struct TestResult {
 pub struct IndividualTestResult {
     pub name: String,
     pub test_type: TestType,
+    pub status: TestStatus,
     pub passed: bool,
-    pub output: String,
-    pub error: Option<String>,
+    pub output: String,        // stdout captured while the test ran
+    pub error: Option<String>, // panic message for failed tests
     pub execution_time: Duration,
 }
 
@@ -33,6 +38,7 @@ pub struct CompilationResult {
     pub success: bool,
     pub warnings: Vec<String>,
     pub errors: Vec<String>,
+    pub diagnostics: Vec<Diagnostic>,
     pub clippy_issues: Vec<ClippyIssue>,
 }
 
@@ -40,11 +46,13 @@ pub struct CompilationResult {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClippyIssue {
     pub level: String, // "error", "warning", "note"
+    pub lint: String,  // e.g. "clippy::needless_return"
     pub message: String,
     pub file: String,
     pub line: u32,
     pub column: u32,
     pub suggestion: Option<String>,
+    pub diagnostic: Diagnostic,
 }
 
 /// Code quality assessment
@@ -76,17 +84,24 @@ pub struct BenchmarkResult {
 }
 
 /// Test runner handles exercise validation
+///
+/// Dependencies are fetched under [`SandboxLimits::for_fetch`], then cargo
+/// builds offline under [`SandboxLimits::for_build`] with the runner's
+/// timeout, while test harnesses run under the exercise's own `TestConfig`
+/// limits.
 pub struct TestRunner {
     timeout: Duration,
     cargo_path: String,
+    target_dir: Option<PathBuf>, // CARGO_TARGET_DIR override
 }
 
 impl TestRunner {
     /// Create a new test runner
     pub fn new() -> Self {
         Self {
-            timeout: Duration::from_secs(60), // 1 minute default timeout
-            cargo_path: "cargo".to_string(),  // Assume cargo is in PATH
+            timeout: sandbox::DEFAULT_BUILD_TIMEOUT,
+            cargo_path: "cargo".to_string(), // Assume cargo is in PATH
+            target_dir: None,
         }
     }
 
@@ -94,15 +109,17 @@ impl TestRunner {
     pub fn run_tests(&self, exercise: &Exercise) -> Result<TestResult> {
         let start_time = Instant::now();
 
-        // Change to exercise directory
-        let original_dir = std::env::current_dir()?;
-        std::env::set_current_dir(&exercise.path)?;
-
-        // Ensure we restore directory even on error
-        let _guard = DirectoryGuard::new(original_dir);
-
         // Step 1: Compilation check
-        let compilation_result = self.check_compilation(exercise)?;
+        let compilation_result = match self.prepare_build(exercise)? {
+            Some(error) => CompilationResult {
+                success: false,
+                warnings: Vec::new(),
+                errors: vec![error],
+                diagnostics: Vec::new(),
+                clippy_issues: Vec::new(),
+            },
+            None => self.check_compilation(exercise)?,
+        };
         
         // Step 2: Run unit tests
         let test_results = if compilation_result.success {
@@ -115,7 +132,7 @@ impl TestRunner {
         let quality_check = self.run_quality_checks(exercise)?;
 
         // Step 4: Performance metrics (for performance exercises)
-        let performance_metrics = if exercise.metadata.exercise_type_enum() == crate::ExerciseType::Performance {
+        let performance_metrics = if exercise.metadata.exercise_type == crate::ExerciseType::Performance {
             Some(self.run_benchmarks(exercise)?)
         } else {
             None
@@ -136,126 +153,279 @@ impl TestRunner {
         })
     }
 
+    /// Offline cargo command rooted at the exercise directory
+    fn cargo(&self, exercise: &Exercise, args: &[&str]) -> Command {
+        let mut command = Command::new(&self.cargo_path);
+        command.args(args).current_dir(&exercise.path);
+        sandbox::offline(&mut command);
+        if let Some(target_dir) = &self.target_dir {
+            command.env("CARGO_TARGET_DIR", target_dir);
+        }
+        command
+    }
+
+    /// Check the exercise's dependencies against its `TestConfig` and fetch
+    /// them, so the build itself can run offline. Returns why the exercise
+    /// can't be built, if it can't.
+    fn prepare_build(&self, exercise: &Exercise) -> Result<Option<String>> {
+        // Cargo reports a broken manifest itself
+        let crates = sandbox::declared_dependencies(&exercise.path).unwrap_or_default();
+        if let Some(violation) = sandbox::dependency_violation(&exercise.metadata.testing, &crates) {
+            return Ok(Some(violation));
+        }
+        if crates.is_empty() {
+            return Ok(None);
+        }
+
+        let mut fetch = Command::new(&self.cargo_path);
+        fetch.arg("fetch").current_dir(&exercise.path);
+        let fetch_sandbox = Sandbox::new(SandboxLimits::for_fetch(self.timeout));
+        let output = fetch_sandbox.run(fetch)?;
+        if output.success() {
+            return Ok(None);
+        }
+        Ok(Some(
+            output
+                .limit_message(fetch_sandbox.limits())
+                .unwrap_or_else(|| String::from_utf8_lossy(&output.stderr).into_owned()),
+        ))
+    }
+
+    /// Run a cargo command under the build limits
+    fn run_build(&self, command: Command) -> Result<SandboxOutput> {
+        Sandbox::new(SandboxLimits::for_build(self.timeout)).run(command)
+    }
+
     /// Check if the code compiles
-    fn check_compilation(&self, _exercise: &Exercise) -> Result<CompilationResult> {
+    fn check_compilation(&self, exercise: &Exercise) -> Result<CompilationResult> {
         // First check with cargo check (faster)
-        let check_output = Command::new(&self.cargo_path)
-            .args(["check", "--message-format=json"])
-            .output()
-            .context("Failed to run cargo check")?;
-
-        let mut errors = Vec::new();
-        let mut warnings = Vec::new();
-
-        // Parse cargo output (simplified - real implementation would parse JSON)
-        if !check_output.status.success() {
-            let stderr = String::from_utf8_lossy(&check_output.stderr);
-            for line in stderr.lines() {
-                if line.contains("error:") {
-                    errors.push(line.to_string());
-                } else if line.contains("warning:") {
-                    warnings.push(line.to_string());
-                }
-            }
+        let build_sandbox = Sandbox::new(SandboxLimits::for_build(self.timeout));
+        let check_output = build_sandbox.run(self.cargo(exercise, &["check", "--all-targets", "--message-format=json"]))?;
+
+        let diagnostics = diagnostics::parse_cargo_messages(&String::from_utf8_lossy(&check_output.stdout));
+
+        let mut errors: Vec<String> = diagnostics.iter()
+            .filter(|d| d.is_error())
+            .map(Diagnostic::display_text)
+            .collect();
+        if let Some(message) = check_output.limit_message(build_sandbox.limits()) {
+            errors.push(message);
         }
+        let warnings: Vec<String> = diagnostics.iter()
+            .filter(|d| !d.is_error())
+            .map(Diagnostic::display_text)
+            .collect();
 
         // Run clippy for additional checks
-        let clippy_issues = self.run_clippy()?;
+        let clippy_issues = self.run_clippy(exercise)?;
 
         Ok(CompilationResult {
-            success: check_output.status.success() && errors.is_empty(),
+            success: check_output.success() && errors.is_empty(),
             warnings,
             errors,
+            diagnostics,
             clippy_issues,
         })
     }
 
     /// Run clippy for code quality
-    fn run_clippy(&self) -> Result<Vec<ClippyIssue>> {
-        let clippy_output = Command::new(&self.cargo_path)
-            .args(["clippy", "--", "-W", "clippy::all"])
-            .output()
-            .context("Failed to run clippy")?;
+    fn run_clippy(&self, exercise: &Exercise) -> Result<Vec<ClippyIssue>> {
+        let clippy_output = self.run_build(self.cargo(exercise, &["clippy", "--message-format=json", "--", "-W", "clippy::all"]))?;
 
-        let mut issues = Vec::new();
-        
-        // Parse clippy output (simplified)
-        let output = String::from_utf8_lossy(&clippy_output.stdout);
-        for line in output.lines() {
-            if line.contains("warning:") || line.contains("error:") {
-                // Simple parsing - real implementation would be more sophisticated
-                issues.push(ClippyIssue {
-                    level: if line.contains("error:") { "error" } else { "warning" }.to_string(),
-                    message: line.to_string(),
-                    file: "src/main.rs".to_string(), // Simplified
-                    line: 1,
-                    column: 1,
-                    suggestion: None,
-                });
-            }
-        }
+        // rustc's own warnings are already reported by `cargo check`
+        let issues = diagnostics::parse_cargo_messages(&String::from_utf8_lossy(&clippy_output.stdout))
+            .into_iter()
+            .filter(Diagnostic::is_clippy_lint)
+            .map(Self::to_clippy_issue)
+            .collect();
 
         Ok(issues)
     }
 
+    /// Convert a clippy diagnostic into a located issue
+    fn to_clippy_issue(diagnostic: Diagnostic) -> ClippyIssue {
+        let span = diagnostic.primary_span();
+        let suggestion = diagnostic.machine_applicable_suggestions()
+            .into_iter()
+            .next()
+            .map(|s| s.replacement);
+
+        ClippyIssue {
+            level: diagnostic.level.as_str().to_string(),
+            lint: diagnostic.code().unwrap_or_default().to_string(),
+            message: diagnostic.message.clone(),
+            file: span.map(|s| s.file_name.clone()).unwrap_or_default(),
+            line: span.map_or(0, |s| s.line_start),
+            column: span.map_or(0, |s| s.column_start),
+            suggestion,
+            diagnostic,
+        }
+    }
+
+    /// Build and run only the exercise's tests, without the compile and
+    /// quality passes of [`TestRunner::run_tests`]. When the tests don't
+    /// build, the compiler errors are returned and no tests run.
+    pub fn run_test_suite(&self, exercise: &Exercise) -> Result<(Vec<Diagnostic>, Vec<IndividualTestResult>)> {
+        if let Some(error) = self.prepare_build(exercise)? {
+            return Ok((Vec::new(), vec![Self::setup_failure(error)]));
+        }
+
+        let build = self.cargo(exercise, &["test", "--no-run", "--message-format=json"]);
+        let build_sandbox = Sandbox::new(SandboxLimits::for_build(self.timeout));
+        let build_output = build_sandbox.run(build)?;
+
+        if !build_output.success() {
+            let errors: Vec<Diagnostic> = diagnostics::parse_cargo_messages(&String::from_utf8_lossy(&build_output.stdout))
+                .into_iter()
+                .filter(Diagnostic::is_error)
+                .collect();
+            let limit_message = build_output.limit_message(build_sandbox.limits());
+            let results = if errors.is_empty() || limit_message.is_some() {
+                vec![Self::harness_failure(&build_output, limit_message)]
+            } else {
+                Vec::new()
+            };
+            return Ok((errors, results));
+        }
+
+        Ok((Vec::new(), self.run_unit_tests(exercise)?))
+    }
+
     /// Run unit tests
-    fn run_unit_tests(&self, _exercise: &Exercise) -> Result<Vec<IndividualTestResult>> {
-        let mut results = Vec::new();
+    fn run_unit_tests(&self, exercise: &Exercise) -> Result<Vec<IndividualTestResult>> {
+        // Build the test harnesses first so compile time doesn't count
+        // against the exercise's own limits
+        let build = self.cargo(exercise, &["test", "--no-run", "--message-format=json"]);
+        let build_output = self.run_build(build)?;
+        if !build_output.success() {
+            return Ok(vec![Self::harness_failure(&build_output, None)]);
+        }
+        let artifacts = diagnostics::parse_test_artifacts(&String::from_utf8_lossy(&build_output.stdout));
 
-        // Run cargo test with JSON output
-        let test_output = Command::new(&self.cargo_path)
-            .args(["test", "--", "--nocapture", "--test-threads=1"])
-            .output()
-            .context("Failed to run cargo test")?;
-
-        let success = test_output.status.success();
-        let output = String::from_utf8_lossy(&test_output.stdout);
-        let stderr = String::from_utf8_lossy(&test_output.stderr);
-
-        // Parse test results (simplified - real implementation would parse structured output)
-        if success {
-            // Count passed tests
-            let passed_count = output.matches("test result: ok").count();
-            for i in 0..passed_count.max(1) {
-                results.push(IndividualTestResult {
-                    name: format!("test_{}", i + 1),
-                    test_type: TestType::Unit,
-                    passed: true,
-                    output: output.clone().into(),
-                    error: None,
-                    execution_time: Duration::from_millis(100), // Placeholder
-                });
+        // Harnesses that shell out to `cargo check` must not have to compile
+        let check_output = self.run_build(self.cargo(exercise, &["check", "--all-targets"]))?;
+        if !check_output.success() {
+            return Ok(vec![Self::harness_failure(&check_output, None)]);
+        }
+
+        // Each harness runs directly, under the exercise's memory limit too
+        let test_sandbox = Sandbox::new(SandboxLimits::for_tests(&exercise.metadata.testing));
+        let mut results = Vec::new();
+        for artifact in &artifacts {
+            // libtest only accepts `--format json` on nightly, but it checks
+            // RUSTC_BOOTSTRAP at runtime, so stable toolchains emit it too.
+            // It is only set here, after compiling, so learner code can't use
+            // unstable features; cargo commands the tests run inherit it, but
+            // find everything already built.
+            let mut test = self.harness(exercise, artifact);
+            test.args(["-Z", "unstable-options", "--format", "json", "--report-time", "--show-output", "--test-threads=1"]);
+            test.env("RUSTC_BOOTSTRAP", "1");
+            let mut test_output = test_sandbox.run(test)?;
+
+            // Fall back to the stable human-readable format if JSON was rejected
+            let cases = match libtest::parse_json_output(
+                &String::from_utf8_lossy(&test_output.stdout),
+                &String::from_utf8_lossy(&test_output.stderr),
+            ) {
+                Some(cases) => cases,
+                None => {
+                    let mut plain = self.harness(exercise, artifact);
+                    plain.args(["--show-output", "--test-threads=1"]);
+                    test_output = test_sandbox.run(plain)?;
+                    libtest::parse_human_output(
+                        &String::from_utf8_lossy(&test_output.stdout),
+                        &String::from_utf8_lossy(&test_output.stderr),
+                    )
+                }
+            };
+
+            let before = results.len();
+            results.extend(cases.into_iter().map(|case| {
+                Self::to_individual_result(TestCase {
+                    suite: artifact.suite.clone(),
+                    ..case
+                })
+            }));
+
+            // Tests cut off by a limit never report their own failure
+            let limit_message = test_output.limit_message(test_sandbox.limits());
+            if limit_message.is_some() || (results.len() == before && !test_output.success()) {
+                results.push(Self::harness_failure(&test_output, limit_message));
             }
-        } else {
-            // Test failed
-            results.push(IndividualTestResult {
-                name: "unit_tests".to_string(),
-                test_type: TestType::Unit,
-                passed: false,
-                output: output.into(),
-                error: Some(stderr.into()),
-                execution_time: Duration::from_millis(500), // Placeholder
-            });
         }
 
         Ok(results)
     }
 
+    /// Command running one test harness from the exercise directory
+    fn harness(&self, exercise: &Exercise, artifact: &diagnostics::TestArtifact) -> Command {
+        let mut command = artifact.command(&exercise.path);
+        // Cargo commands run by the tests must find the harness build
+        if let Some(target_dir) = &self.target_dir {
+            command.env("CARGO_TARGET_DIR", target_dir);
+        }
+        command
+    }
+
+    /// Result for an exercise that could not be built at all
+    fn setup_failure(error: String) -> IndividualTestResult {
+        IndividualTestResult {
+            name: "unit_tests".to_string(),
+            test_type: TestType::Unit,
+            status: TestStatus::Failed,
+            passed: false,
+            output: String::new(),
+            error: Some(error),
+            execution_time: Duration::ZERO,
+        }
+    }
+
+    /// Result for a test run that failed outside of any individual test
+    fn harness_failure(output: &SandboxOutput, limit_message: Option<String>) -> IndividualTestResult {
+        let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+        IndividualTestResult {
+            name: "unit_tests".to_string(),
+            test_type: TestType::Unit,
+            status: TestStatus::Failed,
+            passed: false,
+            output: String::from_utf8_lossy(&output.stdout).into_owned(),
+            error: Some(limit_message.unwrap_or(stderr)),
+            execution_time: output.elapsed,
+        }
+    }
+
+    /// Convert a parsed libtest case into a test result
+    fn to_individual_result(case: TestCase) -> IndividualTestResult {
+        let test_type = if case.suite.contains("integration") {
+            TestType::Integration
+        } else if case.suite.contains("quality") {
+            TestType::Quality
+        } else if case.suite.contains("performance") || case.suite.contains("bench") {
+            TestType::Performance
+        } else {
+            TestType::Unit
+        };
+
+        IndividualTestResult {
+            name: case.name,
+            test_type,
+            status: case.status,
+            passed: case.status != TestStatus::Failed,
+            output: case.stdout,
+            error: case.panic_message,
+            execution_time: case.duration,
+        }
+    }
+
     /// Run quality checks
-    fn run_quality_checks(&self, _exercise: &Exercise) -> Result<QualityResult> {
+    fn run_quality_checks(&self, exercise: &Exercise) -> Result<QualityResult> {
         // Check formatting
-        let fmt_output = Command::new(&self.cargo_path)
-            .args(["fmt", "--check"])
-            .output()
-            .context("Failed to run cargo fmt")?;
+        let fmt_output = self.run_build(self.cargo(exercise, &["fmt", "--check"]))?;
 
-        let format_score = if fmt_output.status.success() { 1.0 } else { 0.7 };
+        let format_score = if fmt_output.success() { 1.0 } else { 0.7 };
 
         // Clippy score based on issues
-        let clippy_output = Command::new(&self.cargo_path)
-            .args(["clippy", "--", "-W", "clippy::all"])
-            .output()
-            .context("Failed to run clippy")?;
+        let clippy_output = self.run_build(self.cargo(exercise, &["clippy", "--", "-W", "clippy::all"]))?;
 
         let clippy_issues = String::from_utf8_lossy(&clippy_output.stderr)
             .matches("warning:").count();
@@ -303,10 +473,7 @@ impl TestRunner {
         }
 
         // Run cargo bench (simplified)
-        let _bench_output = Command::new(&self.cargo_path)
-            .args(["bench"])
-            .output()
-            .context("Failed to run cargo bench")?;
+        let _bench_output = self.run_build(self.cargo(exercise, &["bench"]))?;
 
         // Parse benchmark results (placeholder implementation)
         let benchmark_results = vec![
@@ -326,10 +493,17 @@ impl TestRunner {
         })
     }
 
-    /// Set custom timeout for test execution
+    /// Set custom timeout for cargo builds and checks.
+    ///
+    /// Test harnesses use the exercise's `timeout_seconds` instead.
     pub fn set_timeout(&mut self, timeout: Duration) {
         self.timeout = timeout;
     }
+
+    /// Build into `target_dir` instead of the exercise's own target directory
+    pub fn set_target_dir<P: Into<PathBuf>>(&mut self, target_dir: P) {
+        self.target_dir = Some(target_dir.into());
+    }
 }
 
 impl Default for TestRunner {
@@ -338,23 +512,6 @@ impl Default for TestRunner {
     }
 }
 
-/// RAII guard to restore working directory
-struct DirectoryGuard {
-    original_dir: std::path::PathBuf,
-}
-
-impl DirectoryGuard {
-    fn new(original_dir: std::path::PathBuf) -> Self {
-        Self { original_dir }
-    }
-}
-
-impl Drop for DirectoryGuard {
-    fn drop(&mut self) {
-        let _ = std::env::set_current_dir(&self.original_dir);
-    }
-}
-
 /// Helper functions for test result analysis
 impl TestResult {
     /// Check if all tests passed
@@ -1,5 +1,7 @@
-use crate::exercise::{Exercise, ExerciseType};
+use crate::book::{BookPage, TableOfContents};
+use crate::exercise::{Exercise, ExerciseDifficulty, ExerciseType};
 use crate::metadata::ExerciseMetadata;
+use crate::verification::SolutionVerifier;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
@@ -29,7 +31,7 @@ pub struct ValidationIssue {
 }
 
 /// Severity levels for validation issues
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum IssueSeverity {
     Error,   // Must fix
     Warning, // Should fix
@@ -81,19 +83,52 @@ pub struct PedagogicalValidation {
 
 /// Exercise validator
 pub struct ExerciseValidator {
-    rust_book_chapters: HashSet<String>,
+    book: TableOfContents,
     concept_taxonomy: HashSet<String>,
+    solution_verifier: Option<SolutionVerifier>, // run the tests against solutions and template
 }
 
 impl ExerciseValidator {
     /// Create a new validator
     pub fn new() -> Self {
         Self {
-            rust_book_chapters: Self::load_rust_book_chapters(),
+            book: TableOfContents::vendored().clone(),
             concept_taxonomy: Self::load_concept_taxonomy(),
+            solution_verifier: None,
         }
     }
 
+    /// Check book references against another edition of the book, e.g. a
+    /// local checkout's `SUMMARY.md`, instead of the vendored one
+    pub fn with_book_contents(mut self, book: TableOfContents) -> Self {
+        self.book = book;
+        self
+    }
+
+    /// Also build each exercise and run its tests against every reference
+    /// solution and the starter template. This runs cargo, so it is slow.
+    pub fn with_solution_verification(mut self) -> Self {
+        self.solution_verifier = Some(SolutionVerifier::new());
+        self
+    }
+
+    /// Standard concept names, sorted
+    pub fn concept_taxonomy(&self) -> Vec<&str> {
+        let mut concepts: Vec<&str> = self.concept_taxonomy.iter().map(String::as_str).collect();
+        concepts.sort_unstable();
+        concepts
+    }
+
+    /// Whether a concept name is in the standard taxonomy
+    pub fn is_standard_concept(&self, concept: &str) -> bool {
+        self.concept_taxonomy.contains(concept)
+    }
+
+    /// Whether a Rust Book chapter reference (e.g. "3.2") is known
+    pub fn is_known_book_chapter(&self, chapter: &str) -> bool {
+        self.book.get(chapter).is_some()
+    }
+
     /// Validate an exercise comprehensively
     pub fn validate(&self, exercise: &Exercise) -> Result<ValidationResult> {
         let mut issues = Vec::new();
@@ -114,6 +149,13 @@ impl ExerciseValidator {
         // Generate suggestions
         self.generate_suggestions(&issues, &mut suggestions);
 
+        // Metadata checks report against metadata.json; name the file this exercise uses
+        if let Some(name) = exercise.metadata_file.file_name().map(|name| name.to_string_lossy()) {
+            for issue in issues.iter_mut().filter(|i| i.file.as_deref() == Some("metadata.json")) {
+                issue.file = Some(name.to_string());
+            }
+        }
+
         let is_valid = score >= 0.7 && !issues.iter().any(|i| matches!(i.severity, IssueSeverity::Error));
 
         Ok(ValidationResult {
@@ -205,11 +247,10 @@ impl ExerciseValidator {
     /// Check if difficulty level is appropriate
     fn check_difficulty_appropriateness(&self, metadata: &ExerciseMetadata, issues: &mut Vec<ValidationIssue>) -> bool {
         // Check if time estimate matches difficulty
-        let expected_time = match metadata.difficulty.as_str() {
-            "beginner" => (5, 25),
-            "intermediate" => (15, 45),
-            "advanced" => (30, 90),
-            _ => return false,
+        let expected_time = match metadata.difficulty {
+            ExerciseDifficulty::Beginner => (5, 25),
+            ExerciseDifficulty::Intermediate => (15, 45),
+            ExerciseDifficulty::Advanced => (30, 90),
         };
 
         if metadata.estimated_time_minutes < expected_time.0 || 
@@ -307,19 +348,82 @@ impl ExerciseValidator {
             return false;
         }
 
-        // Validate chapter format
-        if !self.rust_book_chapters.contains(&metadata.rust_book_refs.primary_chapter) {
-            issues.push(ValidationIssue {
-                severity: IssueSeverity::Warning,
-                category: IssueCategory::Metadata,
-                message: format!("Chapter '{}' may not exist in the Rust Book", metadata.rust_book_refs.primary_chapter),
-                file: Some("metadata.json".to_string()),
-                line: None,
-                suggestion: Some("Verify the chapter reference against the official Rust Book".to_string()),
-            });
+        let refs = &metadata.rust_book_refs;
+        let mut valid = true;
+
+        for chapter in std::iter::once(&refs.primary_chapter).chain(&refs.supporting_chapters) {
+            if self.book.get(chapter).is_none() {
+                issues.push(ValidationIssue {
+                    severity: IssueSeverity::Error,
+                    category: IssueCategory::Metadata,
+                    message: format!("Rust Book section '{}' does not exist", chapter),
+                    file: Some("metadata.json".to_string()),
+                    line: None,
+                    suggestion: Some("Use a section number from the book's table of contents, e.g. \"3.1\"".to_string()),
+                });
+                valid = false;
+            }
         }
 
-        true
+        for section in &refs.specific_sections {
+            let Some(page) = self.book.get(&section.chapter) else {
+                // Stale reference: the URL or title usually still identifies the page
+                let candidate = self.book.by_url(&section.url).or_else(|| self.book.by_title(&section.title));
+                issues.push(ValidationIssue {
+                    severity: IssueSeverity::Error,
+                    category: IssueCategory::Metadata,
+                    message: format!("Book section '{}' ({}) does not exist", section.chapter, section.title),
+                    file: Some("metadata.json".to_string()),
+                    line: None,
+                    suggestion: Some(match candidate {
+                        Some(page) => suggest_page(page),
+                        None => "Use a section number from the book's table of contents".to_string(),
+                    }),
+                });
+                valid = false;
+                continue;
+            };
+
+            let url = section.url.split('#').next().unwrap_or_default();
+            if url != page.online_url() {
+                let message = match self.book.by_url(&section.url) {
+                    Some(other) => format!(
+                        "Book section {} links to section {} ({}) instead",
+                        page.id, other.id, other.title
+                    ),
+                    None => format!("Book section {} links to '{}', which is not its page", page.id, section.url),
+                };
+                issues.push(ValidationIssue {
+                    severity: IssueSeverity::Error,
+                    category: IssueCategory::Metadata,
+                    message,
+                    file: Some("metadata.json".to_string()),
+                    line: None,
+                    suggestion: Some(suggest_page(page)),
+                });
+                valid = false;
+            }
+
+            if !same_title(&section.title, &page.title) {
+                let suggestion = match self.book.by_title(&section.title) {
+                    Some(other) => suggest_page(other),
+                    None => format!("Use the book's title \"{}\"", page.title),
+                };
+                issues.push(ValidationIssue {
+                    severity: IssueSeverity::Warning,
+                    category: IssueCategory::Metadata,
+                    message: format!(
+                        "Book section {} is titled \"{}\" in the book, not \"{}\"",
+                        page.id, page.title, section.title
+                    ),
+                    file: Some("metadata.json".to_string()),
+                    line: None,
+                    suggestion: Some(suggestion),
+                });
+            }
+        }
+
+        valid
     }
 
     /// Validate exercise content
@@ -331,13 +435,24 @@ impl ExerciseValidator {
         if !code_compiles { valid = false; }
 
         // Check test comprehensiveness
-        let tests_comprehensive = self.check_test_coverage(exercise, issues);
+        let mut tests_comprehensive = self.check_test_coverage(exercise, issues);
         
         // Check hint quality
         let hints_helpful = self.check_hint_quality(exercise, issues);
         
         // Check solution correctness
-        let solutions_correct = self.check_solutions(exercise, issues);
+        let mut solutions_correct = self.check_solutions(exercise, issues);
+
+        // Run the tests against the solutions and the starter template
+        if let Some(verifier) = &self.solution_verifier {
+            let report = verifier.verify(exercise)?;
+            if !report.failing_solutions.is_empty() {
+                solutions_correct = false;
+                valid = false;
+            }
+            tests_comprehensive &= !report.template_passes;
+            issues.extend(report.issues);
+        }
         
         // Check documentation clarity
         let documentation_clear = self.check_documentation(exercise, issues);
@@ -369,7 +484,7 @@ impl ExerciseValidator {
         }
 
         // Check for missing main function in executable exercises
-        if exercise.metadata.exercise_type_enum() != ExerciseType::Performance {
+        if exercise.metadata.exercise_type != ExerciseType::Performance {
             let has_main = exercise.source_files.iter()
                 .any(|f| f.content.contains("fn main()"));
             
@@ -434,7 +549,7 @@ impl ExerciseValidator {
                 severity: IssueSeverity::Warning,
                 category: IssueCategory::Content,
                 message: "No hints provided".to_string(),
-                file: Some("src/hints.md".to_string()),
+                file: Some("hints.md".to_string()),
                 line: None,
                 suggestion: Some("Add progressive hints to help struggling students".to_string()),
             });
@@ -447,12 +562,28 @@ impl ExerciseValidator {
                 severity: IssueSeverity::Warning,
                 category: IssueCategory::Content,
                 message: "Only one hint level provided".to_string(),
-                file: Some("src/hints.md".to_string()),
+                file: Some("hints.md".to_string()),
                 line: None,
                 suggestion: Some("Provide 3 levels of hints: conceptual, strategic, implementation".to_string()),
             });
         }
 
+        // Levels the metadata offers but hints.md does not have are never served
+        let written = exercise.hints.iter().map(|hint| hint.level.number()).max().unwrap_or(0);
+        if exercise.metadata.hints.available > written {
+            issues.push(ValidationIssue {
+                severity: IssueSeverity::Warning,
+                category: IssueCategory::Content,
+                message: format!(
+                    "Metadata makes {} hint levels available but hints.md only goes up to level {}",
+                    exercise.metadata.hints.available, written
+                ),
+                file: Some("hints.md".to_string()),
+                line: None,
+                suggestion: Some("Add the missing `## Level N` sections or lower hints.available".to_string()),
+            });
+        }
+
         true
     }
 
@@ -545,13 +676,13 @@ impl ExerciseValidator {
 
     /// Check difficulty progression
     fn check_difficulty_progression(&self, _exercise: &Exercise, _issues: &mut Vec<ValidationIssue>) -> bool {
-        // TODO: Implement cross-exercise difficulty analysis
+        // Needs the other exercises in the chapter; see CurriculumValidator
         true
     }
 
     /// Check concept introduction
     fn check_concept_introduction(&self, _exercise: &Exercise, _issues: &mut Vec<ValidationIssue>) -> bool {
-        // TODO: Check if prerequisites are properly handled
+        // Needs the prerequisite graph; see CurriculumValidator
         true
     }
 
@@ -563,7 +694,7 @@ impl ExerciseValidator {
 
     /// Check beginner-friendliness
     fn check_beginner_friendliness(&self, exercise: &Exercise, issues: &mut Vec<ValidationIssue>) -> bool {
-        if exercise.metadata.difficulty == "beginner" {
+        if exercise.metadata.difficulty == ExerciseDifficulty::Beginner {
             // Check for complex language in description
             let complex_words = ["polymorphism", "metaprogramming", "monomorphization"];
             for word in complex_words {
@@ -606,7 +737,7 @@ impl ExerciseValidator {
             .filter(|i| matches!(i.severity, IssueSeverity::Warning))
             .count() as f64 * 0.05;
 
-        (base_score - error_penalty - warning_penalty).max(0.0).min(1.0)
+        (base_score - error_penalty - warning_penalty).clamp(0.0, 1.0)
     }
 
     /// Generate actionable suggestions
@@ -634,27 +765,6 @@ impl ExerciseValidator {
         }
     }
 
-    /// Load known Rust Book chapters
-    fn load_rust_book_chapters() -> HashSet<String> {
-        // In a real implementation, this would load from a configuration file
-        let chapters = vec![
-            "1", "2", "3.1", "3.2", "3.3", "3.4", "3.5",
-            "4.1", "4.2", "4.3", "5.1", "5.2", "5.3",
-            "6.1", "6.2", "6.3", "7.1", "7.2", "7.3", "7.4", "7.5",
-            "8.1", "8.2", "8.3", "9.1", "9.2", "9.3",
-            "10.1", "10.2", "10.3", "11.1", "11.2", "11.3",
-            "12", "13.1", "13.2", "13.3", "13.4",
-            "14.1", "14.2", "14.3", "14.4", "14.5",
-            "15.1", "15.2", "15.3", "15.4", "15.5", "15.6",
-            "16.1", "16.2", "16.3", "16.4",
-            "17.1", "17.2", "17.3", "18.1", "18.2", "18.3",
-            "19.1", "19.2", "19.3", "19.4", "19.5", "19.6",
-            "20.1", "20.2", "20.3", "20.4", "20.5", "20.6"
-        ];
-
-        chapters.into_iter().map(String::from).collect()
-    }
-
     /// Load concept taxonomy
     fn load_concept_taxonomy() -> HashSet<String> {
         // Standard Rust concepts
@@ -675,4 +785,26 @@ impl Default for ExerciseValidator {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+
+/// How to point a stale reference at the right page
+fn suggest_page(page: &BookPage) -> String {
+    format!(
+        "Did you mean section {} \"{}\" ({})?",
+        page.id,
+        page.title,
+        page.online_url()
+    )
+}
+
+/// Titles match ignoring case, punctuation and quote style
+fn same_title(a: &str, b: &str) -> bool {
+    let normalize = |title: &str| -> String {
+        title
+            .chars()
+            .filter(|c| c.is_alphanumeric())
+            .flat_map(char::to_lowercase)
+            .collect()
+    };
+    normalize(a) == normalize(b)
+}
@@ -0,0 +1,252 @@
+use crate::diagnostics::Diagnostic;
+use crate::exercise::Exercise;
+use crate::sandbox;
+use crate::testing::{IndividualTestResult, TestRunner};
+use crate::validation::{IssueCategory, IssueSeverity, ValidationIssue};
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use walkdir::WalkDir;
+
+/// Runs an exercise's tests against each reference solution and against
+/// the starter template.
+///
+/// Every run happens in a throwaway copy of the exercise, so the author's
+/// files are never touched. The copies share one target directory, so
+/// dependencies are only built once per exercise.
+#[derive(Debug, Clone)]
+pub struct SolutionVerifier {
+    timeout: Duration,
+}
+
+/// What verification found for one exercise
+#[derive(Debug, Clone, Default)]
+pub struct VerificationReport {
+    pub failing_solutions: Vec<String>, // solution names, e.g. "reference"
+    pub template_passes: bool,          // the starter code already passes every test
+    pub issues: Vec<ValidationIssue>,
+}
+
+/// Outcome of running the tests against one version of the source
+struct VariantRun {
+    compile_errors: Vec<Diagnostic>,
+    tests: Vec<IndividualTestResult>,
+}
+
+impl VariantRun {
+    fn passed(&self) -> bool {
+        self.compile_errors.is_empty() && self.tests.iter().all(|test| test.passed)
+    }
+}
+
+impl SolutionVerifier {
+    pub fn new() -> Self {
+        Self {
+            timeout: sandbox::DEFAULT_BUILD_TIMEOUT,
+        }
+    }
+
+    /// Set the timeout for each cargo build
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Verify every `solutions/*.rs` file passes the tests and the starter
+    /// template does not
+    pub fn verify(&self, exercise: &Exercise) -> Result<VerificationReport> {
+        let scratch = tempfile::tempdir().context("Failed to create verification directory")?;
+        let mut runner = TestRunner::new();
+        runner.set_timeout(self.timeout);
+        runner.set_target_dir(scratch.path().join("target"));
+
+        // Solutions replace the file learners edit
+        let primary_source = exercise
+            .get_primary_source()
+            .and_then(|source| source.path.strip_prefix(&exercise.path).ok())
+            .map(Path::to_path_buf);
+        let target = primary_source
+            .clone()
+            .unwrap_or_else(|| PathBuf::from("src").join("main.rs"));
+
+        let mut report = VerificationReport::default();
+
+        for solution in &exercise.solutions {
+            let copy = scratch.path().join(format!("solution-{}", solution.name));
+            let run = self.run_variant(&runner, exercise, &copy, Some((&target, &solution.content)))?;
+            if run.passed() {
+                continue;
+            }
+
+            let solution_file = format!("solutions/{}.rs", solution.name);
+            report.failing_solutions.push(solution.name.clone());
+            report
+                .issues
+                .extend(solution_issues(exercise, &solution.name, &solution_file, &target, &run));
+        }
+
+        // Without starter code there is nothing the learner could already pass
+        if let Some(template) = primary_source {
+            let run = self.run_variant(&runner, exercise, &scratch.path().join("template"), None)?;
+            if run.passed() && !run.tests.is_empty() {
+                report.template_passes = true;
+                let content = std::fs::read_to_string(exercise.path.join(&template)).unwrap_or_default();
+                report.issues.push(ValidationIssue {
+                    severity: IssueSeverity::Error,
+                    category: IssueCategory::Pedagogical,
+                    message: format!(
+                        "Starter template already passes all {} tests, so the exercise is trivial",
+                        run.tests.len()
+                    ),
+                    file: Some(relative_name(&template)),
+                    line: line_of(&content, |line| line.contains("TODO")),
+                    suggestion: Some(
+                        "Leave the learner's part unimplemented, or add tests that exercise it".to_string(),
+                    ),
+                });
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Copy the exercise to `copy`, optionally replace one file, and run its tests
+    fn run_variant(
+        &self,
+        runner: &TestRunner,
+        exercise: &Exercise,
+        copy: &Path,
+        replacement: Option<(&Path, &str)>,
+    ) -> Result<VariantRun> {
+        copy_exercise(&exercise.path, copy)?;
+        if let Some((file, content)) = replacement {
+            let file = copy.join(file);
+            if let Some(parent) = file.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&file, content)?;
+        }
+
+        let copied = Exercise::load_from_path(copy)?;
+        let (compile_errors, tests) = runner.run_test_suite(&copied)?;
+        Ok(VariantRun {
+            compile_errors,
+            tests,
+        })
+    }
+}
+
+impl Default for SolutionVerifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Copy an exercise without its build output, as a standalone crate
+fn copy_exercise(from: &Path, to: &Path) -> Result<()> {
+    for entry in WalkDir::new(from)
+        .into_iter()
+        .filter_entry(|entry| entry.file_name() != "target")
+    {
+        let entry = entry?;
+        let destination = to.join(entry.path().strip_prefix(from)?);
+        if entry.file_type().is_dir() {
+            std::fs::create_dir_all(&destination)?;
+        } else if entry.file_type().is_file() {
+            std::fs::copy(entry.path(), &destination)
+                .with_context(|| format!("Failed to copy {:?}", entry.path()))?;
+        }
+    }
+
+    // Exercises are workspace members; the copy must not look for a workspace
+    let manifest = to.join("Cargo.toml");
+    let content = std::fs::read_to_string(&manifest)?;
+    if !content.contains("[workspace]") {
+        std::fs::write(&manifest, format!("{}\n[workspace]\n", content))?;
+    }
+    Ok(())
+}
+
+/// Issues for a solution that does not pass, pointing at the compiler error
+/// in the solution or at the failing test
+fn solution_issues(
+    exercise: &Exercise,
+    solution: &str,
+    solution_file: &str,
+    target: &Path,
+    run: &VariantRun,
+) -> Vec<ValidationIssue> {
+    let suggestion = Some("Fix the solution, or the test if it expects the wrong thing".to_string());
+    let target = relative_name(target);
+
+    let mut issues: Vec<ValidationIssue> = run
+        .compile_errors
+        .iter()
+        .map(|error| {
+            let span = error.primary_span();
+            let file = span.map(|span| {
+                if span.file_name == target {
+                    solution_file.to_string()
+                } else {
+                    span.file_name.clone()
+                }
+            });
+            ValidationIssue {
+                severity: IssueSeverity::Error,
+                category: IssueCategory::Content,
+                message: format!("Solution '{}' does not compile: {}", solution, error.message),
+                file: file.or_else(|| Some(solution_file.to_string())),
+                line: span.map(|span| span.line_start),
+                suggestion: suggestion.clone(),
+            }
+        })
+        .collect();
+
+    for test in run.tests.iter().filter(|test| !test.passed) {
+        let name = test.name.rsplit("::").next().unwrap_or(&test.name);
+        let location = exercise.test_files.iter().find_map(|file| {
+            line_of(&file.content, |line| line.contains(&format!("fn {}(", name))).map(|line| {
+                let path = file.path.strip_prefix(&exercise.path).unwrap_or(&file.path);
+                (relative_name(path), line)
+            })
+        });
+        let reason = test
+            .error
+            .as_deref()
+            .and_then(|error| error.lines().find(|line| !line.trim().is_empty()))
+            .unwrap_or("failed");
+
+        issues.push(ValidationIssue {
+            severity: IssueSeverity::Error,
+            category: IssueCategory::Content,
+            message: format!("Solution '{}' fails test '{}': {}", solution, test.name, reason),
+            file: Some(location.as_ref().map_or(solution_file.to_string(), |(file, _)| file.clone())),
+            line: location.map(|(_, line)| line),
+            suggestion: suggestion.clone(),
+        });
+    }
+
+    if issues.is_empty() {
+        issues.push(ValidationIssue {
+            severity: IssueSeverity::Error,
+            category: IssueCategory::Content,
+            message: format!("Solution '{}' does not pass the tests", solution),
+            file: Some(solution_file.to_string()),
+            line: None,
+            suggestion,
+        });
+    }
+    issues
+}
+
+/// 1-based number of the first line matching `matches`
+fn line_of(content: &str, matches: impl Fn(&str) -> bool) -> Option<u32> {
+    content
+        .lines()
+        .position(matches)
+        .map(|index| index as u32 + 1)
+}
+
+/// Path relative to the exercise, with forward slashes as cargo reports them
+fn relative_name(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
@@ -0,0 +1,151 @@
+use crate::files::ExerciseFiles;
+use crate::metadata::ExerciseMetadata;
+use crate::progress::validate_user_id;
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Per-user copies of the exercise tree.
+///
+/// The shared exercise directory is never written to. The first time a
+/// learner opens an exercise it is copied to
+/// `workspaces/<user>/<chapter>/<exercise>`, with the editable files taken
+/// from the exercise's starter rather than from whatever is on disk, and
+/// all further reads, writes and cargo runs use the copy. The local
+/// learner (the default user) gets a workspace like everyone else.
+#[derive(Debug, Clone)]
+pub struct WorkspaceManager {
+    exercises_root: PathBuf,
+    workspaces_root: PathBuf,
+}
+
+impl WorkspaceManager {
+    /// Workspaces live in `workspaces/` next to the exercises directory
+    pub fn new<P: AsRef<Path>>(exercises_root: P) -> Self {
+        let exercises_root = exercises_root.as_ref().to_path_buf();
+        let workspaces_root = exercises_root
+            .parent()
+            .unwrap_or_else(|| Path::new("."))
+            .join("workspaces");
+
+        Self {
+            exercises_root,
+            workspaces_root,
+        }
+    }
+
+    /// Directory holding every user's workspace
+    pub fn root(&self) -> &Path {
+        &self.workspaces_root
+    }
+
+    /// Directory holding all of a user's exercise copies
+    pub fn user_root(&self, user_id: &str) -> Result<PathBuf> {
+        validate_user_id(user_id)?;
+        Ok(self.workspaces_root.join(user_id))
+    }
+
+    /// Whether the user has their own copy of an exercise
+    pub fn has_copy(&self, user_id: &str, exercise_dir: &Path) -> bool {
+        self.user_root(user_id)
+            .map(|root| root.join(exercise_dir).join("Cargo.toml").exists())
+            .unwrap_or(false)
+    }
+
+    /// The user's copy of an exercise, creating it from the shared exercise
+    /// with `starter` as its editable files if they have none yet
+    pub fn exercise_dir(
+        &self,
+        user_id: &str,
+        exercise_dir: &Path,
+        metadata: &ExerciseMetadata,
+        starter: &BTreeMap<String, String>,
+    ) -> Result<PathBuf> {
+        self.create_copy(user_id, exercise_dir, |staging| {
+            ExerciseFiles::new(staging, metadata)
+                .replace_all(starter)
+                .context("Failed to write starter files")
+        })
+    }
+
+    /// Give the user a copy of the shared exercise exactly as it is on disk,
+    /// unless they have one. Used to keep code that earlier releases saved
+    /// into the shared tree.
+    pub fn adopt_shared(&self, user_id: &str, exercise_dir: &Path) -> Result<PathBuf> {
+        self.create_copy(user_id, exercise_dir, |_| Ok(()))
+    }
+
+    fn create_copy(
+        &self,
+        user_id: &str,
+        exercise_dir: &Path,
+        prepare: impl FnOnce(&Path) -> Result<()>,
+    ) -> Result<PathBuf> {
+        let target = self.user_root(user_id)?.join(exercise_dir);
+        if self.has_copy(user_id, exercise_dir) {
+            return Ok(target);
+        }
+
+        let source = self.exercises_root.join(exercise_dir);
+        if !source.join("Cargo.toml").exists() {
+            anyhow::bail!("Exercise not found: {:?}", exercise_dir);
+        }
+
+        let parent = target
+            .parent()
+            .context("Workspace path has no parent directory")?;
+        std::fs::create_dir_all(parent)?;
+
+        // Copy into a temporary sibling and rename, so a half-finished copy
+        // is never mistaken for a workspace
+        let staging = tempfile::Builder::new()
+            .prefix(".copy-")
+            .tempdir_in(parent)?;
+        copy_exercise(&source, staging.path())
+            .with_context(|| format!("Failed to copy {:?} into workspace", source))?;
+        prepare(staging.path())?;
+
+        match std::fs::rename(staging.path(), &target) {
+            Ok(()) => {
+                // The directory now lives at `target`; don't let the guard delete it
+                let _ = staging.keep();
+            }
+            // Another request created the copy first; use theirs
+            Err(_) if self.has_copy(user_id, exercise_dir) => {}
+            Err(e) => return Err(e).context("Failed to create workspace copy"),
+        }
+
+        Ok(target)
+    }
+
+    /// Drop the user's copy so the next access starts from the starter again
+    pub fn discard_copy(&self, user_id: &str, exercise_dir: &Path) -> Result<()> {
+        if self.has_copy(user_id, exercise_dir) {
+            std::fs::remove_dir_all(self.user_root(user_id)?.join(exercise_dir))?;
+        }
+        Ok(())
+    }
+}
+
+/// Copy an exercise directory, skipping build output
+fn copy_exercise(source: &Path, destination: &Path) -> Result<()> {
+    let entries = WalkDir::new(source)
+        .min_depth(1)
+        .into_iter()
+        .filter_entry(|entry| entry.file_name() != "target");
+
+    for entry in entries {
+        let entry = entry?;
+        let relative = entry.path().strip_prefix(source)?;
+        let target = destination.join(relative);
+
+        if entry.file_type().is_dir() {
+            std::fs::create_dir_all(&target)?;
+        } else if entry.file_type().is_file() {
+            std::fs::copy(entry.path(), &target)?;
+        }
+    }
+
+    Ok(())
+}
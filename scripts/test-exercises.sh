@@ -37,15 +37,15 @@ test_exercise() {
     
     cd "$exercise_path"
     
-    # Test that metadata is valid JSON
+    # Test that metadata exists, and is valid JSON when written as JSON
     if [ -f "metadata.json" ]; then
         if ! python3 -m json.tool metadata.json > /dev/null 2>&1; then
             print_error "Invalid JSON in metadata.json for $exercise_name"
             cd - > /dev/null
             return 1
         fi
-    else
-        print_error "Missing metadata.json for $exercise_name"
+    elif [ ! -f "metadata.toml" ] && [ ! -f "metadata.yaml" ] && [ ! -f "metadata.yml" ]; then
+        print_error "Missing metadata.json, metadata.toml or metadata.yaml for $exercise_name"
         cd - > /dev/null
         return 1
     fi
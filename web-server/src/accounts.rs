@@ -0,0 +1,177 @@
+//! Local learner accounts with bearer-token sessions.
+//!
+//! Accounts are kept in `progress/accounts.json` next to the progress files.
+//! Passwords are stored as Argon2 hashes and session tokens as SHA-256
+//! digests, so the file never contains anything that can be replayed.
+
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use chrono::{DateTime, Utc};
+use exercise_framework::{progress::validate_user_id, DEFAULT_USER};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+const MIN_PASSWORD_LENGTH: usize = 8;
+
+#[derive(Debug, thiserror::Error)]
+pub enum AccountError {
+    #[error("{0}")]
+    InvalidUsername(String),
+    #[error("Passwords must be at least {MIN_PASSWORD_LENGTH} characters")]
+    WeakPassword,
+    #[error("Username is already taken")]
+    UsernameTaken,
+    #[error("Invalid username or password")]
+    InvalidCredentials,
+    #[error(transparent)]
+    Storage(#[from] anyhow::Error),
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct AccountsFile {
+    users: Vec<Account>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Account {
+    username: String,
+    password_hash: String,
+    created_at: DateTime<Utc>,
+    #[serde(default)]
+    sessions: Vec<Session>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Session {
+    token_hash: String,
+    created_at: DateTime<Utc>,
+}
+
+/// Persistent account and session store
+pub struct AccountStore {
+    path: PathBuf,
+    accounts: AccountsFile,
+}
+
+impl AccountStore {
+    /// Open the store in `progress_dir`, creating an empty one if needed
+    pub fn open(progress_dir: &Path) -> anyhow::Result<Self> {
+        let path = progress_dir.join("accounts.json");
+        let accounts = if path.exists() {
+            serde_json::from_str(&std::fs::read_to_string(&path)?)?
+        } else {
+            AccountsFile::default()
+        };
+
+        Ok(Self { path, accounts })
+    }
+
+    /// Create an account and return a session token for it
+    pub fn register(&mut self, username: &str, password: &str) -> Result<String, AccountError> {
+        validate_user_id(username).map_err(|e| AccountError::InvalidUsername(e.to_string()))?;
+        if username == DEFAULT_USER {
+            return Err(AccountError::UsernameTaken);
+        }
+        if password.chars().count() < MIN_PASSWORD_LENGTH {
+            return Err(AccountError::WeakPassword);
+        }
+        if self.find(username).is_some() {
+            return Err(AccountError::UsernameTaken);
+        }
+
+        let salt = SaltString::generate(&mut OsRng);
+        let password_hash = Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .map_err(|e| anyhow::anyhow!("Failed to hash password: {}", e))?
+            .to_string();
+
+        self.accounts.users.push(Account {
+            username: username.to_string(),
+            password_hash,
+            created_at: Utc::now(),
+            sessions: Vec::new(),
+        });
+
+        self.start_session(username)
+    }
+
+    /// Check a password and return a new session token
+    pub fn login(&mut self, username: &str, password: &str) -> Result<String, AccountError> {
+        let account = self.find(username).ok_or(AccountError::InvalidCredentials)?;
+        let hash = PasswordHash::new(&account.password_hash)
+            .map_err(|e| anyhow::anyhow!("Corrupt password hash for {}: {}", username, e))?;
+        Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .map_err(|_| AccountError::InvalidCredentials)?;
+
+        self.start_session(username)
+    }
+
+    /// End the session belonging to `token`
+    pub fn logout(&mut self, token: &str) -> anyhow::Result<()> {
+        let token_hash = hash_token(token);
+        for account in &mut self.accounts.users {
+            account.sessions.retain(|s| s.token_hash != token_hash);
+        }
+        self.save()
+    }
+
+    /// Username for a session token, if the token is valid
+    pub fn authenticate(&self, token: &str) -> Option<String> {
+        let token_hash = hash_token(token);
+        self.accounts
+            .users
+            .iter()
+            .find(|account| account.sessions.iter().any(|s| s.token_hash == token_hash))
+            .map(|account| account.username.clone())
+    }
+
+    fn find(&self, username: &str) -> Option<&Account> {
+        self.accounts.users.iter().find(|a| a.username == username)
+    }
+
+    fn start_session(&mut self, username: &str) -> Result<String, AccountError> {
+        let mut bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut bytes);
+        let token: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+
+        let account = self
+            .accounts
+            .users
+            .iter_mut()
+            .find(|a| a.username == username)
+            .ok_or(AccountError::InvalidCredentials)?;
+        account.sessions.push(Session {
+            token_hash: hash_token(&token),
+            created_at: Utc::now(),
+        });
+
+        self.save()?;
+        Ok(token)
+    }
+
+    /// Write to a temporary file and rename so a crash never truncates the store
+    fn save(&self) -> anyhow::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(&self.accounts)?)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&tmp, std::fs::Permissions::from_mode(0o600))?;
+        }
+        std::fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+fn hash_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
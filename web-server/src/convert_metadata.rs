@@ -0,0 +1,67 @@
+//! `rust-tour convert-metadata`: rewrite exercise metadata in another
+//! format (JSON, TOML or YAML).
+
+use anyhow::{Context, Result};
+use exercise_framework::metadata::{convert_file, find_metadata_file, MetadataFormat};
+use exercise_framework::Exercise;
+use std::path::Path;
+
+const USAGE: &str = "Usage: rust-tour convert-metadata --to <json|toml|yaml> [exercise-id...]
+
+Rewrites the metadata of every exercise (or just the ones named) in the given
+format, replacing the old file.";
+
+/// Run the subcommand from the workspace root (the directory holding `exercises/`)
+pub fn run(args: &[String], workspace_root: &Path) -> Result<()> {
+    let mut format = None;
+    let mut ids = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            "--to" => format = Some(args.next().context("--to needs a format")?.parse::<MetadataFormat>()?),
+            option if option.starts_with("--") => anyhow::bail!("Unknown option '{}'\n\n{}", option, USAGE),
+            id => ids.push(id.to_string()),
+        }
+    }
+    let format = format.with_context(|| format!("--to is required\n\n{}", USAGE))?;
+
+    let exercises_root = workspace_root.join("exercises");
+    if !exercises_root.is_dir() {
+        anyhow::bail!("No exercises directory in {:?}; run from the repository root", workspace_root);
+    }
+
+    let exercises: Vec<_> = Exercise::list_all_with_paths(&exercises_root)?
+        .into_iter()
+        .filter(|(metadata, _)| ids.is_empty() || ids.contains(&metadata.id))
+        .collect();
+    if let Some(unknown) = ids
+        .iter()
+        .find(|id| !exercises.iter().any(|(metadata, _)| metadata.id == **id))
+    {
+        anyhow::bail!("Exercise not found: {}", unknown);
+    }
+
+    let mut converted = 0;
+    for (metadata, path) in &exercises {
+        let Some(file) = find_metadata_file(path)? else {
+            continue;
+        };
+        if MetadataFormat::from_path(&file) == Some(format) {
+            continue;
+        }
+        let target = convert_file(&file, format)?;
+        converted += 1;
+        println!(
+            "✅ {}: {}",
+            metadata.id,
+            target.strip_prefix(workspace_root).unwrap_or(&target).display()
+        );
+    }
+
+    println!("Converted {} of {} exercises to {}", converted, exercises.len(), format);
+    Ok(())
+}
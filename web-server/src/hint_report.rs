@@ -0,0 +1,126 @@
+//! `rust-tour hint-report`: how well each exercise's hints work, from the
+//! hint usage and feedback stored in learner progress.
+
+use anyhow::{Context, Result};
+use exercise_framework::{HintEffectiveness, HintUsageTracker};
+use std::fmt::Write as _;
+use std::path::Path;
+
+const USAGE: &str = "Usage: rust-tour hint-report [options]
+
+Options:
+  --exercise <id>      Only report this exercise
+  --json <file>        Write the report as JSON
+  --markdown <file>    Write a Markdown summary
+  --help               Show this message
+
+Use - as the file to write to stdout. Without any report option the
+Markdown summary is printed. Progress is read from progress/ with the
+backend chosen by RUST_TOUR_PROGRESS_STORE, as the server does.";
+
+/// Run the subcommand from the workspace root (the directory holding `progress/`)
+pub fn run(args: &[String], workspace_root: &Path) -> Result<()> {
+    let mut outputs: Vec<(&str, &str)> = Vec::new(); // (format, file)
+    let mut exercise = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            "--exercise" => {
+                exercise = Some(args.next().context("--exercise needs an id")?);
+            }
+            "--json" | "--markdown" => {
+                let file = args
+                    .next()
+                    .with_context(|| format!("{} needs a file", arg))?;
+                outputs.push((&arg[2..], file));
+            }
+            _ => anyhow::bail!("Unknown argument '{}'\n\n{}", arg, USAGE),
+        }
+    }
+    if outputs.is_empty() {
+        outputs.push(("markdown", "-"));
+    }
+
+    let store = crate::open_progress_store(&workspace_root.join("progress"))?;
+    let tracker = HintUsageTracker::from_store(store.as_ref())?;
+    let mut report = match exercise {
+        Some(exercise_id) => vec![tracker.calculate_effectiveness(exercise_id)],
+        None => tracker.report(),
+    };
+    // Weakest hints first
+    report.sort_by(|a, b| {
+        b.improvement_suggestions
+            .len()
+            .cmp(&a.improvement_suggestions.len())
+            .then(a.success_rate_with_hints.total_cmp(&b.success_rate_with_hints))
+            .then_with(|| a.exercise_id.cmp(&b.exercise_id))
+    });
+
+    for (format, file) in outputs {
+        let content = match format {
+            "json" => serde_json::to_string_pretty(&report)? + "\n",
+            _ => to_markdown(&report),
+        };
+        if file == "-" {
+            print!("{}", content);
+        } else {
+            std::fs::write(file, content).with_context(|| format!("Failed to write {}", file))?;
+            eprintln!("📝 Wrote {} report to {}", format, file);
+        }
+    }
+    Ok(())
+}
+
+fn to_markdown(report: &[HintEffectiveness]) -> String {
+    let mut out = String::from("# Hint effectiveness\n\n");
+    if report.is_empty() {
+        out.push_str("No hint usage recorded yet.\n");
+        return out;
+    }
+
+    for exercise in report {
+        let _ = writeln!(out, "## {}\n", exercise.exercise_id);
+        let _ = writeln!(
+            out,
+            "{} learners, {:.0}% used hints, {:.0}% of them solved it afterwards, {:.1} hints each",
+            exercise.learners,
+            exercise.hint_usage_rate * 100.0,
+            exercise.success_rate_with_hints * 100.0,
+            exercise.average_hints_per_user
+        );
+        if let Some(seconds) = exercise.median_seconds_to_first_hint {
+            let _ = writeln!(out, "Median time to first hint: {}m {:02}s", seconds / 60, seconds % 60);
+        }
+
+        if !exercise.levels.is_empty() {
+            out.push_str("\n| Level | Revealed | Helpful | Not helpful | Last level for | Solved after |\n");
+            out.push_str("|---|---|---|---|---|---|\n");
+            for level in &exercise.levels {
+                let _ = writeln!(
+                    out,
+                    "| {} | {} | {} | {} | {} | {} |",
+                    level.level.number(),
+                    level.learners,
+                    level.helpful,
+                    level.not_helpful,
+                    level.final_level_for,
+                    level.solved_after
+                );
+            }
+        }
+
+        if !exercise.improvement_suggestions.is_empty() {
+            out.push('\n');
+            for suggestion in &exercise.improvement_suggestions {
+                let _ = writeln!(out, "- {}", suggestion);
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
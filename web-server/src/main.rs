@@ -1,35 +1,56 @@
+mod accounts;
+mod convert_metadata;
+mod hint_report;
+mod metadata_schema;
+mod migrate_metadata;
+mod new_exercise;
+mod validate_curriculum;
+mod verify_exercises;
+
+use accounts::{AccountError, AccountStore};
 use axum::{
+    async_trait,
     extract::{
         ws::{Message, WebSocket, WebSocketUpgrade},
-        Path as AxumPath, Query, State,
+        FromRequestParts, Path as AxumPath, Query, State,
     },
-    http::{header, HeaderValue, Method, StatusCode},
-    response::{Html, IntoResponse, Response},
-    routing::{get, post, put},
+    http::{header, request::Parts, HeaderValue, Method, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post, put},
     Json, Router,
 };
-use chrono::{DateTime, Utc};
+use exercise_framework::{
+    activity::{self, CalendarDay},
+    book::{self, LocalBook},
+    diagnostics::{self, CargoEvent, Diagnostic},
+    files::{EditableFile, ExerciseFiles, FileError},
+    hints::{Hint, HintReveal, HintStatus},
+    libtest,
+    metadata::{BookRelevance, TestConfig},
+    progress::{Achievement, ExerciseCompletion},
+    progress_store::SqliteProgressStore,
+    sandbox::{self, OutputLine, OutputStream, Sandbox, SandboxLimits, SandboxOutput},
+    snapshots::{self, FileDiff, Snapshot, SnapshotSummary, SnapshotTrigger, STARTER_ID},
+    ContextualHint, ExerciseMetadata, Framework, HistoryQuery, JsonProgressStore, ProgressStore, Recommendation,
+    ReviewItem, UserProgress, DEFAULT_USER,
+};
 use futures_util::{sink::SinkExt, stream::StreamExt};
 use notify::{RecommendedWatcher, RecursiveMode, Watcher};
 use portable_pty::{native_pty_system, CommandBuilder, PtySize};
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap},
     env,
     io::{Read, Write},
     net::SocketAddr,
-    path::PathBuf,
-    process::Stdio,
+    path::{Path, PathBuf},
+    process::Command,
     sync::Arc,
     thread,
-    time::Duration,
 };
 use tokio::{
     fs,
-    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
-    process::Command,
-    sync::{broadcast, RwLock, Mutex},
-    time::timeout,
+    sync::{broadcast, mpsc, watch, RwLock, Mutex},
 };
 use tower::ServiceBuilder;
 use tower_http::{
@@ -40,7 +61,6 @@ use tower_http::{
 };
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
-use walkdir::WalkDir;
 
 #[cfg(feature = "embed-assets")]
 use rust_embed::RustEmbed;
@@ -55,24 +75,131 @@ struct Assets;
 #[folder = "../web/node_modules/monaco-editor/"]
 struct MonacoAssets;
 
+/// Longest span the activity calendar returns in one request
+const MAX_CALENDAR_DAYS: i64 = 3 * 366;
+
 // Application state
 #[derive(Clone)]
 struct AppState {
-    connections: Arc<RwLock<HashSet<ConnectionId>>>,
+    connections: Arc<RwLock<HashMap<ConnectionId, UserId>>>,
     terminal_sessions: Arc<RwLock<HashMap<String, TerminalSession>>>,
     pty_handles: Arc<RwLock<HashMap<String, PtyHandle>>>,
     broadcast_tx: broadcast::Sender<BroadcastMessage>,
     debug_websocket: bool,
     exercises_path: PathBuf,
-    progress_path: PathBuf,
+    framework: Arc<RwLock<Framework>>,
+    jobs: Arc<RwLock<HashMap<JobId, JobHandle>>>,
+    accounts: Arc<RwLock<AccountStore>>,
+    require_auth: bool,
+    book: Option<Arc<LocalBook>>, // installed copy of the Rust Book, if any
 }
 
 type ConnectionId = Uuid;
+type JobId = Uuid;
+type UserId = String;
+
+// The learner making a request: the account behind a bearer token (or
+// `?token=` for WebSocket upgrades), or the default user when no token is
+// sent and the server does not require sign-in
+struct CurrentUser(UserId);
+
+#[async_trait]
+impl FromRequestParts<AppState> for CurrentUser {
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let header_token = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(str::to_string);
+        let query_token = parts.uri.query().and_then(|query| {
+            query
+                .split('&')
+                .find_map(|pair| pair.strip_prefix("token="))
+                .map(str::to_string)
+        });
+
+        match header_token.or(query_token) {
+            Some(token) => state
+                .accounts
+                .read()
+                .await
+                .authenticate(&token)
+                .map(CurrentUser)
+                .ok_or(StatusCode::UNAUTHORIZED),
+            None if state.require_auth => Err(StatusCode::UNAUTHORIZED),
+            None => Ok(CurrentUser(DEFAULT_USER.to_string())),
+        }
+    }
+}
+
+// The exercise a request addresses: `/api/exercises/by-id/:id/...` by its
+// metadata id, or `/api/exercises/:chapter/:exercise/...` by directory
+// names. Both resolve through the exercise index, so a path names an
+// exercise that exists and never reaches outside the exercises directory.
+#[derive(Debug, Clone)]
+struct ExerciseRef {
+    id: String,
+    path: PathBuf, // relative to the exercises root
+    metadata: ExerciseMetadata,
+}
+
+impl ExerciseRef {
+    /// Directory path as the API reports it, e.g. `ch01_getting_started/ex01_hello_world`
+    fn path_str(&self) -> String {
+        self.path.to_string_lossy().replace('\\', "/")
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for ExerciseRef {
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let AxumPath(params) = AxumPath::<HashMap<String, String>>::from_request_parts(parts, state)
+            .await
+            .map_err(|_| StatusCode::BAD_REQUEST)?;
+        let framework = state.framework.read().await;
+        let index = framework.exercise_index();
+
+        let path = match (params.get("id"), params.get("chapter"), params.get("exercise")) {
+            (Some(id), _, _) => index.path_of(id).map(Path::to_path_buf),
+            (None, Some(chapter), Some(exercise)) => {
+                if !is_plain_dir_name(chapter) || !is_plain_dir_name(exercise) {
+                    warn!("Rejected exercise path {:?}/{:?}", chapter, exercise);
+                    return Err(StatusCode::BAD_REQUEST);
+                }
+                Some(PathBuf::from(chapter).join(exercise))
+            }
+            _ => return Err(StatusCode::BAD_REQUEST),
+        };
+
+        let (path, metadata) = path
+            .and_then(|path| index.at(&path).cloned().map(|metadata| (path, metadata)))
+            .ok_or(StatusCode::NOT_FOUND)?;
+        Ok(ExerciseRef {
+            id: metadata.id.clone(),
+            path,
+            metadata,
+        })
+    }
+}
+
+/// A single directory name: no separators, no `.`/`..`, nothing hidden
+fn is_plain_dir_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
 
 #[derive(Debug, Clone)]
 struct TerminalSession {
-    session_id: String,
     connection_id: ConnectionId,
+    user_id: UserId, // who created it; only they may attach to it
 }
 
 // Separate struct for actual PTY handles (not Clone/Send)
@@ -82,12 +209,38 @@ struct PtyHandle {
     master: Arc<Mutex<Box<dyn portable_pty::MasterPty + Send>>>,
 }
 
+// A cargo run/test/check started through the job API
+struct JobHandle {
+    connection_id: ConnectionId,
+    cancel: watch::Sender<bool>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum JobKind {
+    Run,
+    Test,
+    Check,
+}
+
+// Everything a running job needs to report back to its connection
+#[derive(Clone)]
+struct JobContext {
+    job_id: JobId,
+    connection_id: ConnectionId,
+    broadcast_tx: broadcast::Sender<BroadcastMessage>,
+    cancel: watch::Receiver<bool>,
+}
+
 #[derive(Debug, Clone, Serialize)]
 struct BroadcastMessage {
     #[serde(rename = "type")]
     msg_type: String,
     #[serde(flatten)]
     data: serde_json::Value,
+    // Only deliver to this connection; `None` goes to everyone
+    #[serde(skip)]
+    target: Option<ConnectionId>,
 }
 
 // WebSocket message types
@@ -117,28 +270,37 @@ struct CargoResult {
     stdout: String,
     stderr: String,
     output: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    diagnostics: Option<Vec<Diagnostic>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exercise_id: Option<String>, // set by the exercise routes
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    hints: Vec<ContextualHint>, // suggested for the diagnostics and panics
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct ExerciseMetadata {
-    id: String,
-    title: String,
-    description: String,
-    chapter: u32,
-    exercise_number: u32,
-    difficulty: String,
-    estimated_time_minutes: u32,
-    concepts: Vec<String>,
-    prerequisites: Vec<String>,
-    exercise_type: String,
-    #[serde(default)]
-    rust_book_refs: serde_json::Value,
-    #[serde(default)]
-    hints: serde_json::Value,
-    #[serde(default)]
-    testing: serde_json::Value,
-    #[serde(default)]
-    validation: serde_json::Value,
+#[derive(Debug, Deserialize)]
+struct CredentialsRequest {
+    username: String,
+    password: String,
+}
+
+#[derive(Debug, Serialize)]
+struct SessionResponse {
+    username: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    token: Option<String>,
+    require_auth: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct StartJobRequest {
+    kind: JobKind,
+    connection_id: ConnectionId,
+}
+
+#[derive(Debug, Serialize)]
+struct JobStarted {
+    job_id: JobId,
 }
 
 #[derive(Debug, Serialize)]
@@ -153,55 +315,77 @@ struct ExerciseDetails {
     metadata: ExerciseMetadata,
     #[serde(rename = "mainContent")]
     main_content: String,
+    #[serde(rename = "mainFile")]
+    main_file: String, // the file mainContent came from and /code saves to
+    files: Vec<EditableFile>,
     readme: String,
-    hints: String,
+    hints: Vec<Hint>, // levels revealed so far; more come from /hints/next
     path: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct ProgressData {
-    user_id: String,
-    created_at: String,
-    overall_progress: f64,
-    chapters_completed: u32,
-    exercises_completed: u32,
-    total_exercises: u32,
-    current_streak: u32,
-    longest_streak: u32,
-    total_time_minutes: u32,
-    chapters: serde_json::Value,
-    exercise_history: Vec<ExerciseHistoryEntry>,
-    achievements: Vec<serde_json::Value>,
-    session_stats: SessionStats,
+#[derive(Debug, Deserialize)]
+struct SaveCodeRequest {
+    code: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct ExerciseHistoryEntry {
-    exercise_id: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    viewed_at: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    completed_at: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    time_taken_minutes: Option<u32>,
-    status: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    session_id: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    hints_used: Option<Vec<u32>>,
+#[derive(Debug, Serialize)]
+struct ExerciseFileList {
+    files: Vec<EditableFile>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct SessionStats {
-    exercises_viewed: u32,
-    exercises_completed: u32,
-    hints_used: u32,
-    time_spent: u32,
+#[derive(Debug, Serialize)]
+struct ExerciseFileContent {
+    path: String,
+    content: String,
 }
 
 #[derive(Debug, Deserialize)]
-struct SaveCodeRequest {
-    code: String,
+struct FilePathParam {
+    path: String, // relative to the exercise directory
+}
+
+#[derive(Debug, Deserialize)]
+struct WriteFileRequest {
+    content: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RenameFileRequest {
+    to: String,
+}
+
+#[derive(Debug, Serialize)]
+struct SnapshotList {
+    snapshots: Vec<SnapshotSummary>, // newest first
+}
+
+#[derive(Debug, Deserialize)]
+struct SnapshotParam {
+    snapshot: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct HintLevelParam {
+    level: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct HintFeedbackRequest {
+    helpful: bool,
+}
+
+/// Snapshot ids to compare; `starter` and `current` (the files on disk) also work
+#[derive(Debug, Deserialize)]
+struct DiffQuery {
+    from: Option<String>, // defaults to starter
+    to: Option<String>,   // defaults to current
+}
+
+#[derive(Debug, Serialize)]
+struct SnapshotDiff {
+    from: String,
+    to: String,
+    files: Vec<FileDiff>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -211,20 +395,61 @@ struct CompleteExerciseRequest {
 }
 
 #[derive(Debug, Deserialize)]
-struct HintRequest {
+struct ViewRequest {
     exercise_id: String,
-    hint_level: u32,
 }
 
+/// Days of the activity calendar, in the user's timezone; defaults to the
+/// 52 weeks up to today
 #[derive(Debug, Deserialize)]
-struct ViewRequest {
-    exercise_id: String,
+struct ActivityQuery {
+    from: Option<chrono::NaiveDate>,
+    to: Option<chrono::NaiveDate>,
+}
+
+#[derive(Debug, Serialize)]
+struct ActivityCalendarResponse {
+    timezone: String,
+    today: chrono::NaiveDate,
+    current_streak: u32,
+    longest_streak: u32,
+    max_count: u32,         // busiest day in the range, for scaling a heatmap
+    days: Vec<CalendarDay>, // every day from `from` to `to`, inactive ones too
+}
+
+#[derive(Debug, Deserialize)]
+struct TimezoneRequest {
+    timezone: String, // IANA name, e.g. "Europe/Berlin"
+}
+
+#[derive(Debug, Deserialize)]
+struct RecommendationsQuery {
+    limit: Option<usize>,
 }
 
 #[derive(Debug, Serialize)]
 struct BookResponse {
-    url: String,
+    url: String, // the local copy when there is one
     chapter: String,
+    title: String,
+    online_url: String,
+    local: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct BookReading {
+    sections: Vec<BookReadingSection>,
+}
+
+/// A `specific_sections` entry of an exercise, with its text when the book
+/// is installed locally
+#[derive(Debug, Serialize)]
+struct BookReadingSection {
+    chapter: String,
+    title: String,
+    relevance: BookRelevance,
+    url: String,
+    excerpt: Option<String>, // HTML
 }
 
 #[derive(Debug, Serialize)]
@@ -275,6 +500,22 @@ impl<T> ApiResponse<T> {
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    // Authoring tools run instead of the server
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Some(command) = args.first() {
+        let current_dir = env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        match command.as_str() {
+            "new-exercise" => return new_exercise::run(&args[1..], &current_dir),
+            "verify-exercises" => return verify_exercises::run(&args[1..], &current_dir),
+            "validate-curriculum" => return validate_curriculum::run(&args[1..], &current_dir),
+            "migrate-metadata" => return migrate_metadata::run(&args[1..], &current_dir),
+            "metadata-schema" => return metadata_schema::run(&args[1..], &current_dir),
+            "convert-metadata" => return convert_metadata::run(&args[1..], &current_dir),
+            "hint-report" => return hint_report::run(&args[1..], &current_dir),
+            _ => {}
+        }
+    }
+
     // Initialize tracing
     tracing_subscriber::fmt()
         .with_env_filter(
@@ -292,28 +533,38 @@ async fn main() -> anyhow::Result<()> {
         .map(|v| v == "true")
         .unwrap_or(false);
 
+    // Classroom deployments: every request must come from a signed-in account
+    let require_auth = env::var("RUST_TOUR_REQUIRE_AUTH")
+        .map(|v| v == "true")
+        .unwrap_or(false);
+
     // Set up paths
     let current_dir = env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
     let exercises_path = current_dir.join("exercises");
-    let progress_path = current_dir.join("progress").join("user_progress.json");
+
+    // Initialize the exercise framework (progress lives in ./progress next to ./exercises)
+    let progress_store = open_progress_store(&current_dir.join("progress"))?;
+    let framework = initialize_framework(&exercises_path, progress_store)?;
+    let accounts = AccountStore::open(&current_dir.join("progress"))?;
 
     // Create broadcast channel for WebSocket messages
-    let (broadcast_tx, _) = broadcast::channel(100);
+    let (broadcast_tx, _) = broadcast::channel(1024);
 
     // Initialize application state
     let state = AppState {
-        connections: Arc::new(RwLock::new(HashSet::new())),
+        connections: Arc::new(RwLock::new(HashMap::new())),
         terminal_sessions: Arc::new(RwLock::new(HashMap::new())),
         pty_handles: Arc::new(RwLock::new(HashMap::new())),
         broadcast_tx: broadcast_tx.clone(),
         debug_websocket,
         exercises_path: exercises_path.clone(),
-        progress_path,
+        framework: Arc::new(RwLock::new(framework)),
+        jobs: Arc::new(RwLock::new(HashMap::new())),
+        accounts: Arc::new(RwLock::new(accounts)),
+        require_auth,
+        book: open_local_book(),
     };
 
-    // Initialize progress system
-    initialize_progress_system(&state).await?;
-
     // Set up file watching
     setup_file_watcher(state.clone()).await?;
 
@@ -325,6 +576,9 @@ async fn main() -> anyhow::Result<()> {
 
     info!("🌐 Rust Tour server running on http://localhost:{}", port);
     info!("📡 WebSocket available at ws://localhost:{}/ws", port);
+    if require_auth {
+        info!("🔒 Sign-in required for all learners");
+    }
     info!("🦀 Ready to serve Rust tutorial exercises!");
 
     axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
@@ -341,16 +595,23 @@ fn create_router(state: AppState) -> Router {
         
         // API routes
         .route("/api/exercises", get(get_exercises))
-        .route("/api/exercises/:chapter/:exercise", get(get_exercise))
-        .route("/api/exercises/:chapter/:exercise/code", put(save_exercise_code))
-        .route("/api/exercises/:chapter/:exercise/test", post(test_exercise))
-        .route("/api/exercises/:chapter/:exercise/run", post(run_exercise))
-        .route("/api/exercises/:chapter/:exercise/check", post(check_exercise))
+        .nest("/api/exercises/by-id/:id", exercise_routes())
+        .nest("/api/exercises/:chapter/:exercise", exercise_routes())
+        .route("/api/jobs/:job_id", delete(cancel_job))
+        .route("/api/auth/register", post(register))
+        .route("/api/auth/login", post(login))
+        .route("/api/auth/logout", post(logout))
+        .route("/api/auth/me", get(current_session))
         .route("/api/progress", get(get_progress))
+        .route("/api/progress/history", get(get_progress_history))
+        .route("/api/recommendations", get(get_recommendations))
+        .route("/api/reviews", get(get_review_queue))
         .route("/api/progress/complete", post(complete_exercise))
-        .route("/api/progress/hint", post(track_hint_usage))
         .route("/api/progress/view", post(track_exercise_view))
+        .route("/api/progress/activity", get(get_activity_calendar))
+        .route("/api/progress/timezone", put(set_timezone))
         .route("/api/book/:chapter", get(get_book_chapter))
+        .route("/book/*path", get(serve_book_file))
         
         // Static file routes
         .route("/monaco/*path", get(serve_monaco_files))
@@ -363,7 +624,7 @@ fn create_router(state: AppState) -> Router {
                 .layer(RequestBodyLimitLayer::new(50 * 1024 * 1024)) // 50MB limit
                 .layer(
                     CorsLayer::new()
-                        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
+                        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::PATCH, Method::DELETE])
                         .allow_headers(Any)
                         .allow_origin(Any),
                 )
@@ -371,32 +632,78 @@ fn create_router(state: AppState) -> Router {
         .with_state(state)
 }
 
+/// Routes for one exercise, mounted under both its id and its directory
+fn exercise_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(get_exercise))
+        .route("/code", put(save_exercise_code))
+        .route("/test", post(test_exercise))
+        .route("/run", post(run_exercise))
+        .route("/check", post(check_exercise))
+        .route("/jobs", post(start_job))
+        .route("/files", get(list_exercise_files))
+        .route("/files/*path", get(read_exercise_file)
+            .put(write_exercise_file)
+            .post(create_exercise_file)
+            .patch(rename_exercise_file)
+            .delete(delete_exercise_file))
+        .route("/snapshots", get(list_snapshots))
+        .route("/snapshots/diff", get(diff_snapshots))
+        .route("/snapshots/:snapshot", get(get_snapshot))
+        .route("/snapshots/:snapshot/restore", post(restore_snapshot))
+        .route("/reset", post(reset_exercise))
+        .route("/book", get(get_exercise_reading))
+        .route("/hints/next", get(reveal_next_hint))
+        .route("/hints/:level/feedback", post(record_hint_feedback))
+}
+
 // WebSocket handlers
 async fn websocket_handler(
     ws: WebSocketUpgrade,
+    CurrentUser(user_id): CurrentUser,
     State(state): State<AppState>,
 ) -> Response {
-    ws.on_upgrade(|socket| websocket_connection(socket, state))
+    ws.on_upgrade(|socket| websocket_connection(socket, state, user_id))
 }
 
-async fn websocket_connection(socket: WebSocket, state: AppState) {
+async fn websocket_connection(socket: WebSocket, state: AppState, user_id: UserId) {
     let connection_id = Uuid::new_v4();
     
     // Add connection to state
     {
         let mut connections = state.connections.write().await;
-        connections.insert(connection_id);
+        connections.insert(connection_id, user_id.clone());
     }
     
-    info!("Client connected to WebSocket: {}", connection_id);
+    info!("Client connected to WebSocket: {} ({})", connection_id, user_id);
     
     let mut broadcast_rx = state.broadcast_tx.subscribe();
     let (mut sender, mut receiver) = socket.split();
     
+    // Tell the client its id so it can start jobs scoped to this connection
+    let hello = serde_json::json!({
+        "type": "connected",
+        "connectionId": connection_id
+    });
+    if sender.send(Message::Text(hello.to_string())).await.is_err() {
+        warn!("Failed to send connection id to {}", connection_id);
+    }
+    
     // Spawn task to handle broadcast messages
-    let broadcast_state = state.clone();
     let broadcast_task = tokio::spawn(async move {
-        while let Ok(msg) = broadcast_rx.recv().await {
+        loop {
+            let msg = match broadcast_rx.recv().await {
+                Ok(msg) => msg,
+                // Busy jobs can outpace a slow client; drop the backlog rather than the socket
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("WebSocket {} skipped {} messages", connection_id, skipped);
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            if msg.target.is_some_and(|target| target != connection_id) {
+                continue;
+            }
             if let Ok(json) = serde_json::to_string(&msg) {
                 if sender.send(Message::Text(json)).await.is_err() {
                     break;
@@ -431,7 +738,12 @@ async fn websocket_connection(socket: WebSocket, state: AppState) {
     }
     
     // Clean up terminal sessions for this connection
-    cleanup_terminal_sessions(&state, connection_id).await;
+    if let Err(e) = cleanup_terminal_sessions(&state, connection_id).await {
+        error!("Error cleaning up terminal sessions: {}", e);
+    }
+    
+    // Nobody is left to see the output of this connection's jobs
+    cancel_connection_jobs(&state, connection_id).await;
     
     broadcast_task.abort();
     info!("Client disconnected from WebSocket: {}", connection_id);
@@ -470,6 +782,16 @@ async fn handle_terminal_message(
         debug!("Handling terminal message: {}", msg.action);
     }
     
+    // Everything but creating a session needs to come from its owner
+    if let Some(session_id) = &msg.session_id {
+        if !matches!(msg.action.as_str(), "create" | "check")
+            && !owns_terminal_session(state, connection_id, session_id).await
+        {
+            warn!("Refused terminal {} on session {} from another user", msg.action, session_id);
+            return Ok(());
+        }
+    }
+    
     match msg.action.as_str() {
         "create" => {
             let session_id = msg.session_id.unwrap_or_else(|| Uuid::new_v4().to_string());
@@ -511,25 +833,31 @@ async fn create_terminal_session(
     cols: Option<u16>,
     rows: Option<u16>,
 ) -> anyhow::Result<()> {
-    // Check if session already exists
-    {
-        let sessions = state.terminal_sessions.read().await;
-        if sessions.contains_key(&session_id) {
-            // Update connection ID for existing session
-            let mut sessions = state.terminal_sessions.write().await;
-            if let Some(session) = sessions.get_mut(&session_id) {
-                session.connection_id = connection_id;
-            }
-            send_terminal_response(state, &session_id, "created").await?;
+    // A shell would bypass workspace isolation, so only the local learner gets one
+    let user_id = state.connections.read().await.get(&connection_id).cloned();
+    let Some(user_id) = user_id.filter(|user_id| !state.require_auth && user_id == DEFAULT_USER) else {
+        send_terminal_response(state, connection_id, &session_id, "unavailable").await?;
+        return Ok(());
+    };
+    
+    // Reattach to an existing session
+    let exists = state.terminal_sessions.read().await.contains_key(&session_id);
+    if exists {
+        if !rebind_terminal_session(state, connection_id, &session_id).await {
+            send_terminal_response(state, connection_id, &session_id, "unavailable").await?;
             return Ok(());
         }
+        send_terminal_response(state, connection_id, &session_id, "created").await?;
+        return Ok(());
     }
     
     let cols = cols.unwrap_or(80);
     let rows = rows.unwrap_or(24);
     
-    // Determine working directory and shell
-    let cwd = state.exercises_path.clone();
+    // Determine working directory and shell: the learner's workspaces, so
+    // the shared exercises stay untouched
+    let cwd = state.framework.read().await.workspaces().user_root(DEFAULT_USER)?;
+    std::fs::create_dir_all(&cwd)?;
     let shell = if cfg!(windows) {
         "powershell.exe"
     } else {
@@ -562,8 +890,8 @@ async fn create_terminal_session(
     
     // Create session
     let session = TerminalSession {
-        session_id: session_id.clone(),
         connection_id,
+        user_id,
     };
     
     let pty_handle = PtyHandle {
@@ -612,6 +940,11 @@ async fn create_terminal_session(
         while let Some(data) = rx.recv().await {
             let data_str = String::from_utf8_lossy(&data).to_string();
             
+            // Output goes only to the connection that owns the session,
+            // which changes when the owner reconnects
+            let Some(owner) = terminal_session_owner(&state_clone, &session_id_clone).await else {
+                continue;
+            };
             let message = BroadcastMessage {
                 msg_type: "terminal".to_string(),
                 data: serde_json::json!({
@@ -619,21 +952,25 @@ async fn create_terminal_session(
                     "sessionId": session_id_clone,
                     "data": data_str
                 }),
+                target: Some(owner),
             };
             
             let _ = state_clone.broadcast_tx.send(message);
         }
         
         // Send exit message when PTY closes
-        let exit_message = BroadcastMessage {
-            msg_type: "terminal".to_string(),
-            data: serde_json::json!({
-                "action": "exit",
-                "sessionId": session_id_clone
-            }),
-        };
-        
-        let _ = state_clone.broadcast_tx.send(exit_message);
+        if let Some(owner) = terminal_session_owner(&state_clone, &session_id_clone).await {
+            let exit_message = BroadcastMessage {
+                msg_type: "terminal".to_string(),
+                data: serde_json::json!({
+                    "action": "exit",
+                    "sessionId": session_id_clone
+                }),
+                target: Some(owner),
+            };
+            
+            let _ = state_clone.broadcast_tx.send(exit_message);
+        }
         
         // Clean up session
         {
@@ -646,7 +983,7 @@ async fn create_terminal_session(
         }
     });
     
-    send_terminal_response(state, &session_id, "created").await?;
+    send_terminal_response(state, connection_id, &session_id, "created").await?;
     
     if state.debug_websocket {
         info!("Terminal session {} created with PTY", session_id);
@@ -660,20 +997,13 @@ async fn check_terminal_session(
     connection_id: ConnectionId,
     session_id: String,
 ) -> anyhow::Result<()> {
-    let sessions = state.terminal_sessions.read().await;
-    let handles = state.pty_handles.read().await;
+    let has_pty = state.pty_handles.read().await.contains_key(&session_id);
     
-    if let (Some(_session), Some(_handle)) = (sessions.get(&session_id), handles.get(&session_id)) {
-        send_terminal_response(state, &session_id, "exists").await?;
-        
-        // Update connection ID for existing session
-        drop(sessions);
-        let mut sessions = state.terminal_sessions.write().await;
-        if let Some(session) = sessions.get_mut(&session_id) {
-            session.connection_id = connection_id;
-        }
+    // Someone else's session is reported as missing
+    if has_pty && rebind_terminal_session(state, connection_id, &session_id).await {
+        send_terminal_response(state, connection_id, &session_id, "exists").await?;
     } else {
-        send_terminal_response(state, &session_id, "not_found").await?;
+        send_terminal_response(state, connection_id, &session_id, "not_found").await?;
     }
     Ok(())
 }
@@ -717,7 +1047,7 @@ async fn resize_terminal(
         
         // Move resizing to blocking task since PTY operations are not async
         tokio::task::spawn_blocking(move || {
-            if let Ok(mut master) = master.try_lock() {
+            if let Ok(master) = master.try_lock() {
                 let new_size = PtySize {
                     rows,
                     cols,
@@ -790,8 +1120,39 @@ async fn cleanup_terminal_sessions(
     Ok(())
 }
 
+/// Whether the connection's user created the terminal session
+async fn owns_terminal_session(state: &AppState, connection_id: ConnectionId, session_id: &str) -> bool {
+    let user_id = state.connections.read().await.get(&connection_id).cloned();
+    let sessions = state.terminal_sessions.read().await;
+    match (user_id, sessions.get(session_id)) {
+        (Some(user_id), Some(session)) => session.user_id == user_id,
+        _ => false,
+    }
+}
+
+/// Move a terminal session to a new connection of the user who created it.
+/// Returns false, leaving the session alone, for anyone else.
+async fn rebind_terminal_session(state: &AppState, connection_id: ConnectionId, session_id: &str) -> bool {
+    let user_id = state.connections.read().await.get(&connection_id).cloned();
+    let mut sessions = state.terminal_sessions.write().await;
+    match (user_id, sessions.get_mut(session_id)) {
+        (Some(user_id), Some(session)) if session.user_id == user_id => {
+            session.connection_id = connection_id;
+            true
+        }
+        _ => false,
+    }
+}
+
+/// The connection a terminal session currently belongs to
+async fn terminal_session_owner(state: &AppState, session_id: &str) -> Option<ConnectionId> {
+    let sessions = state.terminal_sessions.read().await;
+    sessions.get(session_id).map(|session| session.connection_id)
+}
+
 async fn send_terminal_response(
     state: &AppState,
+    connection_id: ConnectionId,
     session_id: &str,
     action: &str,
 ) -> anyhow::Result<()> {
@@ -801,6 +1162,7 @@ async fn send_terminal_response(
             "action": action,
             "sessionId": session_id
         }),
+        target: Some(connection_id),
     };
     
     let _ = state.broadcast_tx.send(response);
@@ -809,7 +1171,7 @@ async fn send_terminal_response(
 
 // API handlers
 async fn get_exercises(State(state): State<AppState>) -> Result<Json<Vec<ExerciseWithPath>>, StatusCode> {
-    match scan_exercises(&state.exercises_path).await {
+    match list_exercises(&state).await {
         Ok(exercises) => Ok(Json(exercises)),
         Err(e) => {
             error!("Error loading exercises: {}", e);
@@ -819,166 +1181,712 @@ async fn get_exercises(State(state): State<AppState>) -> Result<Json<Vec<Exercis
 }
 
 async fn get_exercise(
-    AxumPath((chapter, exercise)): AxumPath<(String, String)>,
+    exercise: ExerciseRef,
+    CurrentUser(user_id): CurrentUser,
     State(state): State<AppState>,
 ) -> Result<Json<ExerciseDetails>, StatusCode> {
-    let exercise_path = state.exercises_path.join(&chapter).join(&exercise);
-    
-    match load_exercise_details(&exercise_path, &format!("{}/{}", chapter, exercise)).await {
+    match load_exercise_details(&state, &user_id, &exercise).await {
         Ok(details) => Ok(Json(details)),
         Err(e) => {
-            error!("Error loading exercise {}/{}: {}", chapter, exercise, e);
+            error!("Error loading exercise {}: {}", exercise.id, e);
             Err(StatusCode::NOT_FOUND)
         }
     }
 }
 
 async fn save_exercise_code(
-    AxumPath((chapter, exercise)): AxumPath<(String, String)>,
+    exercise: ExerciseRef,
+    CurrentUser(user_id): CurrentUser,
     State(state): State<AppState>,
     Json(request): Json<SaveCodeRequest>,
 ) -> Result<Json<ApiResponse<()>>, StatusCode> {
-    let exercise_path = state.exercises_path.join(&chapter).join(&exercise);
-    let main_path = exercise_path.join("src").join("main.rs");
+    let files = prepare_exercise_write(&state, &user_id, &exercise).await?;
+    let main_file = files.main_file();
     
-    match fs::write(&main_path, &request.code).await {
+    match files.write(main_file, &request.code) {
         Ok(_) => {
-            // Broadcast file change
-            let exercise_name = match load_exercise_title(&exercise_path).await {
-                Ok(title) => title,
-                Err(_) => format!("{}/{}", chapter, exercise),
-            };
-            
-            let broadcast_msg = BroadcastMessage {
-                msg_type: "file_updated".to_string(),
-                data: serde_json::json!({
-                    "exercise": exercise_name,
-                    "file": "src/main.rs"
-                }),
-            };
-            
-            let _ = state.broadcast_tx.send(broadcast_msg);
-            
+            record_snapshot(&state, &user_id, &exercise, &files, SnapshotTrigger::Save).await;
+            broadcast_file_update(&state, &user_id, &exercise, "file_updated", main_file).await;
             Ok(Json(ApiResponse::success(())))
         }
         Err(e) => {
-            error!("Error saving code for {}/{}: {}", chapter, exercise, e);
+            error!("Error saving code for {}: {}", exercise.id, e);
             Err(StatusCode::INTERNAL_SERVER_ERROR)
         }
     }
 }
 
 async fn test_exercise(
-    AxumPath((chapter, exercise)): AxumPath<(String, String)>,
+    exercise: ExerciseRef,
+    CurrentUser(user_id): CurrentUser,
     State(state): State<AppState>,
 ) -> Result<Json<CargoResult>, StatusCode> {
-    let exercise_path = state.exercises_path.join(&chapter).join(&exercise);
+    let exercise_path = user_exercise_dir(&state, &user_id, &exercise).await?;
     
-    match run_cargo_command("test", &exercise_path, vec!["--", "--nocapture"]).await {
-        Ok(result) => Ok(Json(result)),
+    match run_exercise_tests(&exercise_path, &exercise.metadata.testing, None).await {
+        Ok(result) => {
+            record_test_run(&state, &user_id, &exercise.id, result.success).await;
+            Ok(Json(with_contextual_hints(&state.framework, &exercise, result).await))
+        }
         Err(e) => {
-            error!("Error running tests for {}/{}: {}", chapter, exercise, e);
+            error!("Error running tests for {}: {}", exercise.id, e);
             Err(StatusCode::INTERNAL_SERVER_ERROR)
         }
     }
 }
 
 async fn run_exercise(
-    AxumPath((chapter, exercise)): AxumPath<(String, String)>,
+    exercise: ExerciseRef,
+    CurrentUser(user_id): CurrentUser,
     State(state): State<AppState>,
 ) -> Result<Json<CargoResult>, StatusCode> {
-    let exercise_path = state.exercises_path.join(&chapter).join(&exercise);
+    let exercise_path = user_exercise_dir(&state, &user_id, &exercise).await?;
     
-    match run_cargo_command("run", &exercise_path, vec![]).await {
-        Ok(result) => Ok(Json(result)),
+    match run_exercise_binary(&exercise_path, &exercise.metadata.testing, None).await {
+        Ok(result) => Ok(Json(with_contextual_hints(&state.framework, &exercise, result).await)),
         Err(e) => {
-            error!("Error running exercise {}/{}: {}", chapter, exercise, e);
+            error!("Error running exercise {}: {}", exercise.id, e);
             Err(StatusCode::INTERNAL_SERVER_ERROR)
         }
     }
 }
 
 async fn check_exercise(
-    AxumPath((chapter, exercise)): AxumPath<(String, String)>,
+    exercise: ExerciseRef,
+    CurrentUser(user_id): CurrentUser,
     State(state): State<AppState>,
 ) -> Result<Json<CargoResult>, StatusCode> {
-    let exercise_path = state.exercises_path.join(&chapter).join(&exercise);
+    let exercise_path = user_exercise_dir(&state, &user_id, &exercise).await?;
     
-    match run_cargo_command("clippy", &exercise_path, vec!["--", "-W", "clippy::all"]).await {
-        Ok(result) => Ok(Json(result)),
+    match run_exercise_check(&exercise_path, &exercise.metadata.testing, None).await {
+        Ok(result) => Ok(Json(with_contextual_hints(&state.framework, &exercise, result).await)),
         Err(e) => {
-            error!("Error running clippy for {}/{}: {}", chapter, exercise, e);
+            error!("Error running clippy for {}: {}", exercise.id, e);
             Err(StatusCode::INTERNAL_SERVER_ERROR)
         }
     }
 }
 
-async fn get_progress(State(state): State<AppState>) -> Result<Json<ProgressData>, StatusCode> {
-    match ensure_progress_file(&state.progress_path, &state.exercises_path).await {
-        Ok(progress) => Ok(Json(progress)),
-        Err(e) => {
-            error!("Error loading progress: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
+async fn start_job(
+    exercise: ExerciseRef,
+    CurrentUser(user_id): CurrentUser,
+    State(state): State<AppState>,
+    Json(request): Json<StartJobRequest>,
+) -> Result<Json<ApiResponse<JobStarted>>, StatusCode> {
+    // Output goes to the connection, so it must belong to the same learner
+    match state.connections.read().await.get(&request.connection_id) {
+        Some(owner) if *owner == user_id => {}
+        Some(_) => return Err(StatusCode::FORBIDDEN),
+        None => return Err(StatusCode::BAD_REQUEST),
     }
+    
+    let exercise_path = user_exercise_dir(&state, &user_id, &exercise).await?;
+    let config = exercise.metadata.testing.clone();
+    
+    let job_id = Uuid::new_v4();
+    let (cancel_tx, cancel_rx) = watch::channel(false);
+    state.jobs.write().await.insert(job_id, JobHandle {
+        connection_id: request.connection_id,
+        cancel: cancel_tx,
+    });
+    
+    let job = JobContext {
+        job_id,
+        connection_id: request.connection_id,
+        broadcast_tx: state.broadcast_tx.clone(),
+        cancel: cancel_rx,
+    };
+    let jobs = state.jobs.clone();
+    let state = state.clone();
+    
+    tokio::spawn(async move {
+        job.send("started", serde_json::json!({
+            "kind": request.kind,
+            "exercise_id": exercise.id,
+            "path": exercise.path_str()
+        }));
+        
+        let result = match request.kind {
+            JobKind::Run => run_exercise_binary(&exercise_path, &config, Some(&job)).await,
+            JobKind::Test => run_exercise_tests(&exercise_path, &config, Some(&job)).await,
+            JobKind::Check => run_exercise_check(&exercise_path, &config, Some(&job)).await,
+        };
+        jobs.write().await.remove(&job_id);
+        
+        match result {
+            Ok(result) => {
+                if matches!(request.kind, JobKind::Test) && !job.is_cancelled() {
+                    record_test_run(&state, &user_id, &exercise.id, result.success).await;
+                }
+                job.send("finished", serde_json::json!({
+                    "cancelled": job.is_cancelled(),
+                    "result": with_contextual_hints(&state.framework, &exercise, result).await
+                }));
+            }
+            Err(e) => {
+                error!("Job {} for {} failed: {}", job_id, exercise.id, e);
+                job.send("failed", serde_json::json!({ "error": e.to_string() }));
+            }
+        }
+    });
+    
+    Ok(Json(ApiResponse::success(JobStarted { job_id })))
 }
 
-async fn complete_exercise(
+async fn list_exercise_files(
+    exercise: ExerciseRef,
+    CurrentUser(user_id): CurrentUser,
     State(state): State<AppState>,
-    Json(request): Json<CompleteExerciseRequest>,
-) -> Result<Json<ApiResponse<ProgressData>>, StatusCode> {
-    match update_exercise_completion(&state.progress_path, &state.exercises_path, &request).await {
-        Ok(progress) => Ok(Json(ApiResponse::success_with_extra(
-            progress,
-            serde_json::json!({"message": "Exercise completed successfully"})
-        ))),
-        Err(e) => {
-            error!("Error updating progress: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
-    }
+) -> Result<Json<ApiResponse<ExerciseFileList>>, StatusCode> {
+    let exercise_path = user_exercise_dir(&state, &user_id, &exercise).await?;
+    let files = ExerciseFiles::new(&exercise_path, &exercise.metadata)
+        .list()
+        .map_err(|e| file_error_status(&exercise, e))?;
+    Ok(Json(ApiResponse::success(ExerciseFileList { files })))
 }
 
-async fn track_hint_usage(
+async fn read_exercise_file(
+    exercise: ExerciseRef,
+    AxumPath(FilePathParam { path }): AxumPath<FilePathParam>,
+    CurrentUser(user_id): CurrentUser,
     State(state): State<AppState>,
-    Json(request): Json<HintRequest>,
-) -> Result<Json<ApiResponse<ProgressData>>, StatusCode> {
-    match update_hint_usage(&state.progress_path, &state.exercises_path, &request).await {
-        Ok(progress) => Ok(Json(ApiResponse::success(progress))),
-        Err(e) => {
-            error!("Error tracking hint usage: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
-    }
+) -> Result<Json<ExerciseFileContent>, StatusCode> {
+    let exercise_path = user_exercise_dir(&state, &user_id, &exercise).await?;
+    let content = ExerciseFiles::new(&exercise_path, &exercise.metadata)
+        .read(&path)
+        .map_err(|e| file_error_status(&exercise, e))?;
+    Ok(Json(ExerciseFileContent { path, content }))
 }
 
-async fn track_exercise_view(
+async fn write_exercise_file(
+    exercise: ExerciseRef,
+    AxumPath(FilePathParam { path }): AxumPath<FilePathParam>,
+    CurrentUser(user_id): CurrentUser,
     State(state): State<AppState>,
-    Json(request): Json<ViewRequest>,
-) -> Result<Json<ApiResponse<ProgressData>>, StatusCode> {
-    match update_exercise_view(&state.progress_path, &state.exercises_path, &request).await {
-        Ok(progress) => Ok(Json(ApiResponse::success(progress))),
+    Json(request): Json<WriteFileRequest>,
+) -> Result<Json<ApiResponse<()>>, StatusCode> {
+    let files = writable_exercise_files(&state, &user_id, &exercise, &path).await?;
+    files
+        .write(&path, &request.content)
+        .map_err(|e| file_error_status(&exercise, e))?;
+    record_snapshot(&state, &user_id, &exercise, &files, SnapshotTrigger::Save).await;
+    broadcast_file_update(&state, &user_id, &exercise, "file_updated", &path).await;
+    Ok(Json(ApiResponse::success(())))
+}
+
+async fn create_exercise_file(
+    exercise: ExerciseRef,
+    AxumPath(FilePathParam { path }): AxumPath<FilePathParam>,
+    CurrentUser(user_id): CurrentUser,
+    State(state): State<AppState>,
+    Json(request): Json<WriteFileRequest>,
+) -> Result<(StatusCode, Json<ApiResponse<()>>), StatusCode> {
+    let files = writable_exercise_files(&state, &user_id, &exercise, &path).await?;
+    files
+        .create(&path, &request.content)
+        .map_err(|e| file_error_status(&exercise, e))?;
+    record_snapshot(&state, &user_id, &exercise, &files, SnapshotTrigger::Save).await;
+    broadcast_file_update(&state, &user_id, &exercise, "file_created", &path).await;
+    Ok((StatusCode::CREATED, Json(ApiResponse::success(()))))
+}
+
+async fn rename_exercise_file(
+    exercise: ExerciseRef,
+    AxumPath(FilePathParam { path }): AxumPath<FilePathParam>,
+    CurrentUser(user_id): CurrentUser,
+    State(state): State<AppState>,
+    Json(request): Json<RenameFileRequest>,
+) -> Result<Json<ApiResponse<()>>, StatusCode> {
+    let files = writable_exercise_files(&state, &user_id, &exercise, &path).await?;
+    files
+        .rename(&path, &request.to)
+        .map_err(|e| file_error_status(&exercise, e))?;
+    record_snapshot(&state, &user_id, &exercise, &files, SnapshotTrigger::Save).await;
+    broadcast_to_user(&state, &user_id, "file_renamed", serde_json::json!({
+        "exercise_id": exercise.id,
+        "path": exercise.path_str(),
+        "file": path,
+        "to": request.to
+    })).await;
+    Ok(Json(ApiResponse::success(())))
+}
+
+async fn delete_exercise_file(
+    exercise: ExerciseRef,
+    AxumPath(FilePathParam { path }): AxumPath<FilePathParam>,
+    CurrentUser(user_id): CurrentUser,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<()>>, StatusCode> {
+    let files = writable_exercise_files(&state, &user_id, &exercise, &path).await?;
+    files
+        .delete(&path)
+        .map_err(|e| file_error_status(&exercise, e))?;
+    record_snapshot(&state, &user_id, &exercise, &files, SnapshotTrigger::Save).await;
+    broadcast_file_update(&state, &user_id, &exercise, "file_deleted", &path).await;
+    Ok(Json(ApiResponse::success(())))
+}
+
+async fn list_snapshots(
+    exercise: ExerciseRef,
+    CurrentUser(user_id): CurrentUser,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<SnapshotList>>, StatusCode> {
+    let framework = state.framework.read().await;
+    match framework.snapshots().list(&user_id, &exercise.path) {
+        Ok(snapshots) => Ok(Json(ApiResponse::success(SnapshotList { snapshots }))),
         Err(e) => {
-            error!("Error tracking exercise view: {}", e);
+            error!("Error listing snapshots of {} for {}: {}", exercise.id, user_id, e);
             Err(StatusCode::INTERNAL_SERVER_ERROR)
         }
     }
 }
 
-async fn get_book_chapter(
-    AxumPath(chapter): AxumPath<String>,
-) -> Result<Json<BookResponse>, StatusCode> {
-    let book_url = format!("https://doc.rust-lang.org/book/ch{}.html", chapter);
-    Ok(Json(BookResponse {
-        url: book_url,
-        chapter,
-    }))
+async fn get_snapshot(
+    exercise: ExerciseRef,
+    AxumPath(SnapshotParam { snapshot }): AxumPath<SnapshotParam>,
+    CurrentUser(user_id): CurrentUser,
+    State(state): State<AppState>,
+) -> Result<Json<Snapshot>, StatusCode> {
+    find_snapshot(&state, &user_id, &exercise, &snapshot).await.map(Json)
 }
 
-// Static file handlers
-#[cfg(feature = "embed-assets")]
-async fn serve_static_files(uri: axum::http::Uri) -> Result<Response, StatusCode> {
-    let path = uri.path().trim_start_matches('/');
+async fn diff_snapshots(
+    exercise: ExerciseRef,
+    Query(query): Query<DiffQuery>,
+    CurrentUser(user_id): CurrentUser,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<SnapshotDiff>>, StatusCode> {
+    let from = query.from.unwrap_or_else(|| STARTER_ID.to_string());
+    let to = query.to.unwrap_or_else(|| "current".to_string());
+    let old = snapshot_files(&state, &user_id, &exercise, &from).await?;
+    let new = snapshot_files(&state, &user_id, &exercise, &to).await?;
+    
+    Ok(Json(ApiResponse::success(SnapshotDiff {
+        files: snapshots::diff(&old, &new),
+        from,
+        to,
+    })))
+}
+
+async fn restore_snapshot(
+    exercise: ExerciseRef,
+    AxumPath(SnapshotParam { snapshot }): AxumPath<SnapshotParam>,
+    CurrentUser(user_id): CurrentUser,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<()>>, StatusCode> {
+    restore_files(&state, &user_id, &exercise, &snapshot).await
+}
+
+async fn reset_exercise(
+    exercise: ExerciseRef,
+    CurrentUser(user_id): CurrentUser,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<()>>, StatusCode> {
+    restore_files(&state, &user_id, &exercise, STARTER_ID).await
+}
+
+async fn cancel_job(
+    AxumPath(job_id): AxumPath<JobId>,
+    CurrentUser(user_id): CurrentUser,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<()>>, StatusCode> {
+    let owner = match state.jobs.read().await.get(&job_id) {
+        Some(handle) => state.connections.read().await.get(&handle.connection_id).cloned(),
+        None => return Err(StatusCode::NOT_FOUND),
+    };
+    if owner.as_deref() != Some(user_id.as_str()) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    
+    match state.jobs.read().await.get(&job_id) {
+        Some(handle) => {
+            let _ = handle.cancel.send(true);
+            Ok(Json(ApiResponse::success(())))
+        }
+        None => Err(StatusCode::NOT_FOUND),
+    }
+}
+
+async fn register(
+    State(state): State<AppState>,
+    Json(request): Json<CredentialsRequest>,
+) -> Response {
+    let result = state.accounts.write().await.register(&request.username, &request.password);
+    session_response(&state, &request.username, result)
+}
+
+async fn login(
+    State(state): State<AppState>,
+    Json(request): Json<CredentialsRequest>,
+) -> Response {
+    let result = state.accounts.write().await.login(&request.username, &request.password);
+    session_response(&state, &request.username, result)
+}
+
+async fn logout(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+) -> Result<Json<ApiResponse<()>>, StatusCode> {
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    
+    match state.accounts.write().await.logout(token) {
+        Ok(()) => Ok(Json(ApiResponse::success(()))),
+        Err(e) => {
+            error!("Error ending session: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn current_session(
+    CurrentUser(user_id): CurrentUser,
+    State(state): State<AppState>,
+) -> Json<SessionResponse> {
+    Json(SessionResponse {
+        username: user_id,
+        token: None,
+        require_auth: state.require_auth,
+    })
+}
+
+fn session_response(
+    state: &AppState,
+    username: &str,
+    result: Result<String, AccountError>,
+) -> Response {
+    let status = match &result {
+        Ok(_) => StatusCode::OK,
+        Err(AccountError::InvalidUsername(_) | AccountError::WeakPassword) => StatusCode::BAD_REQUEST,
+        Err(AccountError::UsernameTaken) => StatusCode::CONFLICT,
+        Err(AccountError::InvalidCredentials) => StatusCode::UNAUTHORIZED,
+        Err(AccountError::Storage(e)) => {
+            error!("Account storage error: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    };
+    
+    match result {
+        Ok(token) => Json(ApiResponse::success(SessionResponse {
+            username: username.to_string(),
+            token: Some(token),
+            require_auth: state.require_auth,
+        }))
+        .into_response(),
+        Err(e) => (status, Json(ApiResponse::<()>::error(e.to_string()))).into_response(),
+    }
+}
+
+async fn get_progress(
+    CurrentUser(user_id): CurrentUser,
+    State(state): State<AppState>,
+) -> Result<Json<UserProgress>, StatusCode> {
+    match state.framework.read().await.get_progress(&user_id) {
+        Ok(progress) => Ok(Json(progress)),
+        Err(e) => {
+            error!("Error loading progress: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn get_progress_history(
+    CurrentUser(user_id): CurrentUser,
+    State(state): State<AppState>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<Vec<ExerciseCompletion>>, StatusCode> {
+    match state.framework.read().await.progress_history(&user_id, &query) {
+        Ok(history) => Ok(Json(history)),
+        Err(e) => {
+            error!("Error loading progress history: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn get_recommendations(
+    CurrentUser(user_id): CurrentUser,
+    State(state): State<AppState>,
+    Query(query): Query<RecommendationsQuery>,
+) -> Result<Json<Vec<Recommendation>>, StatusCode> {
+    let limit = query.limit.unwrap_or(5);
+    match state.framework.read().await.recommendations(&user_id, limit) {
+        Ok(recommendations) => Ok(Json(recommendations)),
+        Err(e) => {
+            error!("Error computing recommendations: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn get_review_queue(
+    CurrentUser(user_id): CurrentUser,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<ReviewItem>>, StatusCode> {
+    match state.framework.read().await.review_queue(&user_id) {
+        Ok(queue) => Ok(Json(queue)),
+        Err(e) => {
+            error!("Error building review queue: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn complete_exercise(
+    CurrentUser(user_id): CurrentUser,
+    State(state): State<AppState>,
+    Json(request): Json<CompleteExerciseRequest>,
+) -> Result<Json<ApiResponse<UserProgress>>, StatusCode> {
+    let result = {
+        let framework = state.framework.read().await;
+        framework
+            .complete_exercise(&user_id, &request.exercise_id, request.time_taken_minutes.unwrap_or(0))
+            .and_then(|awarded| Ok((framework.get_progress(&user_id)?, awarded)))
+    };
+
+    match result {
+        Ok((progress, awarded)) => {
+            info!(
+                "Exercise completed: {} in {} minutes ({}/{} total)",
+                request.exercise_id,
+                request.time_taken_minutes.unwrap_or(0),
+                progress.exercises_completed,
+                progress.total_exercises
+            );
+            announce_achievements(&state, &user_id, &awarded).await;
+            Ok(Json(ApiResponse::success_with_extra(
+                progress,
+                serde_json::json!({
+                    "message": "Exercise completed successfully",
+                    "achievements": awarded,
+                })
+            )))
+        }
+        Err(e) => {
+            error!("Error updating progress: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn track_exercise_view(
+    CurrentUser(user_id): CurrentUser,
+    State(state): State<AppState>,
+    Json(request): Json<ViewRequest>,
+) -> Result<Json<ApiResponse<UserProgress>>, StatusCode> {
+    let result = {
+        let framework = state.framework.read().await;
+        framework
+            .record_exercise_view(&user_id, &request.exercise_id)
+            .and_then(|awarded| Ok((framework.get_progress(&user_id)?, awarded)))
+    };
+
+    match result {
+        Ok((progress, awarded)) => {
+            info!("Exercise viewed: {}", request.exercise_id);
+            announce_achievements(&state, &user_id, &awarded).await;
+            Ok(Json(ApiResponse::success(progress)))
+        }
+        Err(e) => {
+            error!("Error tracking exercise view: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Activity per local day with the streaks it makes up, for a heatmap
+async fn get_activity_calendar(
+    CurrentUser(user_id): CurrentUser,
+    State(state): State<AppState>,
+    Query(query): Query<ActivityQuery>,
+) -> Result<Json<ActivityCalendarResponse>, StatusCode> {
+    let progress = state.framework.read().await.get_progress(&user_id).map_err(|e| {
+        error!("Error loading activity calendar: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let timezone = progress.preferences.timezone.clone();
+    let today = activity::local_date(chrono::Utc::now(), &timezone);
+    let to = query.to.unwrap_or(today);
+    let from = query.from.unwrap_or(to - chrono::Duration::days(7 * 52 - 1));
+    if from > to || (to - from).num_days() > MAX_CALENDAR_DAYS {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let days = progress.activity.range(from, to);
+    Ok(Json(ActivityCalendarResponse {
+        timezone,
+        today,
+        current_streak: progress.current_streak,
+        longest_streak: progress.longest_streak,
+        max_count: days.iter().map(|day| day.count).max().unwrap_or(0),
+        days,
+    }))
+}
+
+async fn set_timezone(
+    CurrentUser(user_id): CurrentUser,
+    State(state): State<AppState>,
+    Json(request): Json<TimezoneRequest>,
+) -> Result<Json<ApiResponse<UserProgress>>, StatusCode> {
+    if activity::parse_timezone(&request.timezone).is_none() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let result = {
+        let framework = state.framework.read().await;
+        framework
+            .set_timezone(&user_id, &request.timezone)
+            .and_then(|_| framework.get_progress(&user_id))
+    };
+    match result {
+        Ok(progress) => Ok(Json(ApiResponse::success(progress))),
+        Err(e) => {
+            error!("Error setting timezone: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn get_book_chapter(
+    AxumPath(chapter): AxumPath<String>,
+    State(state): State<AppState>,
+) -> Result<Json<BookResponse>, StatusCode> {
+    let local = state.book.as_ref().and_then(|book| book.page(&chapter).cloned());
+    let is_local = local.is_some();
+    let page = match local.or_else(|| book::section(&chapter)) {
+        Some(page) => page,
+        None => return Err(StatusCode::NOT_FOUND),
+    };
+    
+    Ok(Json(BookResponse {
+        url: if is_local {
+            format!("/book/{}", page.file_name())
+        } else {
+            page.online_url()
+        },
+        online_url: page.online_url(),
+        local: is_local,
+        chapter: page.id,
+        title: page.title,
+    }))
+}
+
+async fn get_exercise_reading(
+    exercise: ExerciseRef,
+    State(state): State<AppState>,
+) -> Json<ApiResponse<BookReading>> {
+    let sections = exercise
+        .metadata
+        .rust_book_refs
+        .specific_sections
+        .iter()
+        .map(|section| {
+            let excerpt = state.book.as_ref().and_then(|book| {
+                book.excerpt(&section.chapter, Some(&section.title))
+                    .map_err(|e| warn!("Failed to read book section {}: {}", section.chapter, e))
+                    .ok()
+                    .flatten()
+            });
+            let url = match &excerpt {
+                Some(excerpt) => format!("/book/{}", excerpt.page.file_name()),
+                None => section.url.clone(),
+            };
+            BookReadingSection {
+                chapter: section.chapter.clone(),
+                title: section.title.clone(),
+                relevance: section.relevance,
+                url,
+                excerpt: excerpt.map(|excerpt| excerpt.html),
+            }
+        })
+        .collect();
+    
+    Json(ApiResponse::success(BookReading { sections }))
+}
+
+/// Reveal the next hint level, as far as the exercise's hint settings and
+/// the learner's preference and failed test runs allow
+async fn reveal_next_hint(
+    exercise: ExerciseRef,
+    CurrentUser(user_id): CurrentUser,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<HintReveal>>, StatusCode> {
+    let reveal = state
+        .framework
+        .write()
+        .await
+        .reveal_next_hint(&user_id, &exercise.id)
+        .map_err(|e| {
+            error!("Error revealing hint for {}: {}", exercise.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    
+    match reveal.status {
+        HintStatus::Disabled => Err(StatusCode::FORBIDDEN),
+        HintStatus::Revealed => {
+            info!("Hint used: {}, level {}", exercise.id, reveal.revealed_level);
+            Ok(Json(ApiResponse::success(reveal)))
+        }
+        HintStatus::Locked | HintStatus::Exhausted => Ok(Json(ApiResponse::success(reveal))),
+    }
+}
+
+/// "Was this helpful?" for a hint level the user has revealed
+async fn record_hint_feedback(
+    exercise: ExerciseRef,
+    AxumPath(HintLevelParam { level }): AxumPath<HintLevelParam>,
+    CurrentUser(user_id): CurrentUser,
+    State(state): State<AppState>,
+    Json(request): Json<HintFeedbackRequest>,
+) -> Result<Json<ApiResponse<()>>, StatusCode> {
+    let recorded = state
+        .framework
+        .write()
+        .await
+        .record_hint_feedback(&user_id, &exercise.id, level, request.helpful)
+        .map_err(|e| {
+            error!("Error recording hint feedback for {}: {}", exercise.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if !recorded {
+        // Only revealed levels can be rated
+        return Err(StatusCode::NOT_FOUND);
+    }
+    Ok(Json(ApiResponse::success(())))
+}
+
+/// Files of the locally installed book, for offline reading
+async fn serve_book_file(
+    AxumPath(path): AxumPath<String>,
+    State(state): State<AppState>,
+) -> Result<Response, StatusCode> {
+    let book = state.book.as_ref().ok_or(StatusCode::NOT_FOUND)?;
+    let file_path = book.file(&path).ok_or(StatusCode::NOT_FOUND)?;
+    
+    match fs::read(&file_path).await {
+        Ok(content) => {
+            let mime = mime_guess::from_path(&file_path).first_or_octet_stream();
+            Ok((
+                [(header::CONTENT_TYPE, HeaderValue::from_str(mime.as_ref()).unwrap())],
+                content,
+            ).into_response())
+        }
+        Err(e) => {
+            error!("Error reading book file {}: {}", path, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// Static file handlers
+#[cfg(feature = "embed-assets")]
+async fn serve_static_files(uri: axum::http::Uri) -> Result<Response, StatusCode> {
+    let path = uri.path().trim_start_matches('/');
     
     // Try to serve the requested file
     if let Some(content) = Assets::get(path) {
@@ -1096,375 +2004,581 @@ async fn serve_monaco_files(
 }
 
 // Helper functions
-async fn scan_exercises(exercises_path: &std::path::Path) -> anyhow::Result<Vec<ExerciseWithPath>> {
-    let mut exercises = Vec::new();
-    
-    if !exercises_path.exists() {
-        return Ok(exercises);
-    }
-    
-    for chapter_entry in WalkDir::new(exercises_path).max_depth(1) {
-        let chapter_entry = chapter_entry?;
-        if !chapter_entry.file_type().is_dir() {
-            continue;
-        }
-        
-        let chapter_name = chapter_entry.file_name().to_string_lossy();
-        if !chapter_name.starts_with("ch") {
-            continue;
-        }
-        
-        for exercise_entry in WalkDir::new(chapter_entry.path()).max_depth(1) {
-            let exercise_entry = exercise_entry?;
-            if !exercise_entry.file_type().is_dir() {
-                continue;
-            }
-            
-            let exercise_name = exercise_entry.file_name().to_string_lossy();
-            if !exercise_name.starts_with("ex") {
-                continue;
-            }
-            
-            let metadata_path = exercise_entry.path().join("metadata.json");
-            if metadata_path.exists() {
-                match fs::read_to_string(&metadata_path).await {
-                    Ok(content) => {
-                        match serde_json::from_str::<ExerciseMetadata>(&content) {
-                            Ok(metadata) => {
-                                exercises.push(ExerciseWithPath {
-                                    metadata,
-                                    path: format!("{}/{}", chapter_name, exercise_name),
-                                });
-                            }
-                            Err(e) => {
-                                warn!("Error parsing metadata for {}/{}: {}", chapter_name, exercise_name, e);
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        warn!("Error reading metadata for {}/{}: {}", chapter_name, exercise_name, e);
-                    }
-                }
-            }
-        }
-    }
-    
-    // Sort by chapter and exercise number
-    exercises.sort_by(|a, b| {
-        match a.metadata.chapter.cmp(&b.metadata.chapter) {
-            std::cmp::Ordering::Equal => a.metadata.exercise_number.cmp(&b.metadata.exercise_number),
-            other => other,
-        }
-    });
-    
-    Ok(exercises)
+async fn list_exercises(state: &AppState) -> anyhow::Result<Vec<ExerciseWithPath>> {
+    let exercises = state.framework.read().await.list_exercises_with_paths()?;
+
+    Ok(exercises
+        .into_iter()
+        .map(|(metadata, path)| ExerciseWithPath {
+            metadata,
+            path: path.to_string_lossy().replace('\\', "/"),
+        })
+        .collect())
 }
 
 async fn load_exercise_details(
-    exercise_path: &std::path::Path,
-    path: &str,
+    state: &AppState,
+    user_id: &str,
+    exercise: &ExerciseRef,
 ) -> anyhow::Result<ExerciseDetails> {
-    // Load metadata
-    let metadata_path = exercise_path.join("metadata.json");
-    let metadata_content = fs::read_to_string(&metadata_path).await?;
-    let metadata: ExerciseMetadata = serde_json::from_str(&metadata_content)?;
-    
+    let exercise_data = state
+        .framework
+        .read()
+        .await
+        .load_user_exercise_at(user_id, &exercise.path)?;
+
     // Load main source file
-    let main_path = exercise_path.join("src").join("main.rs");
-    let main_content = fs::read_to_string(&main_path).await?;
+    let files = ExerciseFiles::new(&exercise_data.path, &exercise_data.metadata);
+    let main_file = files.main_file();
+    let main_content = files.read(main_file).ok().unwrap_or_else(|| {
+        exercise_data
+            .get_primary_source()
+            .map(|source| source.content.clone())
+            .unwrap_or_default()
+    });
     
     // Load README
-    let readme_path = exercise_path.join("README.md");
+    let readme_path = exercise_data.path.join("README.md");
     let readme = fs::read_to_string(&readme_path).await?;
     
-    // Load hints if available
-    let hints_path = exercise_path.join("hints.md");
-    let hints = if hints_path.exists() {
-        fs::read_to_string(&hints_path).await.unwrap_or_default()
-    } else {
-        String::new()
-    };
+    let hints = state
+        .framework
+        .write()
+        .await
+        .revealed_hints(user_id, &exercise.id)?;
     
     Ok(ExerciseDetails {
-        metadata,
+        metadata: exercise_data.metadata,
         main_content,
+        main_file: main_file.to_string(),
+        files: files.list()?,
         readme,
         hints,
-        path: path.to_string(),
+        path: exercise.path_str(),
     })
 }
 
-async fn load_exercise_title(exercise_path: &std::path::Path) -> anyhow::Result<String> {
-    let metadata_path = exercise_path.join("metadata.json");
-    let metadata_content = fs::read_to_string(&metadata_path).await?;
-    let metadata: ExerciseMetadata = serde_json::from_str(&metadata_content)?;
-    Ok(metadata.title)
+/// The learner's workspace copy of the exercise, made from the starter on
+/// first use; cargo runs there
+async fn user_exercise_dir(
+    state: &AppState,
+    user_id: &str,
+    exercise: &ExerciseRef,
+) -> Result<PathBuf, StatusCode> {
+    let framework = state.framework.read().await;
+    framework.user_exercise_dir(user_id, &exercise.path).map_err(|e| {
+        warn!("Failed to resolve workspace for {} on {}: {}", user_id, exercise.id, e);
+        StatusCode::NOT_FOUND
+    })
+}
+
+/// Count a finished test run towards the learner's concept mastery
+async fn record_test_run(state: &AppState, user_id: &str, exercise_id: &str, passed: bool) {
+    let result = state.framework.read().await.record_test_run(user_id, exercise_id, passed);
+    match result {
+        Ok(awarded) => announce_achievements(state, user_id, &awarded).await,
+        Err(e) => warn!("Failed to record test run for {} on {}: {}", user_id, exercise_id, e),
+    }
+}
+
+/// File access for a change to `file`, creating the learner's workspace copy
+/// only once the change is known to be allowed
+async fn writable_exercise_files(
+    state: &AppState,
+    user_id: &str,
+    exercise: &ExerciseRef,
+    file: &str,
+) -> Result<ExerciseFiles, StatusCode> {
+    if let Err(e) = ExerciseFiles::new(Path::new(""), &exercise.metadata).check(file) {
+        warn!("Rejected change in {} by {}: {}", exercise.id, user_id, e);
+        return Err(file_error_status(exercise, e));
+    }
+
+    prepare_exercise_write(state, user_id, exercise).await
+}
+
+/// The learner's writable files, in their workspace copy
+async fn prepare_exercise_write(
+    state: &AppState,
+    user_id: &str,
+    exercise: &ExerciseRef,
+) -> Result<ExerciseFiles, StatusCode> {
+    let path = user_exercise_dir(state, user_id, exercise).await?;
+    Ok(ExerciseFiles::new(path, &exercise.metadata))
+}
+
+/// Add the learner's current files to their snapshot history and, for a
+/// save, count it as activity
+async fn record_snapshot(
+    state: &AppState,
+    user_id: &str,
+    exercise: &ExerciseRef,
+    files: &ExerciseFiles,
+    trigger: SnapshotTrigger,
+) {
+    let framework = state.framework.read().await;
+    if let Err(e) = framework.snapshots().record(user_id, &exercise.path, files, trigger) {
+        warn!("Failed to record snapshot for {} on {}: {}", user_id, exercise.id, e);
+    }
+    // Saving counts towards the learner's streak
+    if trigger == SnapshotTrigger::Save {
+        let result = framework.record_code_save(user_id);
+        drop(framework);
+        match result {
+            Ok(awarded) => announce_achievements(state, user_id, &awarded).await,
+            Err(e) => warn!("Failed to record save for {} on {}: {}", user_id, exercise.id, e),
+        }
+    }
+}
+
+/// Files of a snapshot, the starter, or `current` for what is on disk now
+async fn snapshot_files(
+    state: &AppState,
+    user_id: &str,
+    exercise: &ExerciseRef,
+    snapshot_id: &str,
+) -> Result<BTreeMap<String, String>, StatusCode> {
+    if snapshot_id == "current" {
+        let exercise_path = user_exercise_dir(state, user_id, exercise).await?;
+        return ExerciseFiles::new(exercise_path, &exercise.metadata)
+            .contents()
+            .map_err(|e| file_error_status(exercise, e));
+    }
+    find_snapshot(state, user_id, exercise, snapshot_id)
+        .await
+        .map(|snapshot| snapshot.files)
+}
+
+async fn find_snapshot(
+    state: &AppState,
+    user_id: &str,
+    exercise: &ExerciseRef,
+    snapshot_id: &str,
+) -> Result<Snapshot, StatusCode> {
+    let framework = state.framework.read().await;
+    match framework.snapshots().get(user_id, &exercise.path, snapshot_id) {
+        Ok(Some(snapshot)) => Ok(snapshot),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Error reading snapshot {} of {}: {}", snapshot_id, exercise.id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Replace the learner's files with a snapshot or the starter, keeping what
+/// they had as a snapshot so the restore can be undone
+async fn restore_files(
+    state: &AppState,
+    user_id: &str,
+    exercise: &ExerciseRef,
+    snapshot_id: &str,
+) -> Result<Json<ApiResponse<()>>, StatusCode> {
+    let files = prepare_exercise_write(state, user_id, exercise).await?;
+    let snapshot = find_snapshot(state, user_id, exercise, snapshot_id).await?;
+    
+    record_snapshot(state, user_id, exercise, &files, SnapshotTrigger::Restore).await;
+    files
+        .replace_all(&snapshot.files)
+        .map_err(|e| file_error_status(exercise, e))?;
+    
+    broadcast_to_user(state, user_id, "files_restored", serde_json::json!({
+        "exercise": exercise.metadata.title,
+        "exercise_id": exercise.id,
+        "path": exercise.path_str(),
+        "snapshot": snapshot.id
+    })).await;
+    Ok(Json(ApiResponse::success(())))
+}
+
+fn file_error_status(exercise: &ExerciseRef, error: FileError) -> StatusCode {
+    match error {
+        FileError::InvalidPath(_) => StatusCode::BAD_REQUEST,
+        FileError::NotEditable(_) | FileError::CrateRoot(_) => StatusCode::FORBIDDEN,
+        FileError::NotFound(_) => StatusCode::NOT_FOUND,
+        FileError::AlreadyExists(_) => StatusCode::CONFLICT,
+        FileError::Io(e) => {
+            error!("File error in {}: {}", exercise.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+async fn broadcast_file_update(
+    state: &AppState,
+    user_id: &str,
+    exercise: &ExerciseRef,
+    msg_type: &str,
+    file: &str,
+) {
+    broadcast_to_user(state, user_id, msg_type, serde_json::json!({
+        "exercise": exercise.metadata.title,
+        "exercise_id": exercise.id,
+        "path": exercise.path_str(),
+        "file": file
+    })).await;
+}
+
+/// Send a message to every open connection of one learner
+/// Tell the learner's connections about achievements they just earned
+async fn announce_achievements(state: &AppState, user_id: &str, awarded: &[Achievement]) {
+    for achievement in awarded {
+        info!("🏆 {} earned achievement {}", user_id, achievement.id);
+        broadcast_to_user(
+            state,
+            user_id,
+            "achievement_unlocked",
+            serde_json::json!({ "achievement": achievement }),
+        )
+        .await;
+    }
+}
+
+async fn broadcast_to_user(state: &AppState, user_id: &str, msg_type: &str, data: serde_json::Value) {
+    let connections = state.connections.read().await;
+    for (connection_id, owner) in connections.iter() {
+        if owner == user_id {
+            let _ = state.broadcast_tx.send(BroadcastMessage {
+                msg_type: msg_type.to_string(),
+                data: data.clone(),
+                target: Some(*connection_id),
+            });
+        }
+    }
+}
+
+fn build_limits() -> SandboxLimits {
+    SandboxLimits::for_build(sandbox::DEFAULT_BUILD_TIMEOUT)
 }
 
 async fn run_cargo_command(
     command: &str,
     cwd: &std::path::Path,
     args: Vec<&str>,
+    limits: SandboxLimits,
+    job: Option<&JobContext>,
 ) -> anyhow::Result<CargoResult> {
     let mut cmd = Command::new("cargo");
-    cmd.arg(command)
-        .args(&args)
-        .current_dir(cwd)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped());
-    
-    let output = timeout(Duration::from_secs(60), cmd.output()).await??;
+    cmd.arg(command).args(&args).current_dir(cwd);
+    sandbox::offline(&mut cmd);
+
+    run_sandboxed(cmd, limits, job).await
+}
+
+/// Refuse dependencies the exercise doesn't allow and fetch the others, so
+/// builds can run offline. Returns a failed result if the exercise can't be
+/// built.
+async fn prepare_build(
+    cwd: &std::path::Path,
+    config: &TestConfig,
+    job: Option<&JobContext>,
+) -> anyhow::Result<Option<CargoResult>> {
+    // Cargo reports a broken manifest itself
+    let crates = sandbox::declared_dependencies(cwd).unwrap_or_default();
+    if let Some(violation) = sandbox::dependency_violation(config, &crates) {
+        let stderr = format!("{}\n", violation);
+        return Ok(Some(CargoResult {
+            success: false,
+            code: None,
+            stdout: String::new(),
+            output: stderr.clone(),
+            stderr,
+            diagnostics: None,
+            exercise_id: None,
+            hints: Vec::new(),
+        }));
+    }
+    if crates.is_empty() {
+        return Ok(None);
+    }
+
+    let mut cmd = Command::new("cargo");
+    cmd.arg("fetch").current_dir(cwd);
+    let fetch = run_sandboxed(cmd, SandboxLimits::for_fetch(sandbox::DEFAULT_BUILD_TIMEOUT), job).await?;
+    Ok((!fetch.success).then_some(fetch))
+}
+
+async fn run_sandboxed(
+    cmd: Command,
+    limits: SandboxLimits,
+    job: Option<&JobContext>,
+) -> anyhow::Result<CargoResult> {
+    let sandbox = Sandbox::new(limits);
+    let output = match job {
+        Some(job) => job.run(&sandbox, cmd).await?,
+        None => sandbox.run_async(cmd).await?,
+    };
     
     let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    let mut stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    if let Some(message) = output.limit_message(sandbox.limits()) {
+        stderr.push_str(&format!("\n{}\n", message));
+    } else if output.cancelled {
+        stderr.push_str("\nCancelled\n");
+    }
     let combined_output = format!("{}{}", stdout, stderr);
     
     Ok(CargoResult {
-        success: output.status.success(),
+        success: output.success(),
         code: output.status.code(),
         stdout,
         stderr,
         output: combined_output,
+        diagnostics: None,
+        exercise_id: None,
+        hints: Vec::new(),
     })
 }
 
-async fn count_total_exercises(exercises_path: &std::path::Path) -> anyhow::Result<u32> {
-    let mut count = 0;
-    
-    if !exercises_path.exists() {
-        return Ok(50); // Fallback
+/// Build the exercise, then run its binary directly under the exercise's limits
+async fn run_exercise_binary(
+    cwd: &std::path::Path,
+    config: &TestConfig,
+    job: Option<&JobContext>,
+) -> anyhow::Result<CargoResult> {
+    if let Some(failed) = prepare_build(cwd, config, job).await? {
+        return Ok(failed);
     }
-    
-    for chapter_entry in WalkDir::new(exercises_path).max_depth(1) {
-        let chapter_entry = chapter_entry?;
-        if !chapter_entry.file_type().is_dir() {
-            continue;
-        }
-        
-        let chapter_name = chapter_entry.file_name().to_string_lossy();
-        if !chapter_name.starts_with("ch") {
-            continue;
+    let build = run_cargo_command("build", cwd, vec!["--message-format=json"], build_limits(), job).await?;
+    let binary = diagnostics::parse_binary_artifacts(&build.stdout).into_iter().next();
+    let build = with_diagnostics(build);
+    if !build.success {
+        return Ok(build);
+    }
+
+    let binary = binary.ok_or_else(|| anyhow::anyhow!("cargo build produced no binary"))?;
+
+    let mut cmd = Command::new(binary);
+    cmd.current_dir(cwd);
+    let mut result = run_sandboxed(cmd, SandboxLimits::for_program(config), job).await?;
+
+    // Keep cargo's build progress ahead of the program's own stderr, as `cargo run` would
+    result.stderr = format!("{}{}", build.stderr, result.stderr);
+    result.output = format!("{}{}", result.stdout, result.stderr);
+    Ok(result)
+}
+
+/// Build the test harnesses, then run each directly under the exercise's limits
+async fn run_exercise_tests(
+    cwd: &std::path::Path,
+    config: &TestConfig,
+    job: Option<&JobContext>,
+) -> anyhow::Result<CargoResult> {
+    if let Some(failed) = prepare_build(cwd, config, job).await? {
+        return Ok(failed);
+    }
+    let build = run_cargo_command("test", cwd, vec!["--no-run", "--message-format=json"], build_limits(), job).await?;
+    let artifacts = diagnostics::parse_test_artifacts(&build.stdout);
+    let build = with_diagnostics(build);
+    if !build.success {
+        return Ok(build);
+    }
+
+    // Harnesses that shell out to `cargo check` must not have to compile,
+    // since rustc does not fit in the exercise's memory limit
+    let check = run_cargo_command("check", cwd, vec!["--all-targets", "--quiet"], build_limits(), None).await?;
+    if !check.success {
+        return Ok(check);
+    }
+
+    let mut result = CargoResult {
+        success: true,
+        code: Some(0),
+        stdout: String::new(),
+        stderr: build.stderr,
+        output: String::new(),
+        diagnostics: None,
+        exercise_id: None,
+        hints: Vec::new(),
+    };
+    for artifact in &artifacts {
+        if job.is_some_and(JobContext::is_cancelled) {
+            break;
         }
-        
-        for exercise_entry in WalkDir::new(chapter_entry.path()).max_depth(1) {
-            let exercise_entry = exercise_entry?;
-            if !exercise_entry.file_type().is_dir() {
-                continue;
-            }
-            
-            let exercise_name = exercise_entry.file_name().to_string_lossy();
-            if exercise_name.starts_with("ex") {
-                count += 1;
-            }
+        let mut cmd = artifact.command(cwd);
+        cmd.arg("--nocapture");
+        let harness = run_sandboxed(cmd, SandboxLimits::for_tests(config), job).await?;
+        if result.success && !harness.success {
+            result.success = false;
+            result.code = harness.code;
         }
+        result.stdout.push_str(&harness.stdout);
+        result.stderr.push_str(&harness.stderr);
     }
-    
-    Ok(if count > 0 { count } else { 50 })
+    result.output = format!("{}{}", result.stdout, result.stderr);
+    Ok(result)
 }
 
-async fn ensure_progress_file(
-    progress_path: &std::path::Path,
-    exercises_path: &std::path::Path,
-) -> anyhow::Result<ProgressData> {
-    // Ensure the progress directory exists
-    if let Some(parent) = progress_path.parent() {
-        fs::create_dir_all(parent).await?;
+/// Run clippy on the exercise and report its diagnostics
+async fn run_exercise_check(
+    cwd: &std::path::Path,
+    config: &TestConfig,
+    job: Option<&JobContext>,
+) -> anyhow::Result<CargoResult> {
+    if let Some(failed) = prepare_build(cwd, config, job).await? {
+        return Ok(failed);
     }
-    
-    let total_exercises = count_total_exercises(exercises_path).await?;
-    
-    if !progress_path.exists() {
-        info!("Creating new progress file: {:?}", progress_path);
-        info!("Detected {} total exercises", total_exercises);
-        
-        let default_progress = ProgressData {
-            user_id: "default".to_string(),
-            created_at: Utc::now().to_rfc3339(),
-            overall_progress: 0.0,
-            chapters_completed: 0,
-            exercises_completed: 0,
-            total_exercises,
-            current_streak: 0,
-            longest_streak: 0,
-            total_time_minutes: 0,
-            chapters: serde_json::Value::Object(serde_json::Map::new()),
-            exercise_history: Vec::new(),
-            achievements: Vec::new(),
-            session_stats: SessionStats {
-                exercises_viewed: 0,
-                exercises_completed: 0,
-                hints_used: 0,
-                time_spent: 0,
-            },
-        };
-        
-        let content = serde_json::to_string_pretty(&default_progress)?;
-        fs::write(progress_path, content).await?;
-        info!("Progress file created successfully");
-        return Ok(default_progress);
-    }
-    
-    // File exists, load and validate it
-    let content = fs::read_to_string(progress_path).await?;
-    let mut progress: ProgressData = serde_json::from_str(&content)?;
-    
-    // Ensure all required properties exist (for backwards compatibility)
-    if progress.session_stats.exercises_viewed == 0 && progress.session_stats.exercises_completed == 0 && progress.session_stats.hints_used == 0 && progress.session_stats.time_spent == 0 {
-        // Initialize default session stats if they're all zero (likely missing)
-        progress.session_stats = SessionStats {
-            exercises_viewed: 0,
-            exercises_completed: 0,
-            hints_used: 0,
-            time_spent: 0,
+    let result = run_cargo_command("clippy", cwd, vec!["--message-format=json", "--", "-W", "clippy::all"], build_limits(), job).await?;
+    Ok(with_diagnostics(result))
+}
+
+impl JobContext {
+    /// Run a sandboxed command, streaming its output to the job's connection
+    async fn run(&self, sandbox: &Sandbox, cmd: Command) -> anyhow::Result<SandboxOutput> {
+        let (line_tx, mut line_rx) = mpsc::unbounded_channel();
+        let job = self.clone();
+        let forwarder = tokio::spawn(async move {
+            while let Some(line) = line_rx.recv().await {
+                job.send_line(line);
+            }
+        });
+
+        let mut cancel = self.cancel.clone();
+        let cancelled = async move {
+            // A dropped sender means the job finished; never report that as a cancel
+            if cancel.wait_for(|cancelled| *cancelled).await.is_err() {
+                std::future::pending::<()>().await;
+            }
         };
+
+        let output = sandbox.run_streaming(cmd, Some(line_tx), cancelled).await;
+        let _ = forwarder.await;
+        output
     }
-    
-    // Update total exercises count if it's wrong or missing
-    if progress.total_exercises == 0 || progress.total_exercises == 200 {
-        progress.total_exercises = total_exercises;
-        let content = serde_json::to_string_pretty(&progress)?;
-        fs::write(progress_path, content).await?;
-        info!("Updated total exercises count to {}", total_exercises);
+
+    fn send_line(&self, line: OutputLine) {
+        // Cargo's JSON messages become structured events, everything else is raw output
+        if line.stream == OutputStream::Stdout {
+            if let Some(event) = diagnostics::parse_cargo_event(&line.text) {
+                match event {
+                    CargoEvent::Artifact { name, fresh, .. } => {
+                        self.send("progress", serde_json::json!({ "crate": name, "fresh": fresh }));
+                    }
+                    CargoEvent::Diagnostic { diagnostic } => {
+                        self.send("diagnostic", serde_json::json!({ "diagnostic": diagnostic }));
+                    }
+                    CargoEvent::BuildFinished { .. } => {}
+                }
+                return;
+            }
+        }
+
+        self.send("output", serde_json::json!({ "stream": line.stream, "line": line.text }));
+    }
+
+    fn send(&self, event: &str, mut data: serde_json::Value) {
+        data["jobId"] = serde_json::json!(self.job_id);
+        data["event"] = serde_json::json!(event);
+
+        let _ = self.broadcast_tx.send(BroadcastMessage {
+            msg_type: "job".to_string(),
+            data,
+            target: Some(self.connection_id),
+        });
+    }
+
+    fn is_cancelled(&self) -> bool {
+        *self.cancel.borrow()
     }
-    
-    Ok(progress)
 }
 
-async fn update_exercise_completion(
-    progress_path: &std::path::Path,
-    exercises_path: &std::path::Path,
-    request: &CompleteExerciseRequest,
-) -> anyhow::Result<ProgressData> {
-    let mut progress = ensure_progress_file(progress_path, exercises_path).await?;
-    
-    // Check if already completed to avoid duplicates
-    let existing_entry = progress.exercise_history.iter().find(|entry| entry.exercise_id == request.exercise_id);
-    let is_already_completed = existing_entry.map_or(false, |entry| entry.completed_at.is_some());
-    
-    info!("Checking completion for {}: already completed: {}", request.exercise_id, is_already_completed);
-    
-    if is_already_completed {
-        info!("Exercise {} already completed", request.exercise_id);
-        return Ok(progress);
+async fn cancel_connection_jobs(state: &AppState, connection_id: ConnectionId) {
+    let jobs = state.jobs.read().await;
+    for handle in jobs.values().filter(|h| h.connection_id == connection_id) {
+        let _ = handle.cancel.send(true);
     }
-    
-    // Update progress
-    progress.exercises_completed += 1;
-    progress.session_stats.exercises_completed += 1;
-    progress.total_time_minutes += request.time_taken_minutes.unwrap_or(0);
-    progress.overall_progress = progress.exercises_completed as f64 / progress.total_exercises as f64;
-    
-    // Update or add to exercise history
-    if let Some(entry) = progress.exercise_history.iter_mut().find(|entry| entry.exercise_id == request.exercise_id) {
-        entry.completed_at = Some(Utc::now().to_rfc3339());
-        entry.time_taken_minutes = request.time_taken_minutes;
-        entry.status = "completed".to_string();
-        entry.session_id = Some(format!("session_{}", Utc::now().timestamp_millis()));
-    } else {
-        progress.exercise_history.push(ExerciseHistoryEntry {
-            exercise_id: request.exercise_id.clone(),
-            viewed_at: None,
-            completed_at: Some(Utc::now().to_rfc3339()),
-            time_taken_minutes: request.time_taken_minutes,
-            status: "completed".to_string(),
-            session_id: Some(format!("session_{}", Utc::now().timestamp_millis())),
-            hints_used: None,
-        });
+}
+
+/// Replace cargo's JSON stdout with typed diagnostics plus the human-readable text
+fn with_diagnostics(mut result: CargoResult) -> CargoResult {
+    let diagnostics = diagnostics::parse_cargo_messages(&result.stdout);
+
+    // Cargo prints rendered diagnostics to stderr in human mode; keep that layout
+    let rendered: String = diagnostics.iter().map(Diagnostic::display_text).collect();
+    result.stderr = format!("{}{}", rendered, result.stderr);
+    result.stdout = String::new();
+    result.output = result.stderr.clone();
+    result.diagnostics = Some(diagnostics);
+    result
+}
+
+/// Tag a result with its exercise and attach contextual hints for its
+/// diagnostics and panics
+async fn with_contextual_hints(
+    framework: &RwLock<Framework>,
+    exercise: &ExerciseRef,
+    mut result: CargoResult,
+) -> CargoResult {
+    let diagnostics = result.diagnostics.as_deref().unwrap_or_default();
+    if !result.success || !diagnostics.is_empty() {
+        let panics = libtest::extract_panic_messages(&result.output);
+        result.hints = framework.read().await.contextual_hints(&exercise.metadata, diagnostics, &panics);
     }
-    
-    // Save updated progress
-    let content = serde_json::to_string_pretty(&progress)?;
-    fs::write(progress_path, content).await?;
-    
-    info!(
-        "Exercise completed: {} in {} minutes",
-        request.exercise_id,
-        request.time_taken_minutes.unwrap_or(0)
-    );
-    info!(
-        "Total exercises completed: {}/{}",
-        progress.exercises_completed,
-        progress.total_exercises
-    );
-    
-    Ok(progress)
+    result.exercise_id = Some(exercise.id.clone());
+    result
 }
 
-async fn update_hint_usage(
-    progress_path: &std::path::Path,
-    exercises_path: &std::path::Path,
-    request: &HintRequest,
-) -> anyhow::Result<ProgressData> {
-    let mut progress = ensure_progress_file(progress_path, exercises_path).await?;
-    
-    // Update hint usage stats
-    progress.session_stats.hints_used += 1;
-    
-    // Add to exercise history if not already tracked for this exercise
-    if let Some(entry) = progress.exercise_history.iter_mut().find(|entry| entry.exercise_id == request.exercise_id) {
-        if let Some(ref mut hints_used) = entry.hints_used {
-            if !hints_used.contains(&request.hint_level) {
-                hints_used.push(request.hint_level);
-            }
-        } else {
-            entry.hints_used = Some(vec![request.hint_level]);
+/// The Rust Book to serve at /book, from `RUST_TOUR_BOOK_DIR` or the
+/// toolchain's documentation; without one, book links point online
+fn open_local_book() -> Option<Arc<LocalBook>> {
+    let root = LocalBook::locate()?;
+    match LocalBook::open(&root) {
+        Ok(book) => {
+            info!("📖 Serving the Rust Book from {}", root.display());
+            Some(Arc::new(book))
+        }
+        Err(e) => {
+            warn!("Not serving the Rust Book locally: {}", e);
+            None
         }
-    } else {
-        progress.exercise_history.push(ExerciseHistoryEntry {
-            exercise_id: request.exercise_id.clone(),
-            viewed_at: Some(Utc::now().to_rfc3339()),
-            completed_at: None,
-            time_taken_minutes: None,
-            status: "in_progress".to_string(),
-            session_id: None,
-            hints_used: Some(vec![request.hint_level]),
-        });
     }
-    
-    // Save updated progress
-    let content = serde_json::to_string_pretty(&progress)?;
-    fs::write(progress_path, content).await?;
-    
-    info!("Hint used: {}, level {}", request.exercise_id, request.hint_level);
-    Ok(progress)
 }
 
-async fn update_exercise_view(
-    progress_path: &std::path::Path,
-    exercises_path: &std::path::Path,
-    request: &ViewRequest,
-) -> anyhow::Result<ProgressData> {
-    let mut progress = ensure_progress_file(progress_path, exercises_path).await?;
-    
-    // Update view stats
-    progress.session_stats.exercises_viewed += 1;
-    
-    // Check if already viewed
-    if !progress.exercise_history.iter().any(|entry| entry.exercise_id == request.exercise_id) {
-        progress.exercise_history.push(ExerciseHistoryEntry {
-            exercise_id: request.exercise_id.clone(),
-            viewed_at: Some(Utc::now().to_rfc3339()),
-            completed_at: None,
-            time_taken_minutes: None,
-            status: "viewed".to_string(),
-            session_id: None,
-            hints_used: None,
-        });
+/// Progress backend chosen by `RUST_TOUR_PROGRESS_STORE`: JSON files
+/// (the default) or an embedded SQLite database
+fn open_progress_store(progress_dir: &std::path::Path) -> anyhow::Result<Arc<dyn ProgressStore>> {
+    match env::var("RUST_TOUR_PROGRESS_STORE").as_deref() {
+        Ok("sqlite") => {
+            let database = progress_dir.join("progress.db");
+            info!("🗄️ Storing progress in {}", database.display());
+            Ok(Arc::new(SqliteProgressStore::open(database)?))
+        }
+        Ok("json") | Err(_) => Ok(Arc::new(JsonProgressStore::new(progress_dir))),
+        Ok(other) => anyhow::bail!(
+            "Unknown RUST_TOUR_PROGRESS_STORE {:?}: expected \"json\" or \"sqlite\"",
+            other
+        ),
     }
-    
-    // Save updated progress
-    let content = serde_json::to_string_pretty(&progress)?;
-    fs::write(progress_path, content).await?;
-    
-    info!("Exercise viewed: {}", request.exercise_id);
-    Ok(progress)
 }
 
-async fn initialize_progress_system(state: &AppState) -> anyhow::Result<()> {
-    match ensure_progress_file(&state.progress_path, &state.exercises_path).await {
-        Ok(_) => {
+fn initialize_framework(
+    exercises_path: &std::path::Path,
+    progress_store: Arc<dyn ProgressStore>,
+) -> anyhow::Result<Framework> {
+    match Framework::with_progress_store(exercises_path, progress_store) {
+        Ok(mut framework) => {
+            if let Ok(progress) = framework.get_progress(DEFAULT_USER) {
+                info!("Detected {} total exercises", progress.total_exercises);
+            }
             info!("📊 Progress system initialized");
-            Ok(())
+            match framework.capture_starters() {
+                Ok(0) => {}
+                Ok(captured) => info!("Kept starter code for {} exercises", captured),
+                Err(e) => warn!("Failed to keep starter code: {}", e),
+            }
+            if let Ok(rules) = env::var("RUST_TOUR_HINT_RULES") {
+                match framework.add_hint_rules(&rules) {
+                    Ok(added) => info!("💡 Loaded {} contextual hint rules from {}", added, rules),
+                    Err(e) => warn!("Failed to load hint rules from {}: {:#}", rules, e),
+                }
+            }
+            if let Ok(achievements) = env::var("RUST_TOUR_ACHIEVEMENTS") {
+                match framework.load_achievements(&achievements) {
+                    Ok(defined) => info!("🏆 Loaded {} achievements from {}", defined, achievements),
+                    Err(e) => warn!("Failed to load achievements from {}: {:#}", achievements, e),
+                }
+            }
+            Ok(framework)
         }
         Err(e) => {
             error!("Failed to initialize progress system: {}", e);
@@ -1475,7 +2589,8 @@ async fn initialize_progress_system(state: &AppState) -> anyhow::Result<()> {
 
 async fn setup_file_watcher(state: AppState) -> anyhow::Result<()> {
     let exercises_path = state.exercises_path.clone();
-    let broadcast_tx = state.broadcast_tx.clone();
+    let workspaces_path = state.framework.read().await.workspaces().root().to_path_buf();
+    std::fs::create_dir_all(&workspaces_path)?;
     
     tokio::spawn(async move {
         let (tx, mut rx) = tokio::sync::mpsc::channel(100);
@@ -1499,37 +2614,41 @@ async fn setup_file_watcher(state: AppState) -> anyhow::Result<()> {
             error!("Failed to watch exercises directory: {}", e);
             return;
         }
+        if let Err(e) = watcher.watch(&workspaces_path, RecursiveMode::Recursive) {
+            error!("Failed to watch workspaces directory: {}", e);
+            return;
+        }
         
         while let Some(res) = rx.recv().await {
             match res {
                 Ok(event) => {
                     for path in event.paths {
                         if let Ok(relative_path) = path.strip_prefix(&exercises_path) {
-                            let path_parts: Vec<_> = relative_path.components().collect();
-                            if path_parts.len() >= 2 {
-                                let chapter_dir = path_parts[0].as_os_str().to_string_lossy();
-                                let exercise_dir = path_parts[1].as_os_str().to_string_lossy();
-                                
-                                // Try to load exercise metadata to get title
-                                let metadata_path = exercises_path.join(&*chapter_dir).join(&*exercise_dir).join("metadata.json");
-                                let exercise_name = if metadata_path.exists() {
-                                    match load_exercise_title(&exercises_path.join(&*chapter_dir).join(&*exercise_dir)).await {
-                                        Ok(title) => title,
-                                        Err(_) => exercise_dir.replace('_', " ").replacen("ex", "", 1),
-                                    }
-                                } else {
-                                    exercise_dir.replace('_', " ").replacen("ex", "", 1)
-                                };
-                                
-                                let broadcast_msg = BroadcastMessage {
+                            // Keep the index current, recounting progress totals when exercises come or go
+                            let affects_index = state.framework.read().await.affects_exercise_index(&path);
+                            if affects_index {
+                                if let Err(e) = state.framework.write().await.refresh_exercise_index(&path) {
+                                    warn!("Failed to refresh exercise totals: {}", e);
+                                }
+                            }
+
+                            // The shared tree is the same for everyone
+                            if let Some(data) = file_changed_data(&state, relative_path).await {
+                                let _ = state.broadcast_tx.send(BroadcastMessage {
                                     msg_type: "file_changed".to_string(),
-                                    data: serde_json::json!({
-                                        "exercise": exercise_name,
-                                        "file": relative_path.to_string_lossy()
-                                    }),
-                                };
-                                
-                                let _ = broadcast_tx.send(broadcast_msg);
+                                    data,
+                                    target: None,
+                                });
+                            }
+                        } else if let Ok(relative_path) = path.strip_prefix(&workspaces_path) {
+                            // A learner's own copy is only news to them
+                            let mut components = relative_path.components();
+                            let Some(user_id) = components.next() else {
+                                continue;
+                            };
+                            let user_id = user_id.as_os_str().to_string_lossy();
+                            if let Some(data) = file_changed_data(&state, components.as_path()).await {
+                                broadcast_to_user(&state, &user_id, "file_changed", data).await;
                             }
                         }
                     }
@@ -1544,6 +2663,35 @@ async fn setup_file_watcher(state: AppState) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// The `file_changed` message for a file inside an exercise, relative to the
+/// exercises root or a workspace; build output and hidden files, such as
+/// workspace copies still being staged, are left out
+async fn file_changed_data(state: &AppState, relative_path: &Path) -> Option<serde_json::Value> {
+    let path_parts: Vec<_> = relative_path.components().collect();
+    let ignored = |part: &std::path::Component| {
+        let name = part.as_os_str().to_string_lossy();
+        name == "target" || name.starts_with('.')
+    };
+    if path_parts.len() < 2 || path_parts.iter().any(ignored) {
+        return None;
+    }
+    let chapter_dir = path_parts[0].as_os_str().to_string_lossy();
+    let exercise_dir = path_parts[1].as_os_str().to_string_lossy();
+    
+    let exercise_path = format!("{}/{}", chapter_dir, exercise_dir);
+    let (exercise_name, exercise_id) = match state.framework.read().await.exercise_index().at(&exercise_path) {
+        Some(metadata) => (metadata.title.clone(), Some(metadata.id.clone())),
+        None => (exercise_dir.replace('_', " ").replacen("ex", "", 1), None),
+    };
+    
+    Some(serde_json::json!({
+        "exercise": exercise_name,
+        "exercise_id": exercise_id,
+        "path": exercise_path,
+        "file": relative_path.to_string_lossy()
+    }))
+}
+
 async fn shutdown_signal() {
     let ctrl_c = async {
         tokio::signal::ctrl_c()
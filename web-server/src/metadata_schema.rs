@@ -0,0 +1,47 @@
+//! `rust-tour metadata-schema`: regenerate the JSON Schema editors use to
+//! complete and check `metadata.json`.
+
+use anyhow::{Context, Result};
+use exercise_framework::metadata::json_schema;
+use std::path::Path;
+
+/// Where the schema is shipped, relative to the workspace root
+pub const SCHEMA_FILE: &str = "exercise-framework/schema/exercise-metadata.schema.json";
+
+const USAGE: &str = "Usage: rust-tour metadata-schema [--check | --stdout]
+
+Writes the schema for the current metadata format to
+exercise-framework/schema/exercise-metadata.schema.json.
+  --check   Fail if the shipped schema is out of date instead of writing it
+  --stdout  Print the schema instead of writing it";
+
+/// Run the subcommand from the workspace root
+pub fn run(args: &[String], workspace_root: &Path) -> Result<()> {
+    let schema = serde_json::to_string_pretty(&json_schema())? + "\n";
+    let path = workspace_root.join(SCHEMA_FILE);
+
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        [] => {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&path, schema).with_context(|| format!("Failed to write {:?}", path))?;
+            println!("✅ Wrote {}", SCHEMA_FILE);
+        }
+        ["--check"] => {
+            let shipped = std::fs::read_to_string(&path).unwrap_or_default();
+            if shipped != schema {
+                anyhow::bail!(
+                    "{} is out of date; run `cargo run -p rust-tour -- metadata-schema`",
+                    SCHEMA_FILE
+                );
+            }
+            println!("✅ {} is up to date", SCHEMA_FILE);
+        }
+        ["--stdout"] => print!("{}", schema),
+        ["--help"] => println!("{}", USAGE),
+        _ => anyhow::bail!("Unexpected arguments {:?}\n\n{}", args, USAGE),
+    }
+    Ok(())
+}
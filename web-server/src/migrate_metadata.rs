@@ -0,0 +1,67 @@
+//! `rust-tour migrate-metadata`: upgrade every exercise's metadata file to
+//! the current schema version, keeping its format.
+
+use anyhow::Result;
+use exercise_framework::metadata::{file_schema_version, is_metadata_file, migrate_file, METADATA_SCHEMA_VERSION};
+use std::path::Path;
+use walkdir::WalkDir;
+
+const USAGE: &str = "Usage: rust-tour migrate-metadata [--check]
+
+Rewrites exercise metadata written in an older format as the current one.
+With --check, nothing is written and the command fails if any file is outdated.";
+
+/// Run the subcommand from the workspace root (the directory holding `exercises/`)
+pub fn run(args: &[String], workspace_root: &Path) -> Result<()> {
+    let mut check = false;
+    for arg in args {
+        match arg.as_str() {
+            "--check" => check = true,
+            "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            _ => anyhow::bail!("Unexpected argument '{}'\n\n{}", arg, USAGE),
+        }
+    }
+
+    let exercises_root = workspace_root.join("exercises");
+    if !exercises_root.is_dir() {
+        anyhow::bail!("No exercises directory in {:?}; run from the repository root", workspace_root);
+    }
+
+    let mut outdated = 0;
+    for entry in WalkDir::new(&exercises_root)
+        .into_iter()
+        .filter_entry(|entry| entry.file_name() != "target")
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| is_metadata_file(entry.path()))
+    {
+        let path = entry.path();
+        let relative = path.strip_prefix(workspace_root).unwrap_or(path).display();
+        let version = if check {
+            Some(file_schema_version(path)?).filter(|version| *version != METADATA_SCHEMA_VERSION)
+        } else {
+            migrate_file(path)?
+        };
+
+        if let Some(version) = version {
+            outdated += 1;
+            if check {
+                println!("❌ {} uses schema version {}", relative, version);
+            } else {
+                println!("✅ {} upgraded from version {}", relative, version);
+            }
+        }
+    }
+
+    match (outdated, check) {
+        (0, _) => println!("All metadata is at schema version {}", METADATA_SCHEMA_VERSION),
+        (_, true) => anyhow::bail!(
+            "{} metadata files need migrating; run `cargo run -p rust-tour -- migrate-metadata`",
+            outdated
+        ),
+        (_, false) => println!("Migrated {} files to schema version {}", outdated, METADATA_SCHEMA_VERSION),
+    }
+    Ok(())
+}
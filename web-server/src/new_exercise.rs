@@ -0,0 +1,290 @@
+//! `rust-tour new-exercise`: generate a complete exercise skeleton for authors.
+//!
+//! Every field can be given as a `--flag value`; anything left out is asked
+//! for on the terminal.
+
+use anyhow::{Context, Result};
+use exercise_framework::{
+    scaffold::{find_chapter_dir, next_exercise_number, validate_name},
+    validation::IssueSeverity,
+    ExerciseDifficulty, ExerciseScaffold, ExerciseType, ExerciseValidator,
+};
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+const USAGE: &str = "Usage: rust-tour new-exercise [options]
+
+Options (prompted for when missing):
+  --chapter <n>            Chapter number
+  --chapter-topic <name>   Topic for a new chapter directory, e.g. common_concepts
+  --name <name>            Exercise name in snake_case, e.g. temperature_conversion
+  --title <text>           Exercise title
+  --description <text>     What learners will learn
+  --difficulty <level>     beginner, intermediate or advanced
+  --type <type>            code_completion, bug_fixing, from_scratch, code_review or performance
+  --time <minutes>         Estimated time
+  --concepts <a,b,...>     Concepts taught, from the standard taxonomy
+  --prerequisites <ids>    Comma-separated exercise ids, e.g. ch03-ex01-variables
+  --book <chapter>         Primary Rust Book chapter, e.g. 3.2
+  --allow-custom-concepts  Accept concepts outside the standard taxonomy
+  --help                   Show this message";
+
+const VALUE_OPTIONS: &[&str] = &[
+    "chapter",
+    "chapter-topic",
+    "name",
+    "title",
+    "description",
+    "difficulty",
+    "type",
+    "time",
+    "concepts",
+    "prerequisites",
+    "book",
+];
+
+/// Run the subcommand from the workspace root (the directory holding `exercises/`)
+pub fn run(args: &[String], workspace_root: &Path) -> Result<()> {
+    let (options, flags) = parse_args(args)?;
+    if flags.iter().any(|flag| flag == "help") {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+    let allow_custom_concepts = flags.iter().any(|flag| flag == "allow-custom-concepts");
+
+    let exercises_root = workspace_root.join("exercises");
+    if !exercises_root.is_dir() {
+        anyhow::bail!("No exercises directory in {:?}; run from the repository root", workspace_root);
+    }
+
+    let validator = ExerciseValidator::new();
+    let stdin = io::stdin();
+    let mut prompt = Prompt {
+        options,
+        input: stdin.lock(),
+    };
+
+    let chapter: u32 = prompt
+        .value("chapter", "Chapter number", None)?
+        .parse()
+        .context("Chapter must be a number")?;
+    let chapter_topic = match find_chapter_dir(&exercises_root, chapter)? {
+        Some(_) => None,
+        None => Some(prompt.value(
+            "chapter-topic",
+            "Chapter directory does not exist yet; its topic (e.g. common_concepts)",
+            None,
+        )?),
+    };
+    println!(
+        "Creating exercise {} of chapter {}",
+        next_exercise_number(&exercises_root, chapter)?,
+        chapter
+    );
+
+    let name = prompt.value("name", "Exercise name (snake_case)", None)?;
+    validate_name(&name)?;
+    let title = prompt.value("title", "Title", None)?;
+    let description = prompt.value("description", "Description (what learners will learn)", None)?;
+    let difficulty: ExerciseDifficulty = prompt
+        .value(
+            "difficulty",
+            "Difficulty (beginner, intermediate, advanced)",
+            Some("beginner"),
+        )?
+        .parse()?;
+    let exercise_type: ExerciseType = prompt
+        .value(
+            "type",
+            "Exercise type (code_completion, bug_fixing, from_scratch, code_review, performance)",
+            Some("code_completion"),
+        )?
+        .parse()?;
+    let default_time = match difficulty {
+        ExerciseDifficulty::Beginner => "15",
+        ExerciseDifficulty::Intermediate => "30",
+        ExerciseDifficulty::Advanced => "60",
+    };
+    let estimated_time_minutes: u32 = prompt
+        .value("time", "Estimated time in minutes", Some(default_time))?
+        .parse()
+        .context("Estimated time must be a number of minutes")?;
+    let concepts = prompt.concepts(&validator, allow_custom_concepts)?;
+    let prerequisites = split_list(&prompt.value(
+        "prerequisites",
+        "Prerequisite exercise ids, comma-separated",
+        Some(""),
+    )?);
+    let primary_book_chapter = prompt.value("book", "Primary Rust Book chapter (e.g. 3.2)", None)?;
+    if !validator.is_known_book_chapter(&primary_book_chapter) {
+        eprintln!("⚠️  Chapter '{}' may not exist in the Rust Book", primary_book_chapter);
+    }
+
+    let scaffold = ExerciseScaffold {
+        chapter,
+        chapter_topic,
+        name,
+        title,
+        description,
+        difficulty,
+        exercise_type,
+        estimated_time_minutes,
+        concepts,
+        prerequisites,
+        primary_book_chapter,
+    };
+    let created = scaffold.create(&exercises_root)?;
+
+    println!("✅ Created {} in {}", created.metadata.id, created.path.display());
+    if let Some(member) = &created.registered_member {
+        println!("📦 Added \"{}\" to the workspace members", member);
+    }
+    println!(
+        "🔍 Validation score {:.2} ({})",
+        created.validation.score,
+        if created.validation.is_valid { "valid" } else { "needs work" }
+    );
+    for issue in &created.validation.issues {
+        println!("   {:?}: {}", issue.severity, issue.message);
+    }
+    if created
+        .validation
+        .issues
+        .iter()
+        .any(|issue| matches!(issue.severity, IssueSeverity::Error))
+    {
+        anyhow::bail!("Generated exercise does not validate");
+    }
+    println!("Fill in the TODOs in README.md, hints.md, src/main.rs, tests/ and solutions/");
+
+    Ok(())
+}
+
+/// `--key value` / `--key=value` options and bare `--flag`s
+fn parse_args(args: &[String]) -> Result<(HashMap<String, String>, Vec<String>)> {
+    let mut options = HashMap::new();
+    let mut flags = Vec::new();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let Some(option) = arg.strip_prefix("--") else {
+            anyhow::bail!("Unexpected argument '{}'\n\n{}", arg, USAGE);
+        };
+        let (key, inline_value) = match option.split_once('=') {
+            Some((key, value)) => (key, Some(value.to_string())),
+            None => (option, None),
+        };
+
+        if VALUE_OPTIONS.contains(&key) {
+            let value = match inline_value {
+                Some(value) => value,
+                None => args
+                    .next()
+                    .cloned()
+                    .with_context(|| format!("--{} needs a value", key))?,
+            };
+            options.insert(key.to_string(), value);
+        } else if matches!(key, "help" | "allow-custom-concepts") && inline_value.is_none() {
+            flags.push(key.to_string());
+        } else {
+            anyhow::bail!("Unknown option '--{}'\n\n{}", key, USAGE);
+        }
+    }
+
+    Ok((options, flags))
+}
+
+fn split_list(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Answers from the command line first, then from the terminal
+struct Prompt<R> {
+    options: HashMap<String, String>,
+    input: R,
+}
+
+impl<R: BufRead> Prompt<R> {
+    fn ask(&mut self, key: &str, question: &str, default: Option<&str>) -> Result<String> {
+        loop {
+            match default {
+                Some(default) if !default.is_empty() => print!("{} [{}]: ", question, default),
+                _ => print!("{}: ", question),
+            }
+            io::stdout().flush()?;
+
+            let mut line = String::new();
+            if self.input.read_line(&mut line)? == 0 {
+                println!();
+                return default
+                    .map(String::from)
+                    .with_context(|| format!("Input ended before --{} was given", key));
+            }
+            let answer = line.trim();
+            if !answer.is_empty() {
+                return Ok(answer.to_string());
+            }
+            if let Some(default) = default {
+                return Ok(default.to_string());
+            }
+        }
+    }
+
+    fn value(&mut self, key: &str, question: &str, default: Option<&str>) -> Result<String> {
+        match self.options.remove(key) {
+            Some(value) => Ok(value),
+            None => self.ask(key, question, default),
+        }
+    }
+
+    /// Concepts, checked against the validator's taxonomy. Unknown concepts
+    /// are rejected on the command line unless explicitly allowed, and need
+    /// confirming when typed in.
+    fn concepts(&mut self, validator: &ExerciseValidator, allow_custom: bool) -> Result<Vec<String>> {
+        if let Some(list) = self.options.remove("concepts") {
+            let concepts = split_list(&list);
+            let unknown: Vec<&str> = concepts
+                .iter()
+                .map(String::as_str)
+                .filter(|concept| !validator.is_standard_concept(concept))
+                .collect();
+            if !unknown.is_empty() && !allow_custom {
+                anyhow::bail!(
+                    "Concepts not in the standard taxonomy: {} (pass --allow-custom-concepts to keep them)\nStandard concepts: {}",
+                    unknown.join(", "),
+                    validator.concept_taxonomy().join(", ")
+                );
+            }
+            if concepts.is_empty() {
+                anyhow::bail!("--concepts needs at least one concept");
+            }
+            return Ok(concepts);
+        }
+
+        println!("Standard concepts: {}", validator.concept_taxonomy().join(", "));
+        'ask: loop {
+            let concepts = split_list(&self.ask("concepts", "Concepts taught, comma-separated", None)?);
+            for concept in &concepts {
+                if validator.is_standard_concept(concept) || allow_custom {
+                    continue;
+                }
+                let keep = self.ask(
+                    "concepts",
+                    &format!("'{}' is not in the standard taxonomy; keep it? (y/N)", concept),
+                    Some("n"),
+                )?;
+                if !keep.eq_ignore_ascii_case("y") {
+                    continue 'ask;
+                }
+            }
+            if !concepts.is_empty() {
+                return Ok(concepts);
+            }
+        }
+    }
+}
@@ -0,0 +1,91 @@
+//! `rust-tour validate-curriculum`: validate every exercise and the
+//! curriculum as a whole, writing reports for the content review pipeline.
+
+use anyhow::{Context, Result};
+use exercise_framework::{
+    book::TableOfContents, validation::IssueSeverity, CurriculumValidator, ExerciseValidator,
+};
+use std::path::Path;
+
+const USAGE: &str = "Usage: rust-tour validate-curriculum [options]
+
+Options:
+  --json <file>        Write the full report as JSON
+  --markdown <file>    Write a Markdown summary
+  --junit <file>       Write JUnit XML
+  --verify-solutions   Also run each exercise's tests against its solutions (slow)
+  --book-summary <path>
+                       Check book references against this SUMMARY.md (or a
+                       book checkout) instead of the vendored one
+  --help               Show this message
+
+Use - as the file to write to stdout. Without any report option the
+Markdown summary is printed. Exits with an error when validation fails.";
+
+/// Run the subcommand from the workspace root (the directory holding `exercises/`)
+pub fn run(args: &[String], workspace_root: &Path) -> Result<()> {
+    let mut outputs: Vec<(&str, &str)> = Vec::new(); // (format, file)
+    let mut verify_solutions = false;
+    let mut book_summary = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            "--verify-solutions" => verify_solutions = true,
+            "--book-summary" => {
+                book_summary = Some(args.next().context("--book-summary needs a path")?);
+            }
+            "--json" | "--markdown" | "--junit" => {
+                let file = args
+                    .next()
+                    .with_context(|| format!("{} needs a file", arg))?;
+                outputs.push((&arg[2..], file));
+            }
+            _ => anyhow::bail!("Unknown argument '{}'\n\n{}", arg, USAGE),
+        }
+    }
+    if outputs.is_empty() {
+        outputs.push(("markdown", "-"));
+    }
+
+    let exercises_root = workspace_root.join("exercises");
+    if !exercises_root.is_dir() {
+        anyhow::bail!("No exercises directory in {:?}; run from the repository root", workspace_root);
+    }
+
+    let mut validator = ExerciseValidator::new();
+    if verify_solutions {
+        validator = validator.with_solution_verification();
+    }
+    if let Some(path) = book_summary {
+        validator = validator.with_book_contents(TableOfContents::load(path)?);
+    }
+    let report = CurriculumValidator::with_validator(validator).validate(&exercises_root)?;
+
+    for (format, file) in outputs {
+        let content = match format {
+            "json" => report.to_json()?,
+            "junit" => report.to_junit_xml(),
+            _ => report.to_markdown(),
+        };
+        if file == "-" {
+            print!("{}", content);
+        } else {
+            std::fs::write(file, content).with_context(|| format!("Failed to write {}", file))?;
+            eprintln!("📝 Wrote {} report to {}", format, file);
+        }
+    }
+
+    if !report.is_valid {
+        anyhow::bail!(
+            "Curriculum validation failed: {} errors, {} warnings",
+            report.count(IssueSeverity::Error),
+            report.count(IssueSeverity::Warning)
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,69 @@
+//! `rust-tour verify-exercises`: validate exercises, building each one and
+//! running its tests against the reference solutions and starter template.
+
+use anyhow::Result;
+use exercise_framework::{Exercise, ExerciseValidator};
+use std::path::Path;
+
+const USAGE: &str = "Usage: rust-tour verify-exercises [exercise-id...]
+
+Validates every exercise (or just the ones named), failing when a reference
+solution does not pass the exercise's tests or the starter template already does.";
+
+/// Run the subcommand from the workspace root (the directory holding `exercises/`)
+pub fn run(args: &[String], workspace_root: &Path) -> Result<()> {
+    if let Some(option) = args.iter().find(|arg| arg.starts_with("--")) {
+        if option == "--help" {
+            println!("{}", USAGE);
+            return Ok(());
+        }
+        anyhow::bail!("Unknown option '{}'\n\n{}", option, USAGE);
+    }
+
+    let exercises_root = workspace_root.join("exercises");
+    if !exercises_root.is_dir() {
+        anyhow::bail!("No exercises directory in {:?}; run from the repository root", workspace_root);
+    }
+
+    let exercises: Vec<_> = Exercise::list_all_with_paths(&exercises_root)?
+        .into_iter()
+        .filter(|(metadata, _)| args.is_empty() || args.contains(&metadata.id))
+        .collect();
+    if let Some(unknown) = args
+        .iter()
+        .find(|id| !exercises.iter().any(|(metadata, _)| metadata.id == **id))
+    {
+        anyhow::bail!("Exercise not found: {}", unknown);
+    }
+
+    let validator = ExerciseValidator::new().with_solution_verification();
+    let mut failed = 0;
+    for (metadata, path) in &exercises {
+        println!("🔍 Verifying {}", metadata.id);
+        let exercise = Exercise::load_from_path(path)?;
+        let result = validator.validate(&exercise)?;
+
+        for issue in &result.issues {
+            let location = match (&issue.file, issue.line) {
+                (Some(file), Some(line)) => format!("{}:{}: ", file, line),
+                (Some(file), None) => format!("{}: ", file),
+                _ => String::new(),
+            };
+            println!("   {:?}: {}{}", issue.severity, location, issue.message);
+            if let Some(suggestion) = &issue.suggestion {
+                println!("      💡 {}", suggestion);
+            }
+        }
+        if result.is_valid {
+            println!("✅ {} (score {:.2})", metadata.id, result.score);
+        } else {
+            println!("❌ {} (score {:.2})", metadata.id, result.score);
+            failed += 1;
+        }
+    }
+
+    if failed > 0 {
+        anyhow::bail!("{} of {} exercises failed verification", failed, exercises.len());
+    }
+    Ok(())
+}
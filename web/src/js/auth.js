@@ -0,0 +1,95 @@
+// Learner identity for classroom deployments. The server hands out a
+// bearer token on sign-in; every API call and the WebSocket carry it.
+// Without a token the server treats us as the single local learner
+// (unless it was started with RUST_TOUR_REQUIRE_AUTH=true).
+
+const TOKEN_KEY = 'rustTourToken';
+
+export function getToken() {
+  return localStorage.getItem(TOKEN_KEY);
+}
+
+function setToken(token) {
+  if (token) {
+    localStorage.setItem(TOKEN_KEY, token);
+  } else {
+    localStorage.removeItem(TOKEN_KEY);
+  }
+}
+
+// fetch() with the session token attached
+export function apiFetch(url, options = {}) {
+  const token = getToken();
+  const headers = { ...(options.headers || {}) };
+  if (token) {
+    headers['Authorization'] = `Bearer ${token}`;
+  }
+  return fetch(url, { ...options, headers });
+}
+
+// Resolve to the current session, asking the learner to sign in when the
+// server requires it or our stored token has expired
+export async function ensureSignedIn() {
+  const response = await apiFetch('/api/auth/me');
+  if (response.ok) {
+    return response.json();
+  }
+  if (response.status !== 401) {
+    throw new Error(`Failed to load session: ${response.status}`);
+  }
+
+  setToken(null);
+  return showSignInForm();
+}
+
+async function submitCredentials(action, username, password) {
+  const response = await fetch(`/api/auth/${action}`, {
+    method: 'POST',
+    headers: { 'Content-Type': 'application/json' },
+    body: JSON.stringify({ username, password })
+  });
+  const body = await response.json().catch(() => ({}));
+
+  if (!response.ok) {
+    throw new Error(body.error || 'Sign-in failed');
+  }
+  setToken(body.token);
+  return body;
+}
+
+function showSignInForm() {
+  return new Promise((resolve) => {
+    const overlay = document.createElement('div');
+    overlay.className = 'auth-overlay';
+    overlay.innerHTML = `
+      <form class="auth-form">
+        <h2>Sign in to Rust Tour</h2>
+        <input name="username" placeholder="Username" autocomplete="username" required>
+        <input name="password" type="password" placeholder="Password" autocomplete="current-password" required>
+        <div class="auth-error" hidden></div>
+        <div class="auth-actions">
+          <button type="submit" data-action="login">Sign in</button>
+          <button type="submit" data-action="register">Create account</button>
+        </div>
+      </form>
+    `;
+    document.body.appendChild(overlay);
+
+    const form = overlay.querySelector('form');
+    const errorBox = overlay.querySelector('.auth-error');
+
+    form.addEventListener('submit', async (e) => {
+      e.preventDefault();
+      const action = e.submitter?.dataset.action || 'login';
+
+      try {
+        const session = await submitCredentials(action, form.username.value, form.password.value);
+        overlay.remove();
+        resolve(session);
+      } catch (error) {
+        errorBox.textContent = error.message;
+        errorBox.hidden = false;
+      }
+    });
+  });
+}
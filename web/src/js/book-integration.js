@@ -14,7 +14,7 @@ export class BookIntegration {
     }
 
     try {
-      const url = `${this.baseUrl}ch${chapterRef}.html`;
+      const url = this.baseUrl;
       const response = await fetch(`/api/book/${chapterRef}`);
       
       if (response.ok) {
@@ -1,16 +1,98 @@
+import { apiFetch } from './auth.js';
+
 export class ExerciseManager {
-  constructor() {
+  constructor(websocket = null) {
     this.exercises = [];
     this.currentExercise = null;
+    this.websocket = websocket;
+    this.jobs = new Map();
+    this.pendingJobEvents = new Map(); // Events that arrived before the job id
+    this.activeJobId = null;
   }
 
   async init() {
     console.log('Initializing Exercise Manager...');
+
+    if (this.websocket) {
+      this.websocket.addMessageHandler('job', (data) => this.handleJobEvent(data));
+    }
+  }
+
+  canStreamJobs() {
+    return Boolean(this.websocket && this.websocket.connectionId);
+  }
+
+  // Start a run/test/check job; output streams to onEvent and the
+  // returned promise resolves with the final cargo result
+  async runJob(path, kind, onEvent = () => {}) {
+    const [chapter, exercise] = path.split('/');
+    const response = await apiFetch(`/api/exercises/${chapter}/${exercise}/jobs`, {
+      method: 'POST',
+      headers: {
+        'Content-Type': 'application/json',
+      },
+      body: JSON.stringify({
+        kind,
+        connection_id: this.websocket.connectionId,
+      }),
+    });
+
+    if (!response.ok) {
+      throw new Error(`Failed to start ${kind} job: ${response.statusText}`);
+    }
+
+    const { job_id: jobId } = await response.json();
+    this.activeJobId = jobId;
+
+    const finished = new Promise((resolve, reject) => {
+      this.jobs.set(jobId, { onEvent, resolve, reject });
+    });
+
+    const pending = this.pendingJobEvents.get(jobId) || [];
+    this.pendingJobEvents.delete(jobId);
+    pending.forEach(event => this.handleJobEvent(event));
+
+    return finished;
+  }
+
+  handleJobEvent(data) {
+    const job = this.jobs.get(data.jobId);
+    if (!job) {
+      const pending = this.pendingJobEvents.get(data.jobId) || [];
+      pending.push(data);
+      this.pendingJobEvents.set(data.jobId, pending);
+      return;
+    }
+
+    job.onEvent(data);
+
+    if (data.event === 'finished' || data.event === 'failed') {
+      this.jobs.delete(data.jobId);
+      if (this.activeJobId === data.jobId) {
+        this.activeJobId = null;
+      }
+
+      if (data.event === 'finished') {
+        job.resolve(data.result);
+      } else {
+        job.reject(new Error(data.error));
+      }
+    }
+  }
+
+  async cancelJob(jobId = this.activeJobId) {
+    if (!jobId) return;
+
+    try {
+      await apiFetch(`/api/jobs/${jobId}`, { method: 'DELETE' });
+    } catch (error) {
+      console.error('Error cancelling job:', error);
+    }
   }
 
   async loadExercises() {
     try {
-      const response = await fetch('/api/exercises');
+      const response = await apiFetch('/api/exercises');
       if (!response.ok) {
         throw new Error(`Failed to load exercises: ${response.statusText}`);
       }
@@ -26,7 +108,7 @@ export class ExerciseManager {
   async loadExercise(path) {
     try {
       const [chapter, exercise] = path.split('/');
-      const response = await fetch(`/api/exercises/${chapter}/${exercise}`);
+      const response = await apiFetch(`/api/exercises/${chapter}/${exercise}`);
       
       if (!response.ok) {
         throw new Error(`Failed to load exercise: ${response.statusText}`);
@@ -42,10 +124,36 @@ export class ExerciseManager {
     }
   }
 
+  // Ask for the next hint level; a 403 means the learner turned hints off
+  async revealNextHint(exerciseId) {
+    const response = await apiFetch(`/api/exercises/by-id/${encodeURIComponent(exerciseId)}/hints/next`);
+    if (response.status === 403) {
+      return { status: 'disabled' };
+    }
+    if (!response.ok) {
+      throw new Error(`Failed to reveal hint: ${response.statusText}`);
+    }
+    return await response.json();
+  }
+
+  // "Was this helpful?" for a revealed hint level
+  async sendHintFeedback(exerciseId, level, helpful) {
+    const response = await apiFetch(`/api/exercises/by-id/${encodeURIComponent(exerciseId)}/hints/${level}/feedback`, {
+      method: 'POST',
+      headers: {
+        'Content-Type': 'application/json',
+      },
+      body: JSON.stringify({ helpful }),
+    });
+    if (!response.ok) {
+      throw new Error(`Failed to send hint feedback: ${response.statusText}`);
+    }
+  }
+
   async saveCode(path, code) {
     try {
       const [chapter, exercise] = path.split('/');
-      const response = await fetch(`/api/exercises/${chapter}/${exercise}/code`, {
+      const response = await apiFetch(`/api/exercises/${chapter}/${exercise}/code`, {
         method: 'PUT',
         headers: {
           'Content-Type': 'application/json',
@@ -64,10 +172,14 @@ export class ExerciseManager {
     }
   }
 
-  async runExercise(path) {
+  async runExercise(path, onEvent) {
+    if (this.canStreamJobs()) {
+      return this.runJob(path, 'run', onEvent);
+    }
+
     try {
       const [chapter, exercise] = path.split('/');
-      const response = await fetch(`/api/exercises/${chapter}/${exercise}/run`, {
+      const response = await apiFetch(`/api/exercises/${chapter}/${exercise}/run`, {
         method: 'POST',
       });
 
@@ -82,10 +194,14 @@ export class ExerciseManager {
     }
   }
 
-  async testExercise(path) {
+  async testExercise(path, onEvent) {
+    if (this.canStreamJobs()) {
+      return this.runJob(path, 'test', onEvent);
+    }
+
     try {
       const [chapter, exercise] = path.split('/');
-      const response = await fetch(`/api/exercises/${chapter}/${exercise}/test`, {
+      const response = await apiFetch(`/api/exercises/${chapter}/${exercise}/test`, {
         method: 'POST',
       });
 
@@ -100,10 +216,14 @@ export class ExerciseManager {
     }
   }
 
-  async checkExercise(path) {
+  async checkExercise(path, onEvent) {
+    if (this.canStreamJobs()) {
+      return this.runJob(path, 'check', onEvent);
+    }
+
     try {
       const [chapter, exercise] = path.split('/');
-      const response = await fetch(`/api/exercises/${chapter}/${exercise}/check`, {
+      const response = await apiFetch(`/api/exercises/${chapter}/${exercise}/check`, {
         method: 'POST',
       });
 
@@ -1,12 +1,18 @@
+import { apiFetch } from './auth.js';
+
 export class ProgressTracker {
-  constructor() {
+  constructor(websocket) {
     this.progress = null;
     this.sessionStartTime = Date.now();
     this.currentExerciseStartTime = null;
+
+    // The server awards achievements and announces them to every open tab
+    websocket?.addMessageHandler('achievement_unlocked', (data) => this.unlockAchievement(data.achievement));
   }
 
   async init(exercises = null) {
     await this.loadProgress();
+    await this.syncTimezone();
     
     // Set total exercises based on actual loaded exercises
     if (exercises && Array.isArray(exercises)) {
@@ -16,6 +22,39 @@ export class ProgressTracker {
     console.log('Progress tracker initialized');
   }
   
+  // Streaks are counted in local days, so tell the server where the learner is
+  async syncTimezone() {
+    const timezone = Intl.DateTimeFormat().resolvedOptions().timeZone;
+    if (!timezone || !this.progress?.preferences || this.progress.preferences.timezone === timezone) return;
+
+    try {
+      const response = await apiFetch('/api/progress/timezone', {
+        method: 'PUT',
+        headers: {
+          'Content-Type': 'application/json'
+        },
+        body: JSON.stringify({ timezone })
+      });
+      if (response.ok) {
+        await this.loadProgress();
+      }
+    } catch (error) {
+      console.error('Failed to set timezone:', error);
+    }
+  }
+
+  // Activity per local day with current and longest streaks, for a heatmap
+  async getActivityCalendar(from = null, to = null) {
+    const params = new URLSearchParams();
+    if (from) params.set('from', from);
+    if (to) params.set('to', to);
+    const response = await apiFetch(`/api/progress/activity?${params}`);
+    if (!response.ok) {
+      throw new Error(`Failed to load activity: ${response.statusText}`);
+    }
+    return await response.json();
+  }
+
   setTotalExercises(count) {
     if (this.progress) {
       this.progress.total_exercises = count;
@@ -25,7 +64,7 @@ export class ProgressTracker {
 
   async loadProgress() {
     try {
-      const response = await fetch('/api/progress');
+      const response = await apiFetch('/api/progress');
       if (response.ok) {
         this.progress = await response.json();
       } else {
@@ -71,7 +110,7 @@ export class ProgressTracker {
     this.currentExerciseStartTime = Date.now();
     
     try {
-      const response = await fetch('/api/progress/view', {
+      const response = await apiFetch('/api/progress/view', {
         method: 'POST',
         headers: {
           'Content-Type': 'application/json'
@@ -108,36 +147,6 @@ export class ProgressTracker {
     }
   }
 
-  async trackHintUsed(exerciseId, level) {
-    if (!this.progress) {
-      await this.loadProgress();
-    }
-    
-    try {
-      const response = await fetch('/api/progress/hint', {
-        method: 'POST',
-        headers: {
-          'Content-Type': 'application/json'
-        },
-        body: JSON.stringify({
-          exercise_id: exerciseId,
-          hint_level: level
-        })
-      });
-      
-      if (response.ok) {
-        const result = await response.json();
-        this.progress = result.progress;
-        this.updateProgressDisplay();
-        console.log(`Hint tracked: ${exerciseId}, level ${level}`);
-      } else {
-        console.error('Failed to track hint usage');
-      }
-    } catch (error) {
-      console.error('Error tracking hint usage:', error);
-    }
-  }
-
   async completeExercise(exerciseId, timeSpentMinutes) {
     if (!this.progress) {
       await this.loadProgress();
@@ -173,7 +182,7 @@ export class ProgressTracker {
       });
 
       // Update backend
-      const response = await fetch('/api/progress/complete', {
+      const response = await apiFetch('/api/progress/complete', {
         method: 'POST',
         headers: {
           'Content-Type': 'application/json'
@@ -188,9 +197,6 @@ export class ProgressTracker {
         console.error('Failed to update progress on backend');
       }
 
-      // Check for achievements
-      this.checkForAchievements();
-      
       // Update UI
       this.updateProgressDisplay();
       
@@ -199,61 +205,14 @@ export class ProgressTracker {
     }
   }
 
-  checkForAchievements() {
-    if (!this.progress) return;
-    
-    // Ensure achievements array exists
-    if (!this.progress.achievements) {
-      this.progress.achievements = [];
-    }
-    
-    const achievements = this.progress.achievements;
-    const completed = this.progress.exercises_completed;
-    
-    // First exercise achievement
-    if (completed === 1 && !achievements.find(a => a.id === 'first_exercise')) {
-      this.unlockAchievement({
-        id: 'first_exercise',
-        title: 'Hello, Rust! 🦀',
-        description: 'Completed your first exercise',
-        icon: '🎉',
-        points: 10,
-        unlocked_at: new Date().toISOString()
-      });
-    }
-
-    // Chapter completion achievements
-    if (!this.progress.chapters) {
-      this.progress.chapters = {};
-    }
-    const chaptersCompleted = Object.keys(this.progress.chapters).length;
-    if (chaptersCompleted === 1 && !achievements.find(a => a.id === 'first_chapter')) {
-      this.unlockAchievement({
-        id: 'first_chapter',
-        title: 'Chapter Master',
-        description: 'Completed your first chapter',
-        icon: '<i class="fas fa-graduation-cap"></i>',
-        points: 25,
-        unlocked_at: new Date().toISOString()
-      });
-    }
-
-    // Session achievements
-    if (this.progress.session_stats.exercises_completed === 3 && 
-        !achievements.find(a => a.id === 'session_streak')) {
-      this.unlockAchievement({
-        id: 'session_streak',
-        title: 'Momentum Builder',
-        description: 'Completed 3 exercises in one session',
-        icon: '🔥',
-        points: 15,
-        unlocked_at: new Date().toISOString()
-      });
-    }
-  }
-
   unlockAchievement(achievement) {
-    this.progress.achievements.push(achievement);
+    if (this.progress) {
+      if (!this.progress.achievements) {
+        this.progress.achievements = [];
+      }
+      if (this.progress.achievements.some(a => a.id === achievement.id)) return;
+      this.progress.achievements.push(achievement);
+    }
     
     // Show achievement notification
     this.showAchievementNotification(achievement);
@@ -377,7 +336,7 @@ export class ProgressTracker {
     if (!this.progress) return [];
     
     return this.progress.achievements
-      .sort((a, b) => new Date(b.unlocked_at) - new Date(a.unlocked_at))
+      .sort((a, b) => new Date(b.earned_at) - new Date(a.earned_at))
       .slice(0, limit);
   }
 
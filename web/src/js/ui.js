@@ -178,6 +178,7 @@ export class UI {
             </div>
             <div class="panel-section" id="hints-section">
               <div class="hints-section">
+                <div class="contextual-hints" id="contextual-hints"></div>
                 <div class="hint-level">
                   <button class="hint-button" data-level="1">
                     <i class="fas fa-lightbulb"></i> Level 1: Conceptual Hint
@@ -622,6 +623,21 @@ export class UI {
     this.scrollOutputToBottom();
   }
 
+  clearOutputTab(tab) {
+    document.getElementById(`${tab}-panel`).innerHTML = '';
+    this.switchOutputTab(tab);
+  }
+
+  appendOutputLine(tab, line, stream = 'stdout') {
+    const panel = document.getElementById(`${tab}-panel`);
+    const div = document.createElement('div');
+    div.className = stream === 'stderr' ? 'output-stderr' : 'output-stdout';
+    div.textContent = line;
+    panel.appendChild(div);
+
+    this.scrollOutputToBottom();
+  }
+
   updateTestResults(content) {
     const testsPanel = document.getElementById('tests-panel');
     testsPanel.innerHTML = `<div class="output-stdout">${this.escapeHtml(content)}</div>`;
@@ -645,6 +661,37 @@ export class UI {
     }
   }
 
+  showDiagnostics(diagnostics = []) {
+    const model = this.editor.getModel();
+    if (!model) return;
+
+    const severities = {
+      error: monaco.MarkerSeverity.Error,
+      warning: monaco.MarkerSeverity.Warning,
+      note: monaco.MarkerSeverity.Info,
+      help: monaco.MarkerSeverity.Hint
+    };
+
+    // The editor shows the primary source file, so only underline spans in src/
+    const markers = diagnostics.flatMap(diagnostic =>
+      diagnostic.spans
+        .filter(span => span.file_name.startsWith('src/'))
+        .map(span => ({
+          severity: span.is_primary
+            ? (severities[diagnostic.level] || monaco.MarkerSeverity.Info)
+            : monaco.MarkerSeverity.Hint,
+          message: span.label ? `${diagnostic.message}: ${span.label}` : diagnostic.message,
+          code: diagnostic.code ? diagnostic.code.code : undefined,
+          startLineNumber: span.line_start,
+          startColumn: span.column_start,
+          endLineNumber: span.line_end,
+          endColumn: span.column_end
+        }))
+    );
+
+    monaco.editor.setModelMarkers(model, 'cargo', markers);
+  }
+
   setExecutionStatus(status, message) {
     const statusMessage = document.getElementById('status-message');
     const statusIcon = statusMessage.querySelector('.status-icon');
@@ -665,7 +712,21 @@ export class UI {
     } else {
       // Open the hint
       hintButton.classList.add('used');
-      hintContent.innerHTML = this.formatHintContent(hint.content);
+      hintContent.innerHTML = this.formatHintContent(hint.content) + `
+        <div class="hint-feedback">
+          Was this helpful?
+          <button class="hint-feedback-button" data-helpful="true">👍</button>
+          <button class="hint-feedback-button" data-helpful="false">👎</button>
+        </div>`;
+      hintContent.querySelectorAll('.hint-feedback-button').forEach(button => {
+        button.addEventListener('click', () => {
+          const helpful = button.dataset.helpful === 'true';
+          hintContent.querySelector('.hint-feedback').textContent = 'Thanks for the feedback!';
+          document.dispatchEvent(new CustomEvent('hint-feedback', {
+            detail: { level, helpful }
+          }));
+        });
+      });
       hintContent.classList.add('visible');
       
       // Enable next hint button
@@ -678,6 +739,30 @@ export class UI {
     }
   }
 
+  // Hints the server matched to the errors, lints and panics of the last result
+  showContextualHints(hints = []) {
+    const container = document.getElementById('contextual-hints');
+    container.innerHTML = hints.map(hint => {
+      const trigger = hint.location ? `${hint.trigger} at ${hint.location}` : hint.trigger;
+      const links = hint.rust_book_links
+        .map(url => `<a class="book-link" href="${url}" target="_blank" rel="noopener">📖 ${this.escapeHtml(url)}</a>`)
+        .join('');
+      return `
+        <div class="contextual-hint">
+          <div class="contextual-hint-title">
+            ${this.escapeHtml(hint.title)}
+            <span class="contextual-hint-trigger">${this.escapeHtml(trigger)}</span>
+          </div>
+          <div class="hint-content visible">${this.formatHintContent(this.escapeHtml(hint.content))}</div>
+          ${links}
+        </div>`;
+    }).join('');
+
+    if (hints.length > 0) {
+      this.switchPanelTab('hints');
+    }
+  }
+
   formatHintContent(content) {
     // Convert markdown-like content to HTML
     return content
@@ -697,6 +782,7 @@ export class UI {
       content.classList.remove('visible');
       content.innerHTML = '';
     });
+    document.getElementById('contextual-hints').innerHTML = '';
   }
 
   clearOutputPanels() {
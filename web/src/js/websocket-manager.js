@@ -1,3 +1,5 @@
+import { getToken } from './auth.js';
+
 export class WebSocketManager {
   constructor() {
     this.ws = null;
@@ -7,6 +9,7 @@ export class WebSocketManager {
     this.messageHandlers = [];
     this.typedMessageHandlers = new Map();
     this.isConnecting = false;
+    this.connectionId = null; // Assigned by the server, used to scope jobs
     this.debug = localStorage.getItem('DEBUG_WEBSOCKET') === 'true' || window.location.search.includes('debug=true');
   }
 
@@ -40,6 +43,11 @@ export class WebSocketManager {
         wsUrl = `${protocol}//${window.location.hostname}:3000/ws`;
       }
       
+      const token = getToken();
+      if (token) {
+        wsUrl += `?token=${encodeURIComponent(token)}`;
+      }
+      
       if (this.debug) {
         console.log(`Connecting to WebSocket: ${wsUrl}`);
       }
@@ -66,6 +74,7 @@ export class WebSocketManager {
       this.ws.onclose = (event) => {
         console.log('❌ WebSocket disconnected:', event.code, event.reason);
         this.isConnecting = false;
+        this.connectionId = null;
         this.notifyConnectionStatus(false);
         
         // Attempt to reconnect unless it was a clean close
@@ -125,6 +134,9 @@ export class WebSocketManager {
 
     // Handle built-in message types
     switch (data.type) {
+      case 'connected':
+        this.connectionId = data.connectionId;
+        break;
       case 'file_updated':
         this.handleFileUpdated(data);
         break;
@@ -5,13 +5,17 @@ import { BookIntegration } from './js/book-integration.js';
 import { WebSocketManager } from './js/websocket-manager.js';
 import { UI } from './js/ui.js';
 import { TerminalManager } from './js/terminal.js';
+import { ensureSignedIn } from './js/auth.js';
+
+// Hint levels as the server names them, level 1 first
+const HINT_LEVELS = ['Conceptual', 'Strategic', 'Implementation'];
 
 class RustTour {
   constructor() {
-    this.exerciseManager = new ExerciseManager();
-    this.progressTracker = new ProgressTracker();
-    this.bookIntegration = new BookIntegration();
     this.websocket = new WebSocketManager();
+    this.exerciseManager = new ExerciseManager(this.websocket);
+    this.progressTracker = new ProgressTracker(this.websocket);
+    this.bookIntegration = new BookIntegration();
     this.ui = new UI();
     this.terminal = new TerminalManager(this.websocket);
     
@@ -21,6 +25,9 @@ class RustTour {
 
   async init() {
     try {
+      // Sign in first so progress and code load from the learner's own workspace
+      this.session = await ensureSignedIn();
+      
       // Initialize components
       await this.exerciseManager.init();
       
@@ -97,6 +104,10 @@ class RustTour {
       this.showHint(e.detail.level);
     });
 
+    document.addEventListener('hint-feedback', (e) => {
+      this.sendHintFeedback(e.detail.level, e.detail.helpful);
+    });
+
     // Progress
     document.addEventListener('complete-exercise', () => {
       this.completeExercise();
@@ -125,6 +136,8 @@ class RustTour {
       if (e.ctrlKey && e.key === '~') {
         e.preventDefault();
         this.toggleTerminal();
+      } else if (e.key === 'Escape' && this.exerciseManager.activeJobId) {
+        this.exerciseManager.cancelJob();
       }
     });
   }
@@ -161,7 +174,10 @@ class RustTour {
     try {
       this.ui.setExecutionStatus('running', 'Running code...');
       
-      const result = await this.exerciseManager.runExercise(this.currentExercise.path);
+      const result = await this.exerciseManager.runExercise(
+        this.currentExercise.path,
+        this.streamJobOutput('output', 'Running code...')
+      );
       
       if (result.success) {
         this.ui.setExecutionStatus('success', 'Code executed successfully!');
@@ -170,6 +186,7 @@ class RustTour {
         this.ui.setExecutionStatus('error', 'Execution failed');
         this.ui.updateOutput(result.stderr, 'stderr');
       }
+      this.ui.showContextualHints(result.hints);
     } catch (error) {
       console.error('Failed to run code:', error);
       this.ui.setExecutionStatus('error', 'Failed to run code');
@@ -183,7 +200,10 @@ class RustTour {
     try {
       this.ui.setExecutionStatus('running', 'Running tests...');
       
-      const result = await this.exerciseManager.testExercise(this.currentExercise.path);
+      const result = await this.exerciseManager.testExercise(
+        this.currentExercise.path,
+        this.streamJobOutput('tests', 'Running tests...')
+      );
       
       if (result.success) {
         this.ui.setExecutionStatus('success', 'All tests passed! 🎉');
@@ -198,6 +218,7 @@ class RustTour {
         this.ui.setExecutionStatus('warning', 'Some tests failed');
         this.ui.updateTestResults(result.output);
       }
+      this.ui.showContextualHints(result.hints);
     } catch (error) {
       console.error('Failed to run tests:', error);
       this.ui.setExecutionStatus('error', 'Failed to run tests');
@@ -211,7 +232,10 @@ class RustTour {
     try {
       this.ui.setExecutionStatus('running', 'Checking code quality...');
       
-      const result = await this.exerciseManager.checkExercise(this.currentExercise.path);
+      const result = await this.exerciseManager.checkExercise(
+        this.currentExercise.path,
+        this.streamJobOutput('clippy', 'Checking code quality...')
+      );
       
       if (result.success) {
         this.ui.setExecutionStatus('success', 'Code quality looks great!');
@@ -220,6 +244,8 @@ class RustTour {
       }
       
       this.ui.updateClippyResults(result.stderr);
+      this.ui.showDiagnostics(result.diagnostics);
+      this.ui.showContextualHints(result.hints);
     } catch (error) {
       console.error('Failed to check code:', error);
       this.ui.setExecutionStatus('error', 'Failed to check code');
@@ -227,6 +253,30 @@ class RustTour {
     }
   }
 
+  // Live view of a job's output until its final result replaces it
+  streamJobOutput(tab, statusMessage) {
+    this.ui.clearOutputTab(tab);
+
+    return (event) => {
+      switch (event.event) {
+        case 'output':
+          this.ui.appendOutputLine(tab, event.line, event.stream);
+          break;
+        case 'diagnostic':
+          (event.diagnostic.rendered || event.diagnostic.message)
+            .trimEnd()
+            .split('\n')
+            .forEach(line => this.ui.appendOutputLine(tab, line, 'stderr'));
+          break;
+        case 'progress':
+          if (!event.fresh) {
+            this.ui.setExecutionStatus('running', `${statusMessage} (compiled ${event.crate})`);
+          }
+          break;
+      }
+    };
+  }
+
   async saveCode(silent = false) {
     if (!this.currentExercise) return;
 
@@ -245,32 +295,45 @@ class RustTour {
     }
   }
 
-  showHint(level) {
+  async showHint(level) {
     if (!this.currentExercise) return;
     
-    const hints = this.parseHints(this.currentExercise.hints);
-    const hint = hints[level - 1];
+    const hints = this.currentExercise.hints;
+    const levelOf = (hint) => HINT_LEVELS.indexOf(hint.level) + 1;
+    const revealed = hints.find(hint => levelOf(hint) === level);
+    if (revealed) {
+      this.ui.showHint(revealed, level);
+      return;
+    }
     
-    if (hint) {
-      this.ui.showHint(hint, level);
-      this.progressTracker.trackHintUsed(this.currentExercise.metadata.id, level);
-    } else {
-      this.ui.showError('No hint available for this level.');
+    try {
+      const reveal = await this.exerciseManager.revealNextHint(this.currentExercise.metadata.id);
+      if (reveal.status === 'revealed') {
+        hints.push(reveal.hint);
+        this.ui.showHint(reveal.hint, levelOf(reveal.hint));
+        await this.progressTracker.loadProgress();
+      } else if (reveal.status === 'locked') {
+        const runs = reveal.failures_needed === 1 ? 'test run' : 'test runs';
+        this.ui.showError(`Keep trying: the next hint unlocks after ${reveal.failures_needed} more failed ${runs}.`);
+      } else if (reveal.status === 'disabled') {
+        this.ui.showError('Hints are turned off in your preferences.');
+      } else {
+        this.ui.showError('No hint available for this level.');
+      }
+    } catch (error) {
+      console.error('Failed to reveal hint:', error);
+      this.ui.showError('Failed to load hint. Please try again.');
     }
   }
 
-  parseHints(hintsMarkdown) {
-    const hints = [];
-    const sections = hintsMarkdown.split(/## Level \d+:/);
-    
-    for (let i = 1; i < sections.length; i++) {
-      hints.push({
-        title: sections[i].split('\n')[0].trim(),
-        content: sections[i].substring(sections[i].indexOf('\n') + 1).trim()
-      });
+  async sendHintFeedback(level, helpful) {
+    if (!this.currentExercise) return;
+
+    try {
+      await this.exerciseManager.sendHintFeedback(this.currentExercise.metadata.id, level, helpful);
+    } catch (error) {
+      console.error('Failed to send hint feedback:', error);
     }
-    
-    return hints;
   }
 
   async completeExercise() {
@@ -309,7 +372,7 @@ class RustTour {
   handleWebSocketMessage(data) {
     switch (data.type) {
       case 'file_updated':
-        if (data.exercise === this.currentExercise?.path) {
+        if (data.path === this.currentExercise?.path) {
           // this.ui.showNotification('File updated externally');
         }
         break;